@@ -55,41 +55,8 @@ fn main() -> std::io::Result<()> {
         }
     }
 
-    // Create assets directory structure
-    println!("Creating assets directory...");
-    fs::create_dir_all(release_dir.join("assets/fonts"))?;
-
-    // Copy font assets
-    println!("Copying font assets...");
-    let font_paths = [
-        "src/assets/fonts/times.ttf",
-        "assets/fonts/times.ttf",
-        "times.ttf"
-    ];
-
-    let mut font_copied = false;
-    for font_path in font_paths.iter() {
-        if Path::new(font_path).exists() {
-            match fs::copy(font_path, release_dir.join("assets/fonts/times.ttf")) {
-                Ok(_) => {
-                    println!("Successfully copied font from {}", font_path);
-                    font_copied = true;
-                    break;
-                },
-                Err(e) => println!("Failed to copy font from {}: {}", font_path, e),
-            }
-        } else {
-            println!("Font path not found: {}", font_path);
-        }
-    }
-
-    if !font_copied {
-        println!("WARNING: Could not copy font file from any location!");
-        println!("Please ensure times.ttf exists in one of these locations:");
-        for path in font_paths.iter() {
-            println!("  - {}", path);
-        }
-    }
+    // The default font is compiled into the executable, so no assets need copying
+    println!("Font is embedded in the executable, skipping assets");
 
     println!("\nVerifying files in release directory:");
     for entry in fs::read_dir(release_dir)? {
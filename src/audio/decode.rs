@@ -0,0 +1,67 @@
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::structs::channel_layout::ChannelLayout;
+use crate::structs::sample_wrapper::SampleWrapper;
+
+// Decodes a whole file as fast as possible into interleaved samples at the
+// file's own sample rate, for offline rendering
+pub fn decode_file(path: &Path) -> Result<SampleWrapper, Box<dyn std::error::Error>> {
+    let file = Box::new(File::open(path)?);
+    let mss = MediaSourceStream::new(file, Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        mss,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+    let mut format = probed.format;
+
+    let track = format.default_track().ok_or("the file has no audio track")?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate.ok_or("the audio track has no sample rate")?;
+    let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut samples = Vec::new();
+    let mut layout = None;
+    let mut sample_buf = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet is skipped, as players do
+            Err(SymphoniaError::DecodeError(e)) => {
+                eprintln!("Skipping undecodable packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if layout.is_none() {
+            layout = Some(ChannelLayout::from_channels(decoded.spec().channels));
+        }
+        let buf = sample_buf.get_or_insert_with(|| SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec()));
+        buf.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buf.samples());
+    }
+
+    let layout = layout.ok_or("the file contains no audio")?;
+    Ok(SampleWrapper { samples, layout, sample_rate })
+}
@@ -0,0 +1,32 @@
+use cpal::traits::{DeviceTrait, HostTrait};
+
+// Names of all output devices, with the system default marked
+pub fn output_device_names() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let host = cpal::default_host();
+    let default_name = host.default_output_device().and_then(|device| device.name().ok());
+    let mut names = Vec::new();
+    for device in host.output_devices()? {
+        let name = device.name()?;
+        if Some(&name) == default_name.as_ref() {
+            names.push(format!("{} (default)", name));
+        } else {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+// Looks up an output device by name, falling back to the system default
+pub fn find_output_device(host: &cpal::Host, device_name: Option<&str>) -> Option<cpal::Device> {
+    if let Some(name) = device_name {
+        let found = host
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|device| device.name().ok().as_deref() == Some(name)));
+        if found.is_some() {
+            return found;
+        }
+        eprintln!("Output device '{}' not found, using the default device", name);
+    }
+    host.default_output_device()
+}
@@ -0,0 +1,264 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::audio::decode::decode_file;
+use crate::structs::audio_analyzer::AudioAnalyzer;
+use crate::structs::features::{AnalysisFrame, FeatureExtractor};
+use crate::structs::tempo_estimator::TempoEstimator;
+use crate::structs::waveform_buffer::WaveformBuffer;
+use crate::structs::sample_wrapper::SampleWrapper;
+
+// Columns before the band magnitudes, in output order
+const FIXED_COLUMNS: [&str; 10] = [
+    "time", "rms", "peak", "centroid", "spread", "rolloff", "flatness", "zcr", "onset", "bpm",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeatureFormat {
+    Csv,
+    JsonLines,
+    Npy,
+}
+
+impl FeatureFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        match extension.as_deref() {
+            Some("csv") => Ok(FeatureFormat::Csv),
+            Some("jsonl") | Some("ndjson") => Ok(FeatureFormat::JsonLines),
+            Some("npy") => Ok(FeatureFormat::Npy),
+            _ => Err(format!(
+                "cannot tell the format of {}, use a .csv, .jsonl or .npy file",
+                path.display()
+            )),
+        }
+    }
+}
+
+// Everything measured for one analysis frame
+struct FeatureRow {
+    // Seconds from the start of the track to the end of the frame
+    time: f32,
+    features: AnalysisFrame,
+    // NaN until the tempo can be estimated
+    bpm: f32,
+    bands: Vec<f32>,
+}
+
+impl FeatureRow {
+    fn values(&self) -> impl Iterator<Item = f32> + '_ {
+        let features = &self.features;
+        [
+            self.time,
+            features.rms,
+            features.peak,
+            features.centroid,
+            features.spread,
+            features.rolloff,
+            features.flatness,
+            features.zero_crossing_rate,
+            features.flux,
+            self.bpm,
+        ]
+        .into_iter()
+        .chain(self.bands.iter().copied())
+    }
+}
+
+// Decodes `input` and writes per-frame features to `output`. Frames overlap
+// by half the FFT size, and band magnitudes are grouped the same way as the
+// bars on screen.
+pub fn export_features(
+    input: &Path,
+    output: &Path,
+    fft_size: usize,
+    band_count: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let format = FeatureFormat::from_path(output)?;
+    println!("Decoding {}...", input.display());
+    let track = decode_file(input)?;
+    let rows = analyse_track(&track, fft_size, band_count);
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    match format {
+        FeatureFormat::Csv => write_csv(&mut writer, &rows, band_count)?,
+        FeatureFormat::JsonLines => write_json_lines(&mut writer, &rows)?,
+        FeatureFormat::Npy => write_npy(&mut writer, &rows, band_count)?,
+    }
+    writer.flush()?;
+    println!("Wrote {} frames to {}", rows.len(), output.display());
+    if format == FeatureFormat::Npy {
+        println!("Columns: {}, then band_0 to band_{}", FIXED_COLUMNS.join(", "), band_count.saturating_sub(1));
+    }
+    Ok(())
+}
+
+fn analyse_track(track: &SampleWrapper, fft_size: usize, band_count: usize) -> Vec<FeatureRow> {
+    let hop = fft_size / 2;
+    let channels = track.channels();
+    let total_frames = track.samples.len() / channels;
+    let mut analyzer = AudioAnalyzer::new(fft_size);
+    let mut waveform = WaveformBuffer::new(fft_size);
+    let mut extractor = FeatureExtractor::new();
+    let mut tempo = TempoEstimator::new(track.sample_rate as f32 / hop as f32);
+
+    let mut rows = Vec::with_capacity(total_frames / hop + 1);
+    let mut start = 0;
+    while start < total_frames {
+        let end = (start + hop).min(total_frames);
+        waveform.push(&SampleWrapper {
+            samples: track.samples[start * channels..end * channels].to_vec(),
+            layout: track.layout,
+            sample_rate: track.sample_rate,
+        });
+        start = end;
+
+        let frame = waveform.latest_mono(fft_size);
+        let spectrum = analyzer.process(&frame);
+        let features = extractor.process(&frame, &spectrum, track.sample_rate);
+        tempo.push(features.flux);
+
+        rows.push(FeatureRow {
+            time: end as f32 / track.sample_rate as f32,
+            features,
+            bpm: tempo.estimate().unwrap_or(f32::NAN),
+            bands: band_magnitudes(&spectrum, band_count),
+        });
+    }
+    rows
+}
+
+// Peak magnitude in each of `band_count` equal slices of the spectrum
+fn band_magnitudes(spectrum: &[f32], band_count: usize) -> Vec<f32> {
+    (0..band_count)
+        .map(|band| {
+            let start = band * spectrum.len() / band_count;
+            let end = ((band + 1) * spectrum.len() / band_count).max(start + 1).min(spectrum.len());
+            spectrum[start.min(end)..end].iter().fold(0.0, |max, &x| f32::max(max, x))
+        })
+        .collect()
+}
+
+fn write_csv(writer: &mut impl Write, rows: &[FeatureRow], band_count: usize) -> std::io::Result<()> {
+    let header: Vec<String> = FIXED_COLUMNS
+        .iter()
+        .map(|name| name.to_string())
+        .chain((0..band_count).map(|band| format!("band_{}", band)))
+        .collect();
+    writeln!(writer, "{}", header.join(","))?;
+    for row in rows {
+        // An unknown tempo is left blank
+        let fields: Vec<String> = row
+            .values()
+            .map(|value| if value.is_nan() { String::new() } else { value.to_string() })
+            .collect();
+        writeln!(writer, "{}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+fn write_json_lines(writer: &mut impl Write, rows: &[FeatureRow]) -> std::io::Result<()> {
+    for row in rows {
+        // Every column but the bands becomes a field, with an unknown tempo as null
+        let mut fields: Vec<String> = FIXED_COLUMNS
+            .iter()
+            .zip(row.values())
+            .map(|(name, value)| {
+                let value = if value.is_nan() { "null".to_string() } else { value.to_string() };
+                format!("\"{}\":{}", name, value)
+            })
+            .collect();
+        let bands: Vec<String> = row.bands.iter().map(|band| band.to_string()).collect();
+        fields.push(format!("\"bands\":[{}]", bands.join(",")));
+        writeln!(writer, "{{{}}}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+// A 2D little-endian float32 array, one row per frame, in NumPy's .npy
+// version 1.0 format
+fn write_npy(writer: &mut impl Write, rows: &[FeatureRow], band_count: usize) -> std::io::Result<()> {
+    let columns = FIXED_COLUMNS.len() + band_count;
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+        rows.len(),
+        columns
+    );
+    // The magic, version and length take 10 bytes, and the data must start on
+    // a 64-byte boundary, with the header ending in a newline
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for row in rows {
+        for value in row.values() {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<FeatureRow> {
+        let features = AnalysisFrame { rms: 0.5, peak: 1.0, centroid: 440.0, ..AnalysisFrame::default() };
+        vec![
+            FeatureRow { time: 0.25, features, bpm: f32::NAN, bands: vec![0.1, 0.2] },
+            FeatureRow { time: 0.5, features, bpm: 120.0, bands: vec![0.3, 0.4] },
+        ]
+    }
+
+    #[test]
+    fn npy_header_is_aligned() {
+        let mut output = Vec::new();
+        write_npy(&mut output, &rows(), 2).unwrap();
+
+        assert_eq!(&output[..8], b"\x93NUMPY\x01\x00");
+        let header_length = u16::from_le_bytes([output[8], output[9]]) as usize;
+        assert_eq!((10 + header_length) % 64, 0);
+        let header = std::str::from_utf8(&output[10..10 + header_length]).unwrap();
+        assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (2, 12), }"), "{}", header);
+        assert!(header.ends_with('\n'));
+
+        let data = &output[10 + header_length..];
+        assert_eq!(data.len(), 2 * 12 * 4);
+        let value = |index: usize| f32::from_le_bytes(data[index * 4..index * 4 + 4].try_into().unwrap());
+        assert_eq!(value(0), 0.25);
+        assert!(value(9).is_nan());
+        assert_eq!(value(12 + 9), 120.0);
+        assert_eq!(value(23), 0.4);
+    }
+
+    #[test]
+    fn unknown_tempo_is_blank_in_csv() {
+        let mut output = Vec::new();
+        write_csv(&mut output, &rows(), 2).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "time,rms,peak,centroid,spread,rolloff,flatness,zcr,onset,bpm,band_0,band_1");
+        assert_eq!(lines[1], "0.25,0.5,1,440,0,0,0,0,0,,0.1,0.2");
+        assert_eq!(lines[2], "0.5,0.5,1,440,0,0,0,0,0,120,0.3,0.4");
+    }
+
+    #[test]
+    fn unknown_tempo_is_null_in_json() {
+        let mut output = Vec::new();
+        write_json_lines(&mut output, &rows()).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"time\":0.25,\"rms\":0.5,"), "{}", lines[0]);
+        assert!(lines[0].ends_with(",\"bpm\":null,\"bands\":[0.1,0.2]}"), "{}", lines[0]);
+        assert!(lines[1].ends_with(",\"bpm\":120,\"bands\":[0.3,0.4]}"), "{}", lines[1]);
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::audio::playback::start_audio_playback;
+use crate::structs::audio_analyzer::AudioAnalyzer;
+use crate::structs::audio_playback_state::AudioPlaybackState;
+use crate::structs::beat_detector::BeatDetector;
+use crate::structs::playlist::Playlist;
+use crate::structs::sample_wrapper::SampleWrapper;
+use crate::structs::waveform_buffer::WaveformBuffer;
+
+// Plays every track without a window, printing a status line each second.
+// Returns an error if any track failed to play.
+pub fn run_headless(
+    mut playlist: Playlist,
+    device: Option<String>,
+    volume: f32,
+    fft_size: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if playlist.current().is_none() {
+        return Err("--headless needs at least one file, directory or playlist".into());
+    }
+
+    let mut failed = 0;
+    let mut track = playlist.current().cloned();
+    while let Some(path) = track {
+        println!("Now playing: {}", path.display());
+        let (tx, rx) = mpsc::channel::<SampleWrapper>();
+        let playback_state = Arc::new(Mutex::new(AudioPlaybackState::new()));
+        {
+            let mut state = playback_state.lock().unwrap();
+            state.set_volume(volume);
+            state.is_playing = true;
+        }
+        let handle = start_audio_playback(path.clone(), tx, playback_state.clone(), device.clone());
+
+        let mut analyzer = AudioAnalyzer::new(fft_size);
+        let mut waveform = WaveformBuffer::new(fft_size);
+        let mut beat_detector = BeatDetector::new();
+        let mut last_status = Instant::now();
+        while !handle.is_finished() {
+            while let Ok(sample_wrapper) = rx.try_recv() {
+                waveform.push(&sample_wrapper);
+                let spectrum = analyzer.process(&waveform.latest_mono(fft_size));
+                beat_detector.process(&spectrum, sample_wrapper.sample_rate);
+            }
+            if last_status.elapsed() >= Duration::from_secs(1) {
+                let state = playback_state.lock().unwrap();
+                let (position, duration) = (state.get_song_position(), state.get_song_duration());
+                println!(
+                    "{:02}:{:02} / {:02}:{:02}  beats: {}",
+                    position / 60,
+                    position % 60,
+                    duration / 60,
+                    duration % 60,
+                    beat_detector.beat_count
+                );
+                last_status = Instant::now();
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        if handle.join().is_err() {
+            eprintln!("Could not play {}", path.display());
+            failed += 1;
+        }
+        track = playlist.advance().cloned();
+    }
+
+    if failed > 0 {
+        return Err(format!("{} track(s) could not be played", failed).into());
+    }
+    Ok(())
+}
@@ -1 +1,5 @@
 pub mod playback;
+pub mod devices;
+pub mod headless;
+pub mod decode;
+pub mod feature_export;
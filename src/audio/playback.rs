@@ -1,4 +1,4 @@
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::traits::{DeviceTrait, StreamTrait};
 use ringbuf::HeapRb;
 use std::fs::File;
 use std::path::PathBuf;
@@ -7,18 +7,22 @@ use std::thread;
 use std::time::Duration;
 use symphonia::core::audio::SampleBuffer;
 use symphonia::core::codecs::DecoderOptions;
-use symphonia::core::formats::FormatOptions;
+use symphonia::core::formats::{FormatOptions, SeekMode, SeekTo};
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
 
+use crate::audio::devices::find_output_device;
 use crate::structs::audio_playback_state::AudioPlaybackState;
+use crate::structs::channel_layout::ChannelLayout;
 use crate::structs::sample_wrapper::SampleWrapper;
 
 pub fn start_audio_playback(
     path: PathBuf,
     tx: mpsc::Sender<SampleWrapper>,
     playback_state: std::sync::Arc<std::sync::Mutex<AudioPlaybackState>>,
+    device_name: Option<String>,
 ) -> std::thread::JoinHandle<()> {
     thread::spawn(move || {
         println!("Starting audio playback thread...");
@@ -28,7 +32,7 @@ pub fn start_audio_playback(
 
         // Set up audio output
         let host = cpal::default_host();
-        let device = host.default_output_device().expect("no output device");
+        let device = find_output_device(&host, device_name.as_deref()).expect("no output device");
         let config = device
             .default_output_config()
             .expect("no default output config");
@@ -83,6 +87,7 @@ pub fn start_audio_playback(
             }
         }
 
+        let track_id = track.id;
         let input_sample_rate = track.codec_params.sample_rate.unwrap() as f32;
         let sample_rate = track.codec_params.sample_rate.unwrap() as u32;
         let mut samples_played = 0;
@@ -101,8 +106,8 @@ pub fn start_audio_playback(
         println!("Starting decode loop...");
         loop {
             // Check playback state
-            {
-                let state = playback_state.lock().unwrap();
+            let (volume, seek_request) = {
+                let mut state = playback_state.lock().unwrap();
                 if state.should_stop {
                     println!("Playback stopped");
                     break;
@@ -110,12 +115,26 @@ pub fn start_audio_playback(
                 if !state.is_playing {
                     continue;
                 }
+                (state.get_volume(), state.take_seek_request())
+            };
+
+            if let Some(seconds) = seek_request {
+                let seek_to = SeekTo::Time { time: Time::from(seconds), track_id: Some(track_id) };
+                match format.seek(SeekMode::Coarse, seek_to) {
+                    Ok(_) => {
+                        decoder.reset();
+                        samples_played = seconds * sample_rate;
+                        playback_state.lock().unwrap().set_song_position(seconds);
+                    }
+                    Err(e) => eprintln!("Could not seek: {}", e),
+                }
             }
 
             match format.next_packet() {
                 Ok(packet) => {
                     let decoded = decoder.decode(&packet).expect("decode error");
                     let num_channels = decoded.spec().channels.count() as u32;
+                    let layout = ChannelLayout::from_channels(decoded.spec().channels);
                     if sample_buf.is_none() {
                         sample_buf = Some(SampleBuffer::<f32>::new(
                             decoded.capacity() as u64,
@@ -135,22 +154,29 @@ pub fn start_audio_playback(
                             state.set_song_position(position);
                         }
 
-                        // Simple linear interpolation for resampling
+                        // Simple linear interpolation for resampling, done per frame so
+                        // neighbouring channels are never blended into each other
                         resampled_buffer.clear();
-                        let mut sample_index = 0.0;
-                        while sample_index < samples.len() as f32 {
-                            let index = sample_index as usize;
-                            if index + 1 < samples.len() {
-                                let fraction = sample_index - index as f32;
-                                let sample = samples[index] * (1.0 - fraction)
-                                    + samples[index + 1] * fraction;
-                                resampled_buffer.push(sample);
+                        let channels = num_channels as usize;
+                        let frame_count = samples.len() / channels;
+                        let mut frame_index = 0.0;
+                        while frame_index < frame_count as f32 {
+                            let index = frame_index as usize;
+                            if index + 1 < frame_count {
+                                let fraction = frame_index - index as f32;
+                                for channel in 0..channels {
+                                    let current = samples[index * channels + channel];
+                                    let next = samples[(index + 1) * channels + channel];
+                                    resampled_buffer.push(current * (1.0 - fraction) + next * fraction);
+                                }
                             }
-                            sample_index += 1.0 / sample_rate_ratio;
+                            frame_index += 1.0 / sample_rate_ratio;
                         }
 
                         tx.send(SampleWrapper {
                             samples: resampled_buffer.clone(),
+                            layout,
+                            sample_rate: output_sample_rate as u32,
                         })
                         .ok();
 
@@ -158,7 +184,8 @@ pub fn start_audio_playback(
                             while producer.is_full() {
                                 thread::sleep(Duration::from_micros(100));
                             }
-                            producer.push(sample).ok();
+                            // Volume only affects what is heard, not what is analysed
+                            producer.push(sample * volume).ok();
                         }
                     }
                 }
@@ -171,4 +198,4 @@ pub fn start_audio_playback(
 
         println!("Audio playback thread ending...");
     })
-}
\ No newline at end of file
+}
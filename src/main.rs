@@ -1,11 +1,15 @@
 use rfd::FileDialog;
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
 use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
-use sdl2::rect::Point;
+use sdl2::render::BlendMode;
+use sdl2::rect::{Point, Rect};
 use sdl2::ttf::init as ttf_init;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::mpsc;
-use std::time::Instant;
+use std::time::SystemTime;
 
 mod structs;
 use structs::audio_playback_state::AudioPlaybackState;
@@ -15,150 +19,528 @@ mod ui;
 use structs::sample_wrapper::SampleWrapper;
 use ui::visualisation::draw_visualization;
 mod audio;
+use crate::audio::decode::decode_file;
+use crate::audio::devices::output_device_names;
+use crate::audio::feature_export::export_features;
+use crate::audio::headless::run_headless;
 use crate::audio::playback::start_audio_playback;
 use structs::colour_slider::ColourSlider;
 use structs::element::Element;
+use structs::audio_analyzer::AudioAnalyzer;
+use structs::config::Config;
+use structs::features::AnalysisFrame;
+use structs::chroma::ChromaAnalyzer;
+use structs::pitch_detector::PitchDetector;
+use structs::level_meter::{to_db, LevelMeters};
+use structs::config_paths::default_screenshot_dir;
+use structs::keybindings::{help_lines, Action};
+use structs::control_visibility::ControlVisibility;
+use structs::frame_timer::{FrameStats, FrameTimer};
+use structs::widget::{clear_focus, dispatch, focus_next, Widget, WidgetEvent, WidgetResponse, WidgetState};
+use structs::cli_args::{CliArgs, USAGE};
+use structs::playlist::{Playlist, AUDIO_EXTENSIONS};
+use structs::preset::{apply_startup_look, Preset, PresetCycle, PresetLibrary};
+use structs::visualisation_mode::VisualisationMode;
+use structs::visualisation_state::VisualisationState;
 use ui::visualisation::update_visualization_bar_colors;
-use ui::ui::update_duration_display;
+use ui::ui::format_time;
 use structs::buttons::Button;   
 use structs::buttons::BUTTON_HEIGHT;
 use structs::buttons::BUTTON_WIDTH;
-use ui::ui::create_element_texture;
-use ui::ui::create_play_button_texture;
+use ui::fonts::TextFont;
+use ui::text_cache::{TextCache, DEFAULT_TEXT_CACHE_CAPACITY};
+use ui::spectrogram::SpectrogramView;
+use ui::meters::meter_panel_layout;
+use ui::help::draw_help_overlay;
+use ui::export::{render_track, FrameWriter};
+use ui::screenshot::{save_screenshot, timestamp};
+use ui::layout::{anchored, column, inset, row, Anchor, Length, LayoutContext};
+use sdl2::video::FullscreenType;
+
+const SEEK_STEP_SECONDS: i64 = 5;
+const VOLUME_STEP: f32 = 0.05;
+const DEFAULT_RENDER_FPS: u32 = 60;
 
 struct UiElements<'a> {
     open_button: Button,
     play_button: Button,
     randomiser_button: Button,
     periodic_randomiser_button: Button,
+    mode_button: Button,
+    scope_timebase_button: Button,
+    scope_channels_button: Button,
+    spectrogram_colour_map_button: Button,
+    spectrogram_history_button: Button,
+    radial_mirror_button: Button,
+    radial_ring_button: Button,
+    palette_button: Button,
+    palette_stop_button: Button,
+    gradient_mode_button: Button,
+    interpolation_button: Button,
+    preset_previous_button: Button,
+    preset_next_button: Button,
+    preset_save_button: Button,
+    preset_cycle_button: Button,
+    preset_name_element: Element,
     song_name_element: Element,
     song_position_element: Element,
     song_duration: Element,
     separator_element: Element,
+    key_element: Element,
     red_slider: ColourSlider,
     green_slider: ColourSlider,
     blue_slider: ColourSlider,
-    // Only keep textures that are actually used
-    texture_play: sdl2::render::Texture<'a>,
-    song_name_texture: sdl2::render::Texture<'a>,
-    song_position_texture: sdl2::render::Texture<'a>,
-    song_duration_texture: sdl2::render::Texture<'a>,
-    separator_texture: sdl2::render::Texture<'a>,
-    open_button_texture: sdl2::render::Texture<'a>,
-    randomiser_button_texture: sdl2::render::Texture<'a>,
-    periodic_randomiser_button_texture: sdl2::render::Texture<'a>,
-}
-
-fn setup_sdl() -> Result<(sdl2::Sdl, sdl2::video::Window, sdl2::ttf::Sdl2TtfContext), Box<dyn std::error::Error>> {
+    // Every piece of text on screen is rendered through here
+    text_cache: TextCache<'a>,
+    spectrogram_view: SpectrogramView<'a>,
+    // Lines of the keybinding help overlay
+    help_lines: Vec<String>,
+    show_help: bool,
+    show_fps: bool,
+    // Set by the screenshot key and cleared once the next frame is saved
+    screenshot_pending: bool,
+    visualisation_area: Rect,
+    layout: LayoutContext,
+    // Fades the controls out in fullscreen presentation mode
+    controls: ControlVisibility,
+    // Colour of all text and button outlines
+    theme: Color,
+}
+
+// Identifies each interactive widget so dispatch results can be routed to a handler
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WidgetId {
+    Open,
+    Play,
+    Randomise,
+    AutoRandomise,
+    Mode,
+    ScopeTimebase,
+    ScopeChannels,
+    SpectrogramColourMap,
+    SpectrogramHistory,
+    RadialMirror,
+    RadialRing,
+    Palette,
+    PaletteStop,
+    GradientMode,
+    Interpolation,
+    RedSlider,
+    GreenSlider,
+    BlueSlider,
+    PresetPrevious,
+    PresetNext,
+    PresetSave,
+    PresetCycle,
+}
+
+// The widgets currently on screen, in tab order
+fn widgets<'w>(ui_elements: &'w mut UiElements, mode: VisualisationMode) -> Vec<(WidgetId, &'w mut dyn Widget)> {
+    let mut widgets: Vec<(WidgetId, &'w mut dyn Widget)> = vec![
+        (WidgetId::Open, &mut ui_elements.open_button),
+        (WidgetId::Play, &mut ui_elements.play_button),
+        (WidgetId::Randomise, &mut ui_elements.randomiser_button),
+        (WidgetId::AutoRandomise, &mut ui_elements.periodic_randomiser_button),
+        (WidgetId::Mode, &mut ui_elements.mode_button),
+    ];
+    // Options for the current mode
+    match mode {
+        VisualisationMode::Oscilloscope => {
+            widgets.push((WidgetId::ScopeTimebase, &mut ui_elements.scope_timebase_button));
+            widgets.push((WidgetId::ScopeChannels, &mut ui_elements.scope_channels_button));
+        }
+        VisualisationMode::Spectrogram => {
+            widgets.push((WidgetId::SpectrogramColourMap, &mut ui_elements.spectrogram_colour_map_button));
+            widgets.push((WidgetId::SpectrogramHistory, &mut ui_elements.spectrogram_history_button));
+        }
+        VisualisationMode::Radial => {
+            widgets.push((WidgetId::RadialMirror, &mut ui_elements.radial_mirror_button));
+            widgets.push((WidgetId::RadialRing, &mut ui_elements.radial_ring_button));
+        }
+        _ => {}
+    }
+    widgets.extend([
+        (WidgetId::Palette, &mut ui_elements.palette_button as &mut dyn Widget),
+        (WidgetId::PaletteStop, &mut ui_elements.palette_stop_button),
+        (WidgetId::GradientMode, &mut ui_elements.gradient_mode_button),
+        (WidgetId::Interpolation, &mut ui_elements.interpolation_button),
+        (WidgetId::RedSlider, &mut ui_elements.red_slider),
+        (WidgetId::GreenSlider, &mut ui_elements.green_slider),
+        (WidgetId::BlueSlider, &mut ui_elements.blue_slider),
+        (WidgetId::PresetPrevious, &mut ui_elements.preset_previous_button),
+        (WidgetId::PresetNext, &mut ui_elements.preset_next_button),
+        (WidgetId::PresetSave, &mut ui_elements.preset_save_button),
+        (WidgetId::PresetCycle, &mut ui_elements.preset_cycle_button),
+    ]);
+    widgets
+}
+
+fn setup_sdl(config: &Config, args: &CliArgs) -> Result<(sdl2::Sdl, sdl2::video::Window, sdl2::ttf::Sdl2TtfContext), Box<dyn std::error::Error>> {
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
     let ttf_context = ttf_init().map_err(|e| e.to_string())?;
 
-    let window = video_subsystem
-        .window("Music Visualizer", 1280, 720)
-        .position_centered()
-        .build()?;
+    let (width, height) = args.window_size.unwrap_or((config.ui.window_width, config.ui.window_height));
+    let mut window_builder = video_subsystem.window("Music Visualizer", width, height);
+    window_builder.position_centered().resizable().allow_highdpi();
+    if args.fullscreen {
+        window_builder.fullscreen_desktop();
+    }
+    let mut window = window_builder.build()?;
+    window.set_minimum_size(640, 480)?;
 
     Ok((sdl_context, window, ttf_context))
 }
 
-fn setup_ui_elements<'a, 'b>(
-    font: &'a sdl2::ttf::Font<'a, 'b>,
+fn setup_ui_elements<'a>(
     texture_creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>,
-) -> Result<UiElements<'a>, Box<dyn std::error::Error>> {
-    let mut open_button = Button::new(10, 10, BUTTON_WIDTH, BUTTON_HEIGHT, "Load song");
-    let mut play_button = Button::new(220, 10, BUTTON_WIDTH, BUTTON_HEIGHT, "Play");
-    let mut randomiser_button = Button::new(900, 10, BUTTON_WIDTH, BUTTON_HEIGHT, "Randomise");
-    let mut periodic_randomiser_button = Button::new(1100, 10, BUTTON_WIDTH, BUTTON_HEIGHT, "Auto");
-
-    // Create elements
-    let song_name_element = Element::new(10, 200, 800, 20, "No song selected");
-    let song_position_element = Element::new(520, 25, 100, 20, "00:00");
-    let separator_element = Element::new(615, 25, 20, 20, "/");
-    let song_duration = Element::new(630, 25, 100, 20, "00:00");
-
-    // Create sliders
-    let red_slider = ColourSlider::new(900, 100, 300, 20, "R");
-    let green_slider = ColourSlider::new(900, 130, 300, 20, "G");
-    let blue_slider = ColourSlider::new(900, 160, 300, 20, "B");
-
-    // Create button textures first
-    let open_button_texture = create_element_texture(font, texture_creator, "Load song")?;
-    let texture_play = create_play_button_texture(font, texture_creator, false)?;
-    let randomiser_button_texture = create_element_texture(font, texture_creator, "Randomise")?;
-    let periodic_randomiser_button_texture = create_element_texture(font, texture_creator, "Auto")?;
-
-    // Update text positions with correct textures
-    open_button.update_text_position(open_button_texture.query().width, open_button_texture.query().height);
-    play_button.update_text_position(texture_play.query().width, texture_play.query().height);
-    randomiser_button.update_text_position(randomiser_button_texture.query().width, randomiser_button_texture.query().height);
-    periodic_randomiser_button.update_text_position(periodic_randomiser_button_texture.query().width, periodic_randomiser_button_texture.query().height);
-
-    Ok(UiElements {
-        open_button,
+    presets: &PresetLibrary,
+    config: &Config,
+    layout: LayoutContext,
+) -> UiElements<'a> {
+    let mut initial_state = VisualisationState::new();
+    initial_state.set_initial_color();
+
+    // Everything starts at the origin and is positioned by apply_layout
+    let mut play_button = Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, "Play");
+    // Nothing to play until a song is loaded
+    play_button.state.disabled = true;
+
+    UiElements {
+        open_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, "Load song"),
         play_button,
-        randomiser_button,
-        periodic_randomiser_button,
-        song_name_element,
-        song_position_element,
-        song_duration,
-        separator_element,
-        red_slider,
-        green_slider,
-        blue_slider,
-        texture_play,
-        song_name_texture: create_element_texture(font, texture_creator, "No song selected")?,
-        song_position_texture: create_element_texture(font, texture_creator, "00:00")?,
-        song_duration_texture: create_element_texture(font, texture_creator, "00:00")?,
-        separator_texture: create_element_texture(font, texture_creator, "/")?,
-        open_button_texture,
-        randomiser_button_texture,
-        periodic_randomiser_button_texture,
-    })
-}
-
-fn main() {
-    match run() {
-        Ok(_) => {
-            println!("Program completed successfully");
-            println!("Press Enter to exit...");
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).unwrap();
+        randomiser_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, "Randomise"),
+        periodic_randomiser_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, "Auto"),
+        mode_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &mode_button_label(&initial_state)),
+        scope_timebase_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &scope_timebase_button_label(&initial_state)),
+        scope_channels_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, initial_state.get_scope_channels().label()),
+        spectrogram_colour_map_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, initial_state.get_spectrogram_colour_map().label()),
+        spectrogram_history_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &spectrogram_history_button_label(&initial_state)),
+        radial_mirror_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &toggle_label("Mirror", initial_state.get_radial_mirror())),
+        radial_ring_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &toggle_label("Ring", initial_state.get_radial_inner_ring())),
+        palette_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &palette_button_label(&initial_state)),
+        palette_stop_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &palette_stop_button_label(&initial_state)),
+        gradient_mode_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &gradient_mode_button_label(&initial_state)),
+        interpolation_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &interpolation_button_label(&initial_state)),
+        preset_previous_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, "< Preset"),
+        preset_next_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, "Preset >"),
+        preset_save_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, "Save preset"),
+        preset_cycle_button: Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &presets.cycle.label()),
+        preset_name_element: Element::new(0, 0, 400, BUTTON_HEIGHT, &preset_name_label(presets)),
+        song_name_element: Element::new(0, 0, 800, 20, "No song selected"),
+        song_position_element: Element::new(0, 0, 100, 20, "00:00"),
+        song_duration: Element::new(0, 0, 100, 20, "00:00"),
+        separator_element: Element::new(0, 0, 20, 20, "/"),
+        key_element: Element::new(0, 0, 200, 20, &key_label(&ChromaAnalyzer::new())),
+        red_slider: ColourSlider::new(0, 0, 300, 20, "R"),
+        green_slider: ColourSlider::new(0, 0, 300, 20, "G"),
+        blue_slider: ColourSlider::new(0, 0, 300, 20, "B"),
+        text_cache: TextCache::new(texture_creator, DEFAULT_TEXT_CACHE_CAPACITY),
+        spectrogram_view: SpectrogramView::new(texture_creator),
+        help_lines: help_lines(&config.keybindings),
+        show_help: false,
+        show_fps: config.ui.show_fps,
+        screenshot_pending: false,
+        visualisation_area: Rect::new(0, 0, 1, 1),
+        layout,
+        controls: ControlVisibility::new(config.ui.controls_hide_delay),
+        theme: config.ui.theme_colour,
+    }
+}
+
+// Positions every widget for the current window size. Sizes are in logical
+// pixels: the controls fill rows on the left, the colour controls sit in a
+// panel on the right and the visualisation takes the space below.
+fn apply_layout(ui_elements: &mut UiElements) {
+    let layout = ui_elements.layout;
+    let gap = layout.px(10);
+    let button_width = layout.px(BUTTON_WIDTH);
+    let button_height = layout.px(BUTTON_HEIGHT);
+    let area = inset(layout.bounds(), layout.px(10));
+
+    let side_width = layout.resolve(Length::Pixels(300), area.width() / 3);
+    let controls = anchored(area, Anchor::TopLeft, area.width() - side_width - gap, area.height());
+    let side_panel = anchored(area, Anchor::TopRight, side_width, area.height());
+    let rows = column(controls, 4, gap, button_height);
+    let cells: Vec<Vec<Rect>> = rows.iter().map(|&rect| row(rect, 4, gap, button_width)).collect();
+
+    ui_elements.open_button.set_rect(cells[0][0]);
+    ui_elements.play_button.set_rect(cells[0][1]);
+    let time = row(cells[0][2], 3, 0, cells[0][2].width());
+    ui_elements.song_position_element.set_bounds(time[0]);
+    ui_elements.separator_element.set_bounds(time[1]);
+    ui_elements.song_duration.set_bounds(time[2]);
+    ui_elements.key_element.set_bounds(cells[0][3]);
+
+    // Only one mode's options are visible at a time, so they share cells
+    ui_elements.mode_button.set_rect(cells[1][0]);
+    ui_elements.scope_timebase_button.set_rect(cells[1][1]);
+    ui_elements.scope_channels_button.set_rect(cells[1][2]);
+    ui_elements.spectrogram_colour_map_button.set_rect(cells[1][1]);
+    ui_elements.spectrogram_history_button.set_rect(cells[1][2]);
+    ui_elements.radial_mirror_button.set_rect(cells[1][1]);
+    ui_elements.radial_ring_button.set_rect(cells[1][2]);
+
+    ui_elements.palette_button.set_rect(cells[2][0]);
+    ui_elements.palette_stop_button.set_rect(cells[2][1]);
+    ui_elements.gradient_mode_button.set_rect(cells[2][2]);
+    ui_elements.interpolation_button.set_rect(cells[2][3]);
+
+    ui_elements.preset_previous_button.set_rect(cells[3][0]);
+    ui_elements.preset_next_button.set_rect(cells[3][1]);
+    ui_elements.preset_save_button.set_rect(cells[3][2]);
+    ui_elements.preset_cycle_button.set_rect(cells[3][3]);
+
+    let side_rows: Vec<Rect> = rows.iter().map(|rect| Rect::new(side_panel.x(), rect.y(), side_panel.width(), rect.height())).collect();
+    let randomiser_cells = row(side_rows[0], 2, gap, side_panel.width());
+    ui_elements.randomiser_button.set_rect(randomiser_cells[0]);
+    ui_elements.periodic_randomiser_button.set_rect(randomiser_cells[1]);
+    let slider_top = side_rows[1].y() + layout.px(30) as i32;
+    let slider_area = Rect::new(
+        side_panel.x(),
+        slider_top,
+        side_panel.width(),
+        (side_rows[2].bottom() - slider_top).max(1) as u32,
+    );
+    let sliders = column(slider_area, 3, layout.px(10), layout.px(20));
+    ui_elements.red_slider.set_rect(sliders[0], layout.px(10));
+    ui_elements.green_slider.set_rect(sliders[1], layout.px(10));
+    ui_elements.blue_slider.set_rect(sliders[2], layout.px(10));
+    ui_elements.preset_name_element.set_bounds(side_rows[3]);
+
+    let song_name_top = rows[3].bottom() + gap as i32;
+    ui_elements.song_name_element.set_bounds(Rect::new(controls.x(), song_name_top, controls.width(), layout.px(20)));
+
+    // The visualisation fills what is left, keeping a margin at the bottom
+    let top = song_name_top + (layout.px(20) + gap * 2) as i32;
+    let bottom = area.bottom() - layout.px(40) as i32;
+    let remaining = Rect::new(area.x(), top, area.width(), (bottom - top).max(1) as u32);
+    let width = layout.resolve(Length::Percent(0.8), area.width());
+    ui_elements.visualisation_area = anchored(remaining, Anchor::Centre, width, remaining.height());
+
+    // In presentation mode the controls float over a full-window visualisation
+    if ui_elements.controls.auto_hide {
+        ui_elements.visualisation_area = layout.bounds();
+    }
+}
+
+fn play_button_label(is_playing: bool) -> String {
+    if is_playing { "Pause" } else { "Play" }.to_string()
+}
+
+fn mode_button_label(state: &VisualisationState) -> String {
+    format!("Mode: {}", state.get_mode().label())
+}
+
+fn scope_timebase_button_label(state: &VisualisationState) -> String {
+    format!("{} ms", state.get_scope_timebase_ms())
+}
+
+fn spectrogram_history_button_label(state: &VisualisationState) -> String {
+    format!("History: {}", state.get_spectrogram_history_length())
+}
+
+fn toggle_label(name: &str, enabled: bool) -> String {
+    format!("{}: {}", name, if enabled { "On" } else { "Off" })
+}
+
+fn palette_button_label(state: &VisualisationState) -> String {
+    format!("Palette: {}", state.get_palette().name)
+}
+
+fn palette_stop_button_label(state: &VisualisationState) -> String {
+    format!("Stop {}/{}", state.get_selected_stop() + 1, state.get_palette().stops.len())
+}
+
+fn gradient_mode_button_label(state: &VisualisationState) -> String {
+    format!("By {}", state.get_gradient_mode().label().to_lowercase())
+}
+
+fn interpolation_button_label(state: &VisualisationState) -> String {
+    format!("Blend: {}", state.get_palette().interpolation.label())
+}
+
+// The estimated key, with a dash until there is enough music to go on
+fn key_label(chroma: &ChromaAnalyzer) -> String {
+    match chroma.key {
+        Some(key) => format!("Key: {}", key.label()),
+        None => "Key: -".to_string(),
+    }
+}
+
+fn preset_name_label(presets: &PresetLibrary) -> String {
+    match presets.current() {
+        Some(preset) => format!("Preset: {}", preset.name),
+        None => "Preset: none".to_string(),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
         }
+    };
+    if args.show_help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    if args.show_version {
+        println!("music_visualiser {}", env!("CARGO_PKG_VERSION"));
+        return ExitCode::SUCCESS;
+    }
+
+    let result = if args.list_devices {
+        list_devices()
+    } else if args.headless {
+        run_headless_from_args(&args)
+    } else if let Some(output) = &args.render {
+        run_render(&args, output)
+    } else if let Some(output) = &args.export_features {
+        run_feature_export(&args, output)
+    } else {
+        run(&args)
+    };
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
             eprintln!("Error: {}", e);
-            println!("\nPress Enter to exit...");
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).unwrap();
+            ExitCode::FAILURE
         }
     }
 }
 
-fn run() -> Result<(), Box<dyn std::error::Error>> {
+fn list_devices() -> Result<(), Box<dyn std::error::Error>> {
+    for name in output_device_names()? {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+fn run_headless_from_args(args: &CliArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load();
+    let playlist = Playlist::from_paths(&args.paths)?;
+    let device = args.device.clone().or(config.audio.device);
+    run_headless(playlist, device, config.audio.volume, config.analyzer.fft_size)
+}
+
+fn run_feature_export(args: &CliArgs, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load();
+    let playlist = Playlist::from_paths(&args.paths)?;
+    let path = playlist.current().cloned().ok_or("--export-features needs an audio file")?;
+    export_features(&path, output, config.analyzer.fft_size, config.analyzer.bar_count)
+}
+
+fn run_render(args: &CliArgs, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut app_state = AppState::new(Config::load());
+    let playlist = Playlist::from_paths(&args.paths)?;
+    if playlist.tracks.len() > 1 {
+        return Err(format!("--render takes one audio file, got {}", playlist.tracks.len()).into());
+    }
+    let path = playlist.current().cloned().ok_or("--render needs an audio file")?;
+    let preset = match &args.preset {
+        Some(name) => Some(app_state.presets.presets[find_preset(&app_state.presets, name)?].clone()),
+        None => None,
+    };
+    if let Some(preset) = &preset {
+        app_state.analyzer = AudioAnalyzer::new(preset.analyzer.fft_size);
+    }
+    apply_startup_look(&mut app_state.visualisation_state.lock().unwrap(), preset.as_ref(), args.mode);
+    let size = args.window_size.unwrap_or((app_state.config.ui.window_width, app_state.config.ui.window_height));
+    let fps = args.fps.unwrap_or(DEFAULT_RENDER_FPS);
+
+    // The software renderer needs no window, but SDL must still be initialised
+    let _sdl_context = sdl2::init()?;
+    println!("Decoding {}...", path.display());
+    let track = decode_file(&path)?;
+    let mut writer = FrameWriter::new(output, &path, size, fps)?;
+    println!("Rendering {}x{} at {} fps...", size.0, size.1, fps);
+    render_track(&mut app_state, &track, &mut writer, size, fps)?;
+    writer.finish()?;
+    println!("Saved {}", output.display());
+    Ok(())
+}
+
+fn find_preset(presets: &PresetLibrary, name: &str) -> Result<usize, String> {
+    presets
+        .presets
+        .iter()
+        .position(|preset| preset.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("no preset called '{}'", name))
+}
+
+fn run(args: &CliArgs) -> Result<(), Box<dyn std::error::Error>> {
     println!("Starting application...");
     
     let (tx, rx) = mpsc::channel::<SampleWrapper>();
     println!("Created channels...");
     
-    let mut app_state = AppState::new();
+    let mut app_state = AppState::new(Config::load());
+    app_state.playlist = Playlist::from_paths(&args.paths)?;
+    if args.device.is_some() {
+        app_state.output_device = args.device.clone();
+    }
     println!("Created app state...");
     
+    let result = run_window(args, &mut app_state, &tx, &rx);
+    // Stop playback and save settings whether the window closed or failed
+    handle_quit(&mut app_state)?;
+    result
+}
+
+// Opens the window and runs the event loop until it is closed
+fn run_window(
+    args: &CliArgs,
+    app_state: &mut AppState,
+    tx: &mpsc::Sender<SampleWrapper>,
+    rx: &mpsc::Receiver<SampleWrapper>,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Setup SDL
-    let (sdl_context, window, ttf_context) = setup_sdl()?;
+    let (sdl_context, window, ttf_context) = setup_sdl(&app_state.config, args)?;
     println!("SDL setup complete...");
     
-    let mut canvas = window.into_canvas().build()?;
+    let mut canvas_builder = window.into_canvas();
+    if app_state.config.ui.vsync {
+        canvas_builder = canvas_builder.present_vsync();
+    }
+    let mut canvas = canvas_builder.build()?;
     let texture_creator = canvas.texture_creator();
     println!("Loading font...");
-    let font = ttf_context.load_font("assets/fonts/times.ttf", 24)?;
+    // Render text at the drawable resolution so it stays sharp on high-DPI screens
+    let layout = LayoutContext::from_canvas(&canvas)?;
+    let mut font = TextFont::load(&ttf_context, &app_state.config.ui, scaled_font_size(&app_state.config, layout.scale))?;
     
     // Setup UI
     println!("Setting up UI...");
-    let mut ui_elements = setup_ui_elements(&font, &texture_creator)?;
+    let mut ui_elements = setup_ui_elements(&texture_creator, &app_state.presets, &app_state.config, layout);
+    ui_elements.controls.auto_hide = args.fullscreen;
+    apply_layout(&mut ui_elements);
+    sync_sliders(&mut ui_elements, &app_state.visualisation_state.lock().unwrap());
+    if let Some(name) = &args.preset {
+        let index = find_preset(&app_state.presets, name)?;
+        app_state.presets.current = index;
+        let preset = app_state.presets.presets[index].clone();
+        apply_preset(app_state, &mut ui_elements, &preset)?;
+    }
+    // After the preset, which brings its own mode, so an explicit --mode wins
+    if let Some(mode) = args.mode {
+        select_mode(app_state, &mut ui_elements, mode)?;
+    }
+    if let Some(path) = app_state.playlist.current().cloned() {
+        load_track(app_state, &mut ui_elements, path)?;
+        if args.autoplay {
+            handle_play_button(app_state, &mut ui_elements, tx)?;
+        }
+    }
     let mut event_pump = sdl_context.event_pump()?;
+    let mut frame_timer = FrameTimer::new(app_state.config.ui.fps_limit);
 
     println!("Entering main loop...");
     'running: loop {
+        let dt = frame_timer.tick();
+
         // Clear canvas
         canvas.set_draw_color(Color::RGB(0, 0, 0));
         canvas.clear();
@@ -166,181 +548,363 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
         // Handle events
         for event in event_pump.poll_iter() {
             match event {
-                Event::Quit { .. } => {
-                    handle_quit(&mut app_state)?;
-                    break 'running;
+                Event::Quit { .. } => break 'running,
+                Event::Window { win_event: WindowEvent::SizeChanged(..), .. } => {
+                    let layout = LayoutContext::from_canvas(&canvas)?;
+                    // Moving to a screen with another pixel density changes the text size
+                    if layout.scale != ui_elements.layout.scale {
+                        font = TextFont::load(&ttf_context, &app_state.config.ui, scaled_font_size(&app_state.config, layout.scale))?;
+                        ui_elements.text_cache.clear();
+                    }
+                    ui_elements.layout = layout;
+                    apply_layout(&mut ui_elements);
                 },
                 Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
-                    handle_mouse_click(&mut app_state, &mut ui_elements, x, y, &tx, &font, &texture_creator)?;
+                    // A click on hidden controls only brings them back
+                    let was_hidden = ui_elements.controls.is_hidden();
+                    ui_elements.controls.wake();
+                    if ui_elements.show_help {
+                        // The help overlay covers everything, so a click only dismisses it
+                        ui_elements.show_help = false;
+                    } else if !was_hidden {
+                        let (x, y) = ui_elements.layout.drawable_point(x, y);
+                        let event = WidgetEvent::MouseDown(Point::new(x, y));
+                        handle_widget_event(app_state, &mut ui_elements, &event, tx)?;
+                    }
                 },
-                Event::MouseButtonUp { mouse_btn: MouseButton::Left, .. } => {
-                    handle_mouse_up(&mut ui_elements);
+                Event::MouseButtonUp { mouse_btn: MouseButton::Left, x, y, .. } => {
+                    let (x, y) = ui_elements.layout.drawable_point(x, y);
+                    let event = WidgetEvent::MouseUp(Point::new(x, y));
+                    handle_widget_event(app_state, &mut ui_elements, &event, tx)?;
                 },
                 Event::MouseMotion { x, y, .. } => {
-                    handle_mouse_motion(&mut app_state, &mut ui_elements, x)?;
+                    ui_elements.controls.wake();
+                    let (x, y) = ui_elements.layout.drawable_point(x, y);
+                    let event = WidgetEvent::MouseMove(Point::new(x, y));
+                    handle_widget_event(app_state, &mut ui_elements, &event, tx)?;
+                },
+                Event::KeyDown { keycode: Some(keycode), keymod, repeat: false, .. } => {
+                    let used_by_widget = handle_focus_keys(app_state, &mut ui_elements, keycode, keymod, tx)?;
+                    if !used_by_widget {
+                        handle_key_down(app_state, &mut ui_elements, keycode, canvas.window_mut(), tx)?;
+                    }
                 },
                 _ => {}
             }
         }
 
         // Update state
-        update_state(&mut app_state, &mut ui_elements, &rx, &font, &texture_creator)?;
+        handle_track_end(app_state, &mut ui_elements, tx)?;
+        update_state(app_state, &mut ui_elements, rx, dt)?;
+
+        sdl_context.mouse().show_cursor(!ui_elements.controls.is_hidden());
 
         // Draw UI
-        draw_ui(&mut canvas, &app_state, &ui_elements)?;
+        draw_ui(&mut canvas, app_state, &mut ui_elements, &font, frame_timer.stats())?;
         
         canvas.present();
+        frame_timer.wait();
     }
     Ok(())
 }
 
+// The configured font size in drawable pixels
+fn scaled_font_size(config: &Config, scale: f32) -> u16 {
+    (config.ui.font_size as f32 * scale).round() as u16
+}
+
 fn handle_quit(app_state: &mut AppState) -> Result<(), Box<dyn std::error::Error>> {
     {
         let mut state = app_state.playback_state.lock().unwrap();
         state.should_stop = true;
+        app_state.config.audio.volume = state.get_volume();
     }
     if let Some(handle) = app_state.playback_handle.take() {
         handle.join().ok();
     }
+    {
+        let state = app_state.visualisation_state.lock().unwrap();
+        app_state.config.visualisation.palette = state.get_palette().name.clone();
+        app_state.config.visualisation.colours = state.get_palette().stop_colours();
+    }
+    // Failing to save settings should not turn a clean exit into an error
+    if let Err(e) = app_state.config.save() {
+        eprintln!("Could not save settings: {}", e);
+    }
     Ok(())
 }
 
-fn handle_mouse_up(ui_elements: &mut UiElements) {
-    ui_elements.red_slider.is_dragging = false;
-    ui_elements.green_slider.is_dragging = false;
-    ui_elements.blue_slider.is_dragging = false;
+// Tab moves keyboard focus between the controls and Escape drops it, and a
+// focused widget gets first refusal on other keys (e.g. arrows nudge a slider).
+// Returns true if the key was used here rather than as a shortcut.
+fn handle_focus_keys(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+    keycode: Keycode,
+    keymod: Mod,
+    tx: &mpsc::Sender<SampleWrapper>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mode = app_state.visualisation_state.lock().unwrap().get_mode();
+    if keycode == Keycode::Tab {
+        let backwards = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+        focus_next(&mut widgets(ui_elements, mode), backwards);
+        ui_elements.controls.wake();
+        return Ok(true);
+    }
+    if keycode == Keycode::Escape {
+        return Ok(clear_focus(&mut widgets(ui_elements, mode)));
+    }
+    let used = handle_widget_event(app_state, ui_elements, &WidgetEvent::Key(keycode), tx)?;
+    if used {
+        ui_elements.controls.wake();
+    }
+    Ok(used)
 }
 
-fn handle_mouse_motion(
+fn handle_key_down(
     app_state: &mut AppState,
     ui_elements: &mut UiElements,
-    x: i32,
+    keycode: Keycode,
+    window: &mut sdl2::video::Window,
+    tx: &mpsc::Sender<SampleWrapper>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if ui_elements.red_slider.is_dragging {
-        ui_elements.red_slider.update(x);
-        let mut state = app_state.visualisation_state.lock().unwrap();
-        state.set_red_slider(ui_elements.red_slider.value);
+    let action = match app_state.keymap.action(keycode) {
+        Some(action) => action,
+        None => return Ok(()),
+    };
+    match action {
+        Action::PlayPause => {
+            if app_state.file_path.is_some() {
+                handle_play_button(app_state, ui_elements, tx)?;
+            }
+        }
+        Action::SeekForward => handle_seek(app_state, SEEK_STEP_SECONDS),
+        Action::SeekBackward => handle_seek(app_state, -SEEK_STEP_SECONDS),
+        Action::VolumeUp => handle_volume(app_state, VOLUME_STEP),
+        Action::VolumeDown => handle_volume(app_state, -VOLUME_STEP),
+        Action::TrackNext => handle_track_change(app_state, ui_elements, tx, true)?,
+        Action::TrackPrevious => handle_track_change(app_state, ui_elements, tx, false)?,
+        Action::PresetNext => handle_preset_next_button(app_state, ui_elements)?,
+        Action::PresetPrevious => handle_preset_previous_button(app_state, ui_elements)?,
+        Action::Randomise => handle_randomiser_button(app_state)?,
+        Action::ToggleFullscreen => {
+            let fullscreen = match window.fullscreen_state() {
+                FullscreenType::Off => FullscreenType::Desktop,
+                _ => FullscreenType::Off,
+            };
+            window.set_fullscreen(fullscreen)?;
+            ui_elements.controls.auto_hide = fullscreen != FullscreenType::Off;
+            ui_elements.controls.wake();
+            apply_layout(ui_elements);
+        }
+        Action::ToggleHelp => ui_elements.show_help = !ui_elements.show_help,
+        Action::ToggleFpsOverlay => ui_elements.show_fps = !ui_elements.show_fps,
+        Action::Screenshot => ui_elements.screenshot_pending = true,
+        _ => {
+            if let Some(mode) = action.mode() {
+                select_mode(app_state, ui_elements, mode)?;
+            }
+        }
     }
-    if ui_elements.green_slider.is_dragging {
-        ui_elements.green_slider.update(x);
-        let mut state = app_state.visualisation_state.lock().unwrap();
-        state.set_green_slider(ui_elements.green_slider.value);
+    Ok(())
+}
+
+fn handle_seek(app_state: &mut AppState, offset_seconds: i64) {
+    if app_state.playback_handle.is_none() {
+        return;
     }
-    if ui_elements.blue_slider.is_dragging {
-        ui_elements.blue_slider.update(x);
-        let mut state = app_state.visualisation_state.lock().unwrap();
-        state.set_blue_slider(ui_elements.blue_slider.value);
+    let mut state = app_state.playback_state.lock().unwrap();
+    let target = (state.get_song_position() as i64 + offset_seconds).max(0);
+    state.request_seek(target as u32);
+}
+
+fn handle_volume(app_state: &mut AppState, change: f32) {
+    let mut state = app_state.playback_state.lock().unwrap();
+    let volume = state.get_volume() + change;
+    state.set_volume(volume);
+}
+
+// Switches to the next or previous playlist entry, carrying on playing if a song was playing
+fn handle_track_change(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+    tx: &mpsc::Sender<SampleWrapper>,
+    forward: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = if forward {
+        app_state.playlist.advance().cloned()
+    } else {
+        app_state.playlist.previous().cloned()
+    };
+    if let Some(path) = path {
+        let was_playing = app_state.playback_state.lock().unwrap().is_playing;
+        load_track(app_state, ui_elements, path)?;
+        if was_playing {
+            handle_play_button(app_state, ui_elements, tx)?;
+        }
     }
     Ok(())
 }
 
-fn handle_mouse_click<'a, 'b>(
+// Passes an input event through the widget dispatcher and runs the handler of
+// whichever widget responds. Returns true if a widget consumed the event.
+fn handle_widget_event(
     app_state: &mut AppState,
-    ui_elements: &mut UiElements<'a>,
-    x: i32,
-    y: i32,
+    ui_elements: &mut UiElements,
+    event: &WidgetEvent,
     tx: &mpsc::Sender<SampleWrapper>,
-    font: &'a sdl2::ttf::Font<'a, 'b>,
-    texture_creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let click_point = Point::new(x, y);
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mode = app_state.visualisation_state.lock().unwrap().get_mode();
+    let (id, response) = match dispatch(&mut widgets(ui_elements, mode), event) {
+        Some(result) => result,
+        None => return Ok(false),
+    };
 
-    if ui_elements.open_button.is_clicked(click_point) {
-        handle_open_button(app_state, ui_elements, font, texture_creator)?;
-    } else if app_state.file_path.is_some() && ui_elements.play_button.is_clicked(click_point) {
-        handle_play_button(app_state, ui_elements, tx, font, texture_creator)?;
-    } else if ui_elements.randomiser_button.is_clicked(click_point) {
-        handle_randomiser_button(app_state, ui_elements)?;
-    } else if ui_elements.periodic_randomiser_button.is_clicked(click_point) {
-        handle_periodic_randomiser_button(app_state)?;
-    } else {
-        handle_slider_click(app_state, ui_elements, x, y)?;
+    match (id, response) {
+        (WidgetId::RedSlider, WidgetResponse::Changed(value)) => {
+            app_state.visualisation_state.lock().unwrap().set_red_slider(value);
+        }
+        (WidgetId::GreenSlider, WidgetResponse::Changed(value)) => {
+            app_state.visualisation_state.lock().unwrap().set_green_slider(value);
+        }
+        (WidgetId::BlueSlider, WidgetResponse::Changed(value)) => {
+            app_state.visualisation_state.lock().unwrap().set_blue_slider(value);
+        }
+        (_, WidgetResponse::Clicked) => handle_button_click(app_state, ui_elements, id, tx)?,
+        _ => {}
     }
+    Ok(true)
+}
 
+fn handle_button_click(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+    id: WidgetId,
+    tx: &mpsc::Sender<SampleWrapper>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match id {
+        WidgetId::Open => handle_open_button(app_state, ui_elements)?,
+        WidgetId::Play => handle_play_button(app_state, ui_elements, tx)?,
+        WidgetId::Randomise => handle_randomiser_button(app_state)?,
+        WidgetId::AutoRandomise => handle_periodic_randomiser_button(app_state)?,
+        WidgetId::Mode => handle_mode_button(app_state, ui_elements)?,
+        WidgetId::ScopeTimebase => handle_scope_timebase_button(app_state, ui_elements)?,
+        WidgetId::ScopeChannels => handle_scope_channels_button(app_state, ui_elements)?,
+        WidgetId::SpectrogramColourMap => handle_spectrogram_colour_map_button(app_state, ui_elements)?,
+        WidgetId::SpectrogramHistory => handle_spectrogram_history_button(app_state, ui_elements)?,
+        WidgetId::RadialMirror => handle_radial_mirror_button(app_state, ui_elements)?,
+        WidgetId::RadialRing => handle_radial_ring_button(app_state, ui_elements)?,
+        WidgetId::Palette => handle_palette_button(app_state, ui_elements)?,
+        WidgetId::PaletteStop => handle_palette_stop_button(app_state, ui_elements)?,
+        WidgetId::GradientMode => handle_gradient_mode_button(app_state, ui_elements)?,
+        WidgetId::Interpolation => handle_interpolation_button(app_state, ui_elements)?,
+        WidgetId::PresetPrevious => handle_preset_previous_button(app_state, ui_elements)?,
+        WidgetId::PresetNext => handle_preset_next_button(app_state, ui_elements)?,
+        WidgetId::PresetSave => handle_preset_save_button(app_state, ui_elements)?,
+        WidgetId::PresetCycle => handle_preset_cycle_button(app_state, ui_elements)?,
+        WidgetId::RedSlider | WidgetId::GreenSlider | WidgetId::BlueSlider => {}
+    }
     Ok(())
 }
 
-fn handle_open_button<'a, 'b>(
+fn handle_open_button(
     app_state: &mut AppState,
-    ui_elements: &mut UiElements<'a>,
-    font: &'a sdl2::ttf::Font<'a, 'b>,
-    texture_creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut dialog = FileDialog::new().add_filter("Audio", &AUDIO_EXTENSIONS);
+    if let Some(directory) = &app_state.config.audio.last_directory {
+        dialog = dialog.set_directory(directory);
+    }
+    if let Some(path) = dialog.pick_file() {
+        app_state.config.audio.last_directory = path.parent().map(|parent| parent.to_path_buf());
+        app_state.playlist = Playlist::single(path.clone());
+        load_track(app_state, ui_elements, path)?;
+    }
+    Ok(())
+}
+
+// Stops any current playback and makes `path` the loaded song
+fn load_track(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+    path: PathBuf,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(path) = FileDialog::new()
-        .add_filter("Audio", &["mp3", "wav", "ogg"])
-        .pick_file()
     {
-        {
-            let mut state = app_state.playback_state.lock().unwrap();
-            state.should_stop = true;
-            state.is_playing = false;
-
-            let file_name = path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("No song selected");
-            let selected_song = format!("Now playing: {}", file_name);
-            state.set_selected_song(selected_song.clone());
-
-            update_song_display(&mut *state, ui_elements, font, texture_creator, &selected_song)?;
-        }
+        let mut state = app_state.playback_state.lock().unwrap();
+        state.should_stop = true;
+        state.is_playing = false;
 
-        if let Some(handle) = app_state.playback_handle.take() {
-            handle.join().ok();
-        }
-        app_state.file_path = Some(path);
-        ui_elements.texture_play = create_play_button_texture(font, texture_creator, false)?;
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("No song selected");
+        let selected_song = format!("Now playing: {}", file_name);
+        state.set_selected_song(selected_song.clone());
+        // The playback thread fills these in again if the new file reports its length
+        state.set_song_duration(0);
+        state.set_song_position(0);
+        state.seek_request = None;
+
+        update_song_display(&state, ui_elements, &selected_song);
+    }
+
+    if let Some(handle) = app_state.playback_handle.take() {
+        handle.join().ok();
+    }
+    app_state.file_path = Some(path);
+    ui_elements.play_button.state.disabled = false;
+    app_state.waveform.clear();
+    app_state.goniometer.clear();
+    app_state.spectrogram.clear();
+    app_state.beat_detector.reset();
+    app_state.features.reset();
+    app_state.chroma.reset();
+    app_state.pitch.reset();
+    app_state.meters.reset();
+    ui_elements.key_element.set_text(&key_label(&app_state.chroma));
+    app_state.analysis = AnalysisFrame::default();
+    app_state.particle_system.clear();
+    ui_elements.play_button.text = play_button_label(false);
+    if app_state.presets.cycle == PresetCycle::OnTrackChange {
+        handle_preset_next_button(app_state, ui_elements)?;
     }
     Ok(())
 }
 
-fn update_song_display<'a, 'b>(
-    state: &mut AudioPlaybackState,
-    ui_elements: &mut UiElements<'a>,
-    font: &'a sdl2::ttf::Font<'a, 'b>,
-    texture_creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>,
-    display_song: &str,
+// When the playback thread finishes on its own the song has ended: move on
+// to the next playlist entry, or reset the play button at the end of the list
+fn handle_track_end(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+    tx: &mpsc::Sender<SampleWrapper>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let duration = state.get_song_duration();
-    let position = state.get_song_position();
-    
-    ui_elements.song_name_texture = create_element_texture(font, texture_creator, display_song)?;
-    ui_elements.song_position_texture = create_element_texture(
-        font, 
-        texture_creator, 
-        &format!("{:02}:{:02}", position / 60, position % 60)
-    )?;
-    ui_elements.song_duration_texture = create_element_texture(
-        font, 
-        texture_creator, 
-        &format!("{:02}:{:02}", duration / 60, duration % 60)
-    )?;
-
-    update_element_positions(ui_elements);
-    Ok(())
-}
-
-fn update_element_positions(ui_elements: &mut UiElements<'_>) {
-    ui_elements.song_position_element.update_text_position(
-        ui_elements.song_position_texture.query().width,
-        ui_elements.song_position_texture.query().height,
-    );
-    ui_elements.song_name_element.update_text_position(
-        ui_elements.song_name_texture.query().width,
-        ui_elements.song_name_texture.query().height,
-    );
-    ui_elements.song_duration.update_text_position(
-        ui_elements.song_duration_texture.query().width,
-        ui_elements.song_duration_texture.query().height,
-    );
+    if !app_state.playback_handle.as_ref().is_some_and(|handle| handle.is_finished()) {
+        return Ok(());
+    }
+    if let Some(handle) = app_state.playback_handle.take() {
+        handle.join().ok();
+    }
+    app_state.playback_state.lock().unwrap().is_playing = false;
+
+    if let Some(path) = app_state.playlist.advance().cloned() {
+        load_track(app_state, ui_elements, path)?;
+        handle_play_button(app_state, ui_elements, tx)?;
+    } else {
+        ui_elements.play_button.text = play_button_label(false);
+    }
+    Ok(())
 }
 
-fn handle_play_button<'a, 'b>(
+fn update_song_display(state: &AudioPlaybackState, ui_elements: &mut UiElements, display_song: &str) {
+    ui_elements.song_name_element.set_text(display_song);
+    ui_elements.song_position_element.set_text(&format_time(state.get_song_position()));
+    ui_elements.song_duration.set_text(&format_time(state.get_song_duration()));
+}
+
+fn handle_play_button(
     app_state: &mut AppState,
-    ui_elements: &mut UiElements<'a>,
+    ui_elements: &mut UiElements,
     tx: &mpsc::Sender<SampleWrapper>,
-    font: &'a sdl2::ttf::Font<'a, 'b>,
-    texture_creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut state = app_state.playback_state.lock().unwrap();
     if state.is_playing {
@@ -353,22 +917,21 @@ fn handle_play_button<'a, 'b>(
             let tx_clone = tx.clone();
             let state_clone = app_state.playback_state.clone();
             drop(state);
-            app_state.playback_handle = Some(start_audio_playback(path, tx_clone, state_clone));
+            app_state.playback_handle = Some(start_audio_playback(path, tx_clone, state_clone, app_state.output_device.clone()));
             state = app_state.playback_state.lock().unwrap();
         } else {
             state.is_playing = true;
         }
     }
-    ui_elements.texture_play = create_play_button_texture(font, texture_creator, state.is_playing)?;
+    ui_elements.play_button.text = play_button_label(state.is_playing);
     Ok(())
 }
 
 fn handle_randomiser_button(
     app_state: &mut AppState,
-    ui_elements: &mut UiElements,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut state = app_state.visualisation_state.lock().unwrap();
-    update_visualization_bar_colors(&mut state, &mut ui_elements.red_slider, &mut ui_elements.green_slider, &mut ui_elements.blue_slider)?;
+    let state = app_state.visualisation_state.lock().unwrap();
+    update_visualization_bar_colors(&state, &mut app_state.colour_randomiser);
     Ok(())
 }
 
@@ -381,91 +944,291 @@ fn handle_periodic_randomiser_button(
     Ok(())
 }
 
-fn handle_slider_click(
+fn is_mode(app_state: &AppState, mode: VisualisationMode) -> bool {
+    app_state.visualisation_state.lock().unwrap().get_mode() == mode
+}
+
+fn handle_mode_button(
     app_state: &mut AppState,
     ui_elements: &mut UiElements,
-    x: i32,
-    y: i32,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let click_point = Point::new(x, y);
-    
-    if ui_elements.red_slider.rect.contains_point(click_point) {
-        ui_elements.red_slider.is_dragging = true;
-        ui_elements.red_slider.update(x);
-        let mut state = app_state.visualisation_state.lock().unwrap();
-        state.set_red_slider(ui_elements.red_slider.value);
-    } else if ui_elements.green_slider.rect.contains_point(click_point) {
-        ui_elements.green_slider.is_dragging = true;
-        ui_elements.green_slider.update(x);
-        let mut state = app_state.visualisation_state.lock().unwrap();
-        state.set_green_slider(ui_elements.green_slider.value);
-    } else if ui_elements.blue_slider.rect.contains_point(click_point) {
-        ui_elements.blue_slider.is_dragging = true;
-        ui_elements.blue_slider.update(x);
-        let mut state = app_state.visualisation_state.lock().unwrap();
-        state.set_blue_slider(ui_elements.blue_slider.value);
+    let next_mode = app_state.visualisation_state.lock().unwrap().get_mode().next();
+    select_mode(app_state, ui_elements, next_mode)
+}
+
+fn select_mode(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+    mode: VisualisationMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    state.set_mode(mode);
+    ui_elements.mode_button.text = mode_button_label(&state);
+    Ok(())
+}
+
+fn handle_scope_timebase_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    state.cycle_scope_timebase();
+    ui_elements.scope_timebase_button.text = scope_timebase_button_label(&state);
+    Ok(())
+}
+
+fn handle_scope_channels_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    state.cycle_scope_channels();
+    ui_elements.scope_channels_button.text = state.get_scope_channels().label().to_string();
+    Ok(())
+}
+
+fn handle_spectrogram_colour_map_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    state.cycle_spectrogram_colour_map();
+    ui_elements.spectrogram_colour_map_button.text = state.get_spectrogram_colour_map().label().to_string();
+    Ok(())
+}
+
+fn handle_spectrogram_history_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    state.cycle_spectrogram_history();
+    ui_elements.spectrogram_history_button.text = spectrogram_history_button_label(&state);
+    Ok(())
+}
+
+fn handle_radial_mirror_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    let mirror = !state.get_radial_mirror();
+    state.set_radial_mirror(mirror);
+    ui_elements.radial_mirror_button.text = toggle_label("Mirror", mirror);
+    Ok(())
+}
+
+fn handle_radial_ring_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    let inner_ring = !state.get_radial_inner_ring();
+    state.set_radial_inner_ring(inner_ring);
+    ui_elements.radial_ring_button.text = toggle_label("Ring", inner_ring);
+    Ok(())
+}
+
+fn handle_palette_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    app_state.colour_randomiser.cancel();
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    state.cycle_palette();
+    ui_elements.palette_button.text = palette_button_label(&state);
+    // A new palette resets the stop selection and its blend mode
+    ui_elements.palette_stop_button.text = palette_stop_button_label(&state);
+    ui_elements.interpolation_button.text = interpolation_button_label(&state);
+    sync_sliders(ui_elements, &state);
+    Ok(())
+}
+
+fn handle_palette_stop_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    state.cycle_selected_stop();
+    ui_elements.palette_stop_button.text = palette_stop_button_label(&state);
+    sync_sliders(ui_elements, &state);
+    Ok(())
+}
+
+fn handle_gradient_mode_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    state.cycle_gradient_mode();
+    ui_elements.gradient_mode_button.text = gradient_mode_button_label(&state);
+    Ok(())
+}
+
+fn handle_interpolation_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    state.cycle_interpolation();
+    ui_elements.interpolation_button.text = interpolation_button_label(&state);
+    Ok(())
+}
+
+fn handle_preset_previous_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(preset) = app_state.presets.previous().cloned() {
+        apply_preset(app_state, ui_elements, &preset)?;
+    }
+    Ok(())
+}
+
+fn handle_preset_next_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(preset) = app_state.presets.next().cloned() {
+        apply_preset(app_state, ui_elements, &preset)?;
+    }
+    Ok(())
+}
+
+fn handle_preset_save_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let name = app_state.presets.next_custom_name();
+    let preset = {
+        let state = app_state.visualisation_state.lock().unwrap();
+        Preset::from_state(&name, &state, app_state.analyzer.window_size())
+    };
+    // Keep the new preset selected even if the file could not be written
+    if let Err(e) = app_state.presets.add(preset) {
+        eprintln!("Could not save preset: {}", e);
+    }
+    update_preset_name(&app_state.presets, ui_elements);
+    Ok(())
+}
+
+fn handle_preset_cycle_button(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+) -> Result<(), Box<dyn std::error::Error>> {
+    app_state.presets.cycle = app_state.presets.cycle.next();
+    ui_elements.preset_cycle_button.text = app_state.presets.cycle.label();
+    Ok(())
+}
+
+fn update_preset_name(presets: &PresetLibrary, ui_elements: &mut UiElements) {
+    ui_elements.preset_name_element.set_text(&preset_name_label(presets));
+}
+
+fn apply_preset(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+    preset: &Preset,
+) -> Result<(), Box<dyn std::error::Error>> {
+    app_state.colour_randomiser.cancel();
+    if preset.analyzer.fft_size != app_state.analyzer.window_size() {
+        app_state.analyzer = AudioAnalyzer::new(preset.analyzer.fft_size);
+    }
+    let mut state = app_state.visualisation_state.lock().unwrap();
+    preset.apply(&mut state);
+    sync_sliders(ui_elements, &state);
+
+    // Every state-driven label may have changed
+    let labels = [
+        (&mut ui_elements.mode_button, mode_button_label(&state)),
+        (&mut ui_elements.scope_timebase_button, scope_timebase_button_label(&state)),
+        (&mut ui_elements.scope_channels_button, state.get_scope_channels().label().to_string()),
+        (&mut ui_elements.spectrogram_colour_map_button, state.get_spectrogram_colour_map().label().to_string()),
+        (&mut ui_elements.spectrogram_history_button, spectrogram_history_button_label(&state)),
+        (&mut ui_elements.radial_mirror_button, toggle_label("Mirror", state.get_radial_mirror())),
+        (&mut ui_elements.radial_ring_button, toggle_label("Ring", state.get_radial_inner_ring())),
+        (&mut ui_elements.palette_button, palette_button_label(&state)),
+        (&mut ui_elements.palette_stop_button, palette_stop_button_label(&state)),
+        (&mut ui_elements.gradient_mode_button, gradient_mode_button_label(&state)),
+        (&mut ui_elements.interpolation_button, interpolation_button_label(&state)),
+    ];
+    for (button, label) in labels {
+        button.text = label;
     }
+
+    update_preset_name(&app_state.presets, ui_elements);
     Ok(())
 }
 
-fn update_state<'a, 'b>(
+// Points the RGB sliders at the colour of the currently selected stop
+fn sync_sliders(ui_elements: &mut UiElements, state: &VisualisationState) {
+    let colour = state.get_selected_color();
+    ui_elements.red_slider.set_value(colour.r);
+    ui_elements.green_slider.set_value(colour.g);
+    ui_elements.blue_slider.set_value(colour.b);
+}
+
+fn update_state(
     app_state: &mut AppState,
-    ui_elements: &mut UiElements<'a>,
+    ui_elements: &mut UiElements,
     rx: &mpsc::Receiver<SampleWrapper>,
-    font: &'a sdl2::ttf::Font<'a, 'b>,
-    texture_creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+    dt: f32,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if app_state.last_second.elapsed().as_secs() >= 1 {
+    app_state.second_timer += dt;
+    if app_state.second_timer >= 1.0 {
         let state = app_state.playback_state.lock().unwrap();
         if state.is_playing {
             {
-                let mut visualisation_state = app_state.visualisation_state.lock().unwrap();
+                let visualisation_state = app_state.visualisation_state.lock().unwrap();
                 if visualisation_state.get_is_auto_randomising() {
-                    update_visualization_bar_colors(&mut visualisation_state, &mut ui_elements.red_slider, &mut ui_elements.green_slider, &mut ui_elements.blue_slider)?;
+                    update_visualization_bar_colors(&visualisation_state, &mut app_state.colour_randomiser);
                 }
             }
-            ui_elements.song_name_element.move_element(
-                ui_elements.song_name_element.rect.x,
-                ui_elements.song_name_element.rect.y,
-                ui_elements.song_position_texture.query().width as i32 - 1 / 20,
-            );
-            if ui_elements.song_name_element.get_element_current_position() > 750 {
-                ui_elements.song_name_element.move_element(
-                    ui_elements.song_name_element.rect.x,
-                    ui_elements.song_name_element.rect.y,
-                    -1300,
-                );
+            let step = ui_elements.song_position_element.rect.width() as i32;
+            ui_elements.song_name_element.scroll_by(step);
+            let bounds = ui_elements.song_name_element.bounds;
+            if ui_elements.song_name_element.get_element_current_position() > bounds.right() - 60 {
+                ui_elements.song_name_element.scroll_by(-(bounds.width() as i32 + 500));
             }
         }
-        app_state.last_second = Instant::now();
-    }
-
-    if let Ok(sample_wrapper) = rx.try_recv() {
-        let spectrum = app_state.analyzer.process(&sample_wrapper.samples);
-        let spectrum_len = spectrum.len();
-
-        {
-            let state = app_state.playback_state.lock().unwrap();
-            let position = state.get_song_position();
-            let minutes = position / 60;
-            let seconds = position % 60;
-            let position_str = format!("{:02}:{:02}", minutes, seconds);
-            ui_elements.song_position_texture = create_element_texture(font, texture_creator, &position_str)?;
-            ui_elements.song_position_element.update_text_position(
-                ui_elements.song_position_texture.query().width,
-                ui_elements.song_position_texture.query().height,
-            );
-            ui_elements.song_duration_texture = update_duration_display(&state, font, texture_creator, &mut ui_elements.song_duration)?;
-        }
+        app_state.second_timer %= 1.0;
+    }
 
-        for i in 0..16 {
-            let start = i * spectrum_len / 16;
-            let end = (i + 1) * spectrum_len / 16;
-            app_state.frequencies[i] = spectrum[start..end]
-                .iter()
-                .fold(0.0, |max, &x| f32::max(max, x));
-            app_state.frequencies[i] = (app_state.frequencies[i] * 5.0).min(1.0);
-        }
+    if let Some(colours) = app_state.colour_randomiser.advance(dt) {
+        let mut state = app_state.visualisation_state.lock().unwrap();
+        state.palette.set_stop_colours(&colours);
+        sync_sliders(ui_elements, &state);
+    }
+    if is_mode(app_state, VisualisationMode::Particles) {
+        let beat_detector = &app_state.beat_detector;
+        let brightness = app_state.analysis.brightness();
+        app_state.particle_system.update(dt, beat_detector.energy, beat_detector.beat_count, brightness);
+    }
+
+    // Take every packet that arrived since the last frame, so analysis keeps
+    // pace with the audio whatever the frame rate
+    let mut received = false;
+    while let Ok(sample_wrapper) = rx.try_recv() {
+        process_samples(app_state, ui_elements, &sample_wrapper)?;
+        received = true;
+    }
+    if received {
+        let state = app_state.playback_state.lock().unwrap();
+        ui_elements.song_position_element.set_text(&format_time(state.get_song_position()));
+        ui_elements.song_duration.set_text(&format_time(state.get_song_duration()));
+        ui_elements.key_element.set_text(&key_label(&app_state.chroma));
+    }
+    Ok(())
+}
+
+fn process_samples(
+    app_state: &mut AppState,
+    ui_elements: &mut UiElements,
+    sample_wrapper: &SampleWrapper,
+) -> Result<(), Box<dyn std::error::Error>> {
+    app_state.analyse(sample_wrapper);
+    if app_state.presets.should_cycle_on_beat(app_state.beat_detector.beat_count) {
+        handle_preset_next_button(app_state, ui_elements)?;
     }
     Ok(())
 }
@@ -473,68 +1236,297 @@ fn update_state<'a, 'b>(
 fn draw_ui(
     canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
     app_state: &AppState,
-    ui_elements: &UiElements,
+    ui_elements: &mut UiElements,
+    font: &TextFont,
+    frame_stats: FrameStats,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Draw buttons
-    draw_buttons(canvas, app_state, ui_elements)?;
-    
-    // Draw sliders
-    draw_sliders(canvas, ui_elements)?;
-    
-    // Draw text elements
-    draw_text_elements(canvas, ui_elements)?;
-    
-    // Draw visualization
-    {
+    // Draw visualization first so controls can sit on top of it when fullscreen
+    let mode = {
         let state = app_state.visualisation_state.lock().unwrap();
-        draw_visualization(canvas, &app_state.frequencies, &state)?;
+        draw_visualization(canvas, ui_elements.visualisation_area, app_state, &state, &mut ui_elements.spectrogram_view)?;
+        state.get_mode()
+    };
+    if mode == VisualisationMode::Tuner {
+        draw_tuner_reading(canvas, ui_elements, font, &app_state.pitch)?;
+    }
+    if mode == VisualisationMode::Bars && app_state.config.visualisation.show_meters {
+        draw_meter_readouts(canvas, ui_elements, font, &app_state.meters)?;
+    }
+    // Taken before the controls are drawn, since they can overlap it
+    if ui_elements.screenshot_pending && app_state.config.ui.screenshot_visualisation_only {
+        take_screenshot(canvas, app_state, Some(ui_elements.visualisation_area));
+        ui_elements.screenshot_pending = false;
+    }
+
+    let alpha = (ui_elements.controls.opacity() * 255.0).round() as u8;
+    if alpha > 0 {
+        canvas.set_blend_mode(BlendMode::Blend);
+
+        // Draw buttons
+        draw_buttons(canvas, app_state, ui_elements, font, alpha)?;
+
+        // Draw sliders
+        draw_sliders(canvas, ui_elements, alpha)?;
+
+        // Draw text elements
+        draw_text_elements(canvas, ui_elements, font, alpha)?;
+
+        canvas.set_blend_mode(BlendMode::None);
+    }
+    // Overlays are left out of whole-window screenshots
+    if ui_elements.screenshot_pending {
+        take_screenshot(canvas, app_state, None);
+        ui_elements.screenshot_pending = false;
+    }
+
+    if ui_elements.show_help {
+        draw_help_overlay(canvas, &mut ui_elements.text_cache, font, &ui_elements.help_lines, ui_elements.theme)?;
+    }
+    if ui_elements.show_fps {
+        draw_fps_overlay(canvas, ui_elements, font, frame_stats)?;
     }
     
     Ok(())
 }
 
+// Saves what has been drawn so far, tagged with the track and position. A
+// failed screenshot is reported but does not stop the visualiser.
+fn take_screenshot(
+    canvas: &sdl2::render::Canvas<sdl2::video::Window>,
+    app_state: &AppState,
+    area: Option<Rect>,
+) {
+    let Some(directory) = app_state
+        .config
+        .ui
+        .screenshot_directory
+        .clone()
+        .or_else(default_screenshot_dir)
+    else {
+        eprintln!("Could not save screenshot: no folder to save it in");
+        return;
+    };
+
+    let mut text = vec![
+        ("Software", format!("music_visualiser {}", env!("CARGO_PKG_VERSION"))),
+        ("Creation Time", timestamp(SystemTime::now())),
+        ("Mode", app_state.visualisation_state.lock().unwrap().get_mode().label().to_string()),
+    ];
+    if let Some(path) = &app_state.file_path {
+        let track = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let state = app_state.playback_state.lock().unwrap();
+        let position = format!(
+            "{} / {}",
+            format_time(state.get_song_position()),
+            format_time(state.get_song_duration())
+        );
+        text.push(("Title", track));
+        text.push(("Position", position));
+    }
+
+    match save_screenshot(canvas, area, &directory, &text) {
+        Ok(path) => println!("Saved screenshot to {}", path.display()),
+        Err(e) => eprintln!("Could not save screenshot: {}", e),
+    }
+}
+
+// Note name, cents offset and frequency, centred at the top of the tuner
+fn draw_tuner_reading(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    ui_elements: &mut UiElements,
+    font: &TextFont,
+    detector: &PitchDetector,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Rounded to whole cents and tenths of a hertz so the cache stays small
+    let text = match detector.pitch {
+        Some(pitch) => format!("{}  {:+.0} cents  {:.1} Hz", pitch.note_name(), pitch.cents, pitch.frequency),
+        None => "No pitch".to_string(),
+    };
+    let texture = ui_elements.text_cache.get(font, &text, ui_elements.theme)?;
+    let query = texture.query();
+    let area = ui_elements.visualisation_area;
+    let target = Rect::new(
+        area.center().x() - query.width as i32 / 2,
+        area.y() + ui_elements.layout.px(10) as i32,
+        query.width,
+        query.height,
+    );
+    texture.set_alpha_mod(255);
+    canvas.copy(texture, None, Some(target))?;
+    Ok(())
+}
+
+// Momentary, short-term and integrated loudness and the highest true peak,
+// one line each under the meters, shrunk to fit if the panel is small
+fn draw_meter_readouts(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    ui_elements: &mut UiElements,
+    font: &TextFont,
+    meters: &LevelMeters,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let loudness = |value: Option<f32>| value.map_or("-".to_string(), |value| format!("{:.1}", value));
+    let true_peak = meters.true_peak_max();
+    let lines = [
+        format!("M {} LUFS", loudness(meters.loudness.momentary)),
+        format!("S {} LUFS", loudness(meters.loudness.short_term)),
+        format!("I {} LUFS", loudness(meters.loudness.integrated)),
+        format!("TP {} dBTP", if true_peak > 0.0 { format!("{:.1}", to_db(true_peak)) } else { "-".to_string() }),
+    ];
+    let area = meter_panel_layout(ui_elements.visualisation_area).readouts;
+    let line_height = area.height() / lines.len() as u32;
+    for (index, line) in lines.iter().enumerate() {
+        let texture = ui_elements.text_cache.get(font, line, ui_elements.theme)?;
+        let query = texture.query();
+        let scale = (line_height as f32 / query.height as f32)
+            .min(area.width() as f32 / query.width as f32)
+            .min(1.0);
+        let target = Rect::new(
+            area.x(),
+            area.y() + (line_height * index as u32) as i32,
+            ((query.width as f32 * scale) as u32).max(1),
+            ((query.height as f32 * scale) as u32).max(1),
+        );
+        texture.set_alpha_mod(255);
+        canvas.copy(texture, None, Some(target))?;
+    }
+    Ok(())
+}
+
+fn draw_fps_overlay(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    ui_elements: &mut UiElements,
+    font: &TextFont,
+    stats: FrameStats,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Stats only change twice a second, so this adds few entries to the cache
+    let text = format!("{:.0} fps  {:.1} ms (worst {:.1} ms)", stats.fps, stats.average_ms, stats.worst_ms);
+    let texture = ui_elements.text_cache.get(font, &text, ui_elements.theme)?;
+    let query = texture.query();
+    let margin = ui_elements.layout.px(8);
+    let padding = margin / 2;
+    let target = Rect::new(margin as i32, margin as i32, query.width, query.height);
+    let background = Rect::new(
+        (margin - padding) as i32,
+        (margin - padding) as i32,
+        query.width + padding * 2,
+        query.height + padding * 2,
+    );
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+    canvas.fill_rect(background)?;
+    canvas.set_blend_mode(BlendMode::None);
+    texture.set_alpha_mod(255);
+    canvas.copy(texture, None, Some(target))?;
+    Ok(())
+}
+
 fn draw_buttons(
     canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
     app_state: &AppState,
-    ui_elements: &UiElements,
+    ui_elements: &mut UiElements,
+    font: &TextFont,
+    alpha: u8,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Draw open button
-    canvas.set_draw_color(Color::RGB(0, 0, 0));
-    canvas.fill_rect(ui_elements.open_button.rect)?;
-    canvas.set_draw_color(Color::RGB(47, 198, 18));
-    canvas.draw_rect(ui_elements.open_button.rect)?;
-    canvas.copy(&ui_elements.open_button_texture, None, Some(ui_elements.open_button.text_rect))?;
-    
-    // Draw play button if file is loaded
-    if app_state.file_path.is_some() {
-        canvas.set_draw_color(Color::RGB(0, 0, 0));
-        canvas.fill_rect(ui_elements.play_button.rect)?;
-        canvas.set_draw_color(Color::RGB(47, 198, 18));
-        canvas.draw_rect(ui_elements.play_button.rect)?;
-        canvas.copy(&ui_elements.texture_play, None, Some(ui_elements.play_button.text_rect))?;
+    let theme = Color::RGBA(ui_elements.theme.r, ui_elements.theme.g, ui_elements.theme.b, alpha);
+    let mode = app_state.visualisation_state.lock().unwrap().get_mode();
+
+    let mut buttons = vec![
+        &mut ui_elements.open_button,
+        &mut ui_elements.play_button,
+        &mut ui_elements.randomiser_button,
+        &mut ui_elements.periodic_randomiser_button,
+        &mut ui_elements.palette_button,
+        &mut ui_elements.palette_stop_button,
+        &mut ui_elements.gradient_mode_button,
+        &mut ui_elements.interpolation_button,
+        &mut ui_elements.preset_previous_button,
+        &mut ui_elements.preset_next_button,
+        &mut ui_elements.preset_save_button,
+        &mut ui_elements.preset_cycle_button,
+        &mut ui_elements.mode_button,
+    ];
+    // Options for the current mode
+    match mode {
+        VisualisationMode::Oscilloscope => {
+            buttons.push(&mut ui_elements.scope_timebase_button);
+            buttons.push(&mut ui_elements.scope_channels_button);
+        }
+        VisualisationMode::Spectrogram => {
+            buttons.push(&mut ui_elements.spectrogram_colour_map_button);
+            buttons.push(&mut ui_elements.spectrogram_history_button);
+        }
+        VisualisationMode::Radial => {
+            buttons.push(&mut ui_elements.radial_mirror_button);
+            buttons.push(&mut ui_elements.radial_ring_button);
+        }
+        _ => {}
     }
-    
-    // Draw randomiser buttons
-    canvas.set_draw_color(Color::RGB(0, 0, 0));
-    canvas.fill_rect(ui_elements.randomiser_button.rect)?;
-    canvas.fill_rect(ui_elements.periodic_randomiser_button.rect)?;
-    canvas.set_draw_color(Color::RGB(47, 198, 18));
-    canvas.draw_rect(ui_elements.randomiser_button.rect)?;
-    canvas.draw_rect(ui_elements.periodic_randomiser_button.rect)?;
-    canvas.copy(&ui_elements.randomiser_button_texture, None, Some(ui_elements.randomiser_button.text_rect))?;
-    canvas.copy(&ui_elements.periodic_randomiser_button_texture, None, Some(ui_elements.periodic_randomiser_button.text_rect))?;
-    
+    let focus_margin = ui_elements.layout.px(3);
+    for button in buttons {
+        let state = button.state;
+        canvas.set_draw_color(widget_fill(&state, theme, alpha));
+        canvas.fill_rect(button.rect)?;
+        canvas.set_draw_color(widget_outline(&state, theme, alpha));
+        canvas.draw_rect(button.rect)?;
+        if state.focused {
+            canvas.draw_rect(inset(button.rect, focus_margin))?;
+        }
+        if button.text.is_empty() {
+            continue;
+        }
+        let texture = ui_elements.text_cache.get(font, &button.text, ui_elements.theme)?;
+        let query = texture.query();
+        button.update_text_position(query.width, query.height);
+        // Disabled buttons keep their label but at reduced strength
+        texture.set_alpha_mod(if state.disabled { alpha / 3 } else { alpha });
+        canvas.copy(texture, None, Some(button.text_rect))?;
+    }
+
     Ok(())
 }
 
+// Background of a widget, tinted with the theme while hovered or pressed
+fn widget_fill(state: &WidgetState, theme: Color, alpha: u8) -> Color {
+    let tint = |divisor: u8| Color::RGBA(theme.r / divisor, theme.g / divisor, theme.b / divisor, alpha);
+    if state.disabled {
+        Color::RGBA(0, 0, 0, alpha)
+    } else if state.pressed {
+        tint(3)
+    } else if state.hovered {
+        tint(6)
+    } else {
+        Color::RGBA(0, 0, 0, alpha)
+    }
+}
+
+fn widget_outline(state: &WidgetState, theme: Color, alpha: u8) -> Color {
+    if state.disabled {
+        Color::RGBA(90, 90, 90, alpha)
+    } else {
+        theme
+    }
+}
+
 fn draw_sliders(
     canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
     ui_elements: &UiElements,
+    alpha: u8,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let theme = Color::RGBA(ui_elements.theme.r, ui_elements.theme.g, ui_elements.theme.b, alpha);
     for slider in [&ui_elements.red_slider, &ui_elements.green_slider, &ui_elements.blue_slider].iter() {
         // Draw background
-        canvas.set_draw_color(Color::RGB(20, 20, 20));
+        let background = if slider.state.hovered || slider.state.pressed {
+            Color::RGBA(40, 40, 40, alpha)
+        } else {
+            Color::RGBA(20, 20, 20, alpha)
+        };
+        canvas.set_draw_color(background);
         canvas.fill_rect(slider.background_rect)?;
+        if slider.state.focused {
+            canvas.set_draw_color(theme);
+            canvas.draw_rect(slider.background_rect)?;
+        }
 
         // Draw track
         let mut gradient_rect = slider.rect.clone();
@@ -543,25 +1535,52 @@ fn draw_sliders(
             let progress = x as f32 / slider.rect.width() as f32;
             let color_value = (progress * 255.0) as u8;
             match slider.label.as_str() {
-                "R" => canvas.set_draw_color(Color::RGB(color_value, 0, 0)),
-                "G" => canvas.set_draw_color(Color::RGB(0, color_value, 0)),
-                "B" => canvas.set_draw_color(Color::RGB(0, 0, color_value)),
+                "R" => canvas.set_draw_color(Color::RGBA(color_value, 0, 0, alpha)),
+                "G" => canvas.set_draw_color(Color::RGBA(0, color_value, 0, alpha)),
+                "B" => canvas.set_draw_color(Color::RGBA(0, 0, color_value, alpha)),
                 _ => {}
             }
             gradient_rect.set_x(slider.rect.x + x as i32);
             canvas.fill_rect(gradient_rect)?;
         }
+
+        // Draw handle at the current value
+        let handle_width = ui_elements.layout.px(4);
+        let handle_x = slider.rect.x + (slider.value as f32 / 255.0 * slider.rect.width() as f32) as i32;
+        canvas.set_draw_color(Color::RGBA(255, 255, 255, alpha));
+        canvas.fill_rect(Rect::new(
+            handle_x - handle_width as i32 / 2,
+            slider.background_rect.y,
+            handle_width,
+            slider.background_rect.height(),
+        ))?;
     }
     Ok(())
 }
 
 fn draw_text_elements(
     canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
-    ui_elements: &UiElements,
+    ui_elements: &mut UiElements,
+    font: &TextFont,
+    alpha: u8,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    canvas.copy(&ui_elements.song_name_texture, None, Some(ui_elements.song_name_element.rect))?;
-    canvas.copy(&ui_elements.song_position_texture, None, Some(ui_elements.song_position_element.rect))?;
-    canvas.copy(&ui_elements.separator_texture, None, Some(ui_elements.separator_element.rect))?;
-    canvas.copy(&ui_elements.song_duration_texture, None, Some(ui_elements.song_duration.rect))?;
+    let elements = [
+        &mut ui_elements.song_name_element,
+        &mut ui_elements.preset_name_element,
+        &mut ui_elements.song_position_element,
+        &mut ui_elements.separator_element,
+        &mut ui_elements.song_duration,
+        &mut ui_elements.key_element,
+    ];
+    for element in elements {
+        if element.text.is_empty() {
+            continue;
+        }
+        let texture = ui_elements.text_cache.get(font, &element.text, ui_elements.theme)?;
+        let query = texture.query();
+        element.update_text_position(query.width, query.height);
+        texture.set_alpha_mod(alpha);
+        canvas.copy(texture, None, Some(element.rect))?;
+    }
     Ok(())
 }
@@ -1,39 +1,179 @@
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
 use std::path::PathBuf;
 use std::thread;
-use crate::structs::audio_analyzer::AudioAnalyzer;
+use crate::structs::audio_analyzer::{AudioAnalyzer, Transform};
 use crate::structs::audio_playback_state::AudioPlaybackState;
+use crate::structs::beat_detector::BeatDetector;
+use crate::structs::chroma::ChromaAnalyzer;
+use crate::structs::colour_randomiser::ColourRandomiser;
+use crate::structs::config::Config;
+use crate::structs::constant_q::ConstantQCache;
+use crate::structs::features::{AnalysisFrame, FeatureExtractor};
+use crate::structs::goniometer::Goniometer;
+use crate::structs::keybindings::Keymap;
+use crate::structs::level_meter::LevelMeters;
+use crate::structs::particle_system::ParticleSystem;
+use crate::structs::pitch_detector::{self, PitchDetector};
+use crate::structs::playlist::Playlist;
+use crate::structs::preset::PresetLibrary;
+use crate::structs::radial::RadialState;
+use crate::structs::sample_wrapper::SampleWrapper;
+use crate::structs::spectrogram::Spectrogram;
+use crate::structs::visualisation_mode::VisualisationMode;
 use crate::structs::visualisation_state::VisualisationState;
+use crate::structs::waveform_buffer::WaveformBuffer;
 
 pub struct AppState {
+    pub config: Config,
     pub analyzer: AudioAnalyzer,
+    // Constant-Q transforms for the sample rates seen so far, built in the background
+    pub constant_q: ConstantQCache,
     pub frequencies: Vec<f32>,
+    pub waveform: WaveformBuffer,
+    pub goniometer: Goniometer,
+    pub spectrogram: Spectrogram,
+    pub beat_detector: BeatDetector,
+    pub features: FeatureExtractor,
+    // Features of the most recent analysis frame
+    pub analysis: AnalysisFrame,
+    pub chroma: ChromaAnalyzer,
+    pub pitch: PitchDetector,
+    pub meters: LevelMeters,
+    pub radial: RadialState,
+    pub particle_system: ParticleSystem,
+    pub colour_randomiser: ColourRandomiser,
+    pub presets: PresetLibrary,
+    pub keymap: Keymap,
+    pub sample_rate: u32,
     pub playback_state: Arc<Mutex<AudioPlaybackState>>,
     pub visualisation_state: Arc<Mutex<VisualisationState>>,
     pub playback_handle: Option<thread::JoinHandle<()>>,
     pub file_path: Option<PathBuf>,
-    pub last_second: Instant,
+    pub playlist: Playlist,
+    // Output device for playback; set from the config or the command line
+    pub output_device: Option<String>,
+    // Seconds since the once-a-second updates last ran
+    pub second_timer: f32,
 }
 
 impl AppState {
-    pub fn new() -> Self {
+    pub fn new(config: Config) -> Self {
         let playback_state = Arc::new(Mutex::new(AudioPlaybackState::new()));
         let visualisation_state = Arc::new(Mutex::new(VisualisationState::new()));
         
+        {
+            let mut state = playback_state.lock().unwrap();
+            state.set_volume(config.audio.volume);
+        }
+        let presets = PresetLibrary::load();
         {
             let mut state = visualisation_state.lock().unwrap();
             state.set_initial_color();
+            if let Some(palette) = presets.palette_named(&config.visualisation.palette) {
+                state.set_palette(palette);
+            }
+            state.apply_config(&config);
+        }
+        let colour_randomiser = ColourRandomiser::new(None, config.visualisation.transition_duration);
+        let mut constant_q = ConstantQCache::new();
+        if config.analyzer.transform == Transform::ConstantQ {
+            // Most tracks use one of these, so they are usually ready before the first song
+            for sample_rate in [44100, 48000] {
+                constant_q.prepare(sample_rate, config.analyzer.bins_per_octave);
+            }
         }
 
         Self {
-            analyzer: AudioAnalyzer::new(1024),
-            frequencies: vec![0.0f32; 16],
+            analyzer: AudioAnalyzer::new(config.analyzer.fft_size),
+            constant_q,
+            frequencies: vec![0.0f32; config.analyzer.bar_count],
+            // Enough history for the longest oscilloscope timebase plus trigger search
+            waveform: WaveformBuffer::new(48000),
+            goniometer: Goniometer::new(),
+            spectrogram: Spectrogram::new(),
+            beat_detector: BeatDetector::new(),
+            features: FeatureExtractor::new(),
+            analysis: AnalysisFrame::default(),
+            chroma: ChromaAnalyzer::new(),
+            pitch: PitchDetector::new(),
+            meters: LevelMeters::new(),
+            radial: RadialState::new(),
+            particle_system: ParticleSystem::new(),
+            colour_randomiser,
+            presets,
+            keymap: Keymap::new(&config.keybindings),
+            sample_rate: 44100,
             playback_state,
             visualisation_state,
             playback_handle: None,
             file_path: None,
-            last_second: Instant::now(),
+            playlist: Playlist::new(),
+            output_device: config.audio.device.clone(),
+            second_timer: 0.0,
+            config,
         }
     }
-}
\ No newline at end of file
+
+    // Runs one block of samples through every analysis the visualisations use
+    pub fn analyse(&mut self, sample_wrapper: &SampleWrapper) {
+        self.sample_rate = sample_wrapper.sample_rate;
+        self.waveform.push(sample_wrapper);
+        self.goniometer.push(sample_wrapper);
+        self.meters.process(sample_wrapper);
+
+        let analysis_frame = self.waveform.latest_mono(self.analyzer.window_size());
+        let spectrum = self.analyzer.process(&analysis_frame);
+        self.spectrogram.push(&spectrum, sample_wrapper.sample_rate);
+        self.beat_detector.process(&spectrum, sample_wrapper.sample_rate);
+        self.analysis = self.features.process(&analysis_frame, &spectrum, sample_wrapper.sample_rate);
+        self.chroma.process(&spectrum, sample_wrapper.sample_rate, sample_wrapper.frames().len());
+        let elapsed = sample_wrapper.frames().len() as f32 / sample_wrapper.sample_rate as f32;
+        self.radial.update(self.beat_detector.energy, self.beat_detector.is_beat, elapsed);
+
+        let (bar_count, bar_gain, smoothing, mode) = {
+            let state = self.visualisation_state.lock().unwrap();
+            (state.get_bar_count(), state.get_bar_gain(), state.get_smoothing(), state.get_mode())
+        };
+        // Only the tuner shows the pitch, and the search is the costliest analysis
+        if mode == VisualisationMode::Tuner {
+            let window = self.waveform.latest_mono(pitch_detector::window_size(sample_wrapper.sample_rate));
+            self.pitch.process(&window, sample_wrapper.sample_rate, elapsed);
+        }
+        // With the constant-Q transform the bars are spaced by pitch rather
+        // than frequency. The FFT stands in while the transform is being built.
+        let bands = match self.config.analyzer.transform {
+            Transform::Fft => spectrum,
+            Transform::ConstantQ => self.constant_q_bins(sample_wrapper.sample_rate).unwrap_or(spectrum),
+        };
+        let bands_len = bands.len();
+        self.frequencies.resize(bar_count, 0.0);
+        for i in 0..bar_count {
+            let start = i * bands_len / bar_count;
+            let end = ((i + 1) * bands_len / bar_count).max(start + 1).min(bands_len);
+            let peak = bands[start.min(end)..end]
+                .iter()
+                .fold(0.0, |max, &x| f32::max(max, x));
+            let level = (peak * bar_gain).min(1.0);
+            // Bars jump up immediately and fall back at the smoothing rate
+            let previous = self.frequencies[i];
+            self.frequencies[i] = level.max(previous * smoothing + level * (1.0 - smoothing));
+        }
+    }
+
+    // Constant-Q magnitudes of the newest audio, or None while the transform
+    // for this sample rate is still being built
+    fn constant_q_bins(&mut self, sample_rate: u32) -> Option<Vec<f32>> {
+        let constant_q = self.constant_q.get(sample_rate, self.config.analyzer.bins_per_octave)?;
+        // The lowest bins look further back than the waveform normally keeps
+        self.waveform.ensure_capacity(constant_q.window_size());
+        Some(constant_q.process(&self.waveform.latest_mono(constant_q.window_size())))
+    }
+
+    // Offline rendering waits for the transform so every frame is analysed the same way
+    pub fn wait_for_constant_q(&mut self, sample_rate: u32) {
+        if self.config.analyzer.transform == Transform::ConstantQ {
+            let constant_q = self.constant_q.wait(sample_rate, self.config.analyzer.bins_per_octave);
+            self.waveform.ensure_capacity(constant_q.window_size());
+        }
+    }
+}
@@ -1,4 +1,22 @@
 use rustfft::{FftPlanner, num_complex::Complex};
+use serde::{Deserialize, Serialize};
+
+// FFT sizes accepted from presets and the config file
+pub const MIN_FFT_SIZE: usize = 256;
+pub const MAX_FFT_SIZE: usize = 16384;
+
+pub fn is_valid_fft_size(size: usize) -> bool {
+    size.is_power_of_two() && (MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&size)
+}
+
+// What the bars and radial view are computed from. The other analyses always
+// use the FFT, since they rely on its evenly spaced bins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    Fft,
+    ConstantQ,
+}
 
 pub struct AudioAnalyzer {
     fft: std::sync::Arc<dyn rustfft::Fft<f32>>,
@@ -18,6 +36,10 @@ impl AudioAnalyzer {
         }
     }
 
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
     pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
         // Apply window function and convert to complex
         for (i, &sample) in samples.iter().take(self.window_size).enumerate() {
@@ -2,8 +2,12 @@ pub struct AudioPlaybackState {
     pub is_playing: bool,
     pub should_stop: bool,
     pub selected_song: Option<String>,
+    // 0 when the file does not say how long it is
     pub song_duration: u32,
     pub song_position: u32,
+    pub volume: f32,
+    // Position in seconds the playback thread should jump to
+    pub seek_request: Option<u32>,
 }
 
 impl AudioPlaybackState {
@@ -14,6 +18,8 @@ impl AudioPlaybackState {
             selected_song: None,
             song_duration: 0,
             song_position: 0,
+            volume: 1.0,
+            seek_request: None,
         }
     }
 }
@@ -38,4 +44,45 @@ impl AudioPlaybackState {
     pub fn get_song_position(&self) -> u32 {
         self.song_position
     }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    pub fn get_volume(&self) -> f32 {
+        self.volume
+    }
+
+    // Seeks past the end are held at the end, unless the length is unknown
+    pub fn request_seek(&mut self, seconds: u32) {
+        let seconds = if self.song_duration > 0 { seconds.min(self.song_duration) } else { seconds };
+        self.seek_request = Some(seconds);
+    }
+
+    pub fn take_seek_request(&mut self) -> Option<u32> {
+        self.seek_request.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeks_are_clamped_to_a_known_duration() {
+        let mut state = AudioPlaybackState::new();
+        state.set_song_duration(120);
+        state.request_seek(150);
+        assert_eq!(state.take_seek_request(), Some(120));
+        state.request_seek(30);
+        assert_eq!(state.take_seek_request(), Some(30));
+        assert_eq!(state.take_seek_request(), None);
+    }
+
+    #[test]
+    fn seeks_are_not_clamped_when_the_duration_is_unknown() {
+        let mut state = AudioPlaybackState::new();
+        state.request_seek(45);
+        assert_eq!(state.take_seek_request(), Some(45));
+    }
 }
@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+
+// Upper edge of the band used for beat detection
+const BASS_CUTOFF_HZ: f32 = 150.0;
+// Roughly one second of analysis frames at typical packet sizes
+const HISTORY_LENGTH: usize = 43;
+// How far above the recent average the bass energy must jump to count as a beat
+const BEAT_THRESHOLD: f32 = 1.4;
+const MIN_BEAT_INTERVAL_FRAMES: u32 = 8;
+
+// Energy-based beat detector working on the bass end of the spectrum
+pub struct BeatDetector {
+    history: VecDeque<f32>,
+    frames_since_beat: u32,
+    // Bass energy relative to the recent peak, 0.0..=1.0
+    pub energy: f32,
+    pub is_beat: bool,
+    pub beat_count: u64,
+}
+
+impl BeatDetector {
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+            frames_since_beat: 0,
+            energy: 0.0,
+            is_beat: false,
+            beat_count: 0,
+        }
+    }
+
+    pub fn process(&mut self, spectrum: &[f32], sample_rate: u32) {
+        let fft_size = spectrum.len() * 2;
+        let cutoff_bin = ((BASS_CUTOFF_HZ * fft_size as f32 / sample_rate as f32) as usize)
+            .clamp(1, spectrum.len().max(1));
+        let bass_energy: f32 = spectrum.iter().take(cutoff_bin).map(|m| m * m).sum();
+
+        let average = if self.history.is_empty() {
+            bass_energy
+        } else {
+            self.history.iter().sum::<f32>() / self.history.len() as f32
+        };
+        let peak = self.history.iter().copied().fold(bass_energy, f32::max);
+
+        self.frames_since_beat = self.frames_since_beat.saturating_add(1);
+        self.is_beat = self.history.len() == HISTORY_LENGTH
+            && bass_energy > average * BEAT_THRESHOLD
+            && self.frames_since_beat >= MIN_BEAT_INTERVAL_FRAMES;
+        if self.is_beat {
+            self.frames_since_beat = 0;
+            self.beat_count += 1;
+        }
+        self.energy = if peak > f32::EPSILON { bass_energy / peak } else { 0.0 };
+
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(bass_energy);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
@@ -1,4 +1,6 @@
-use sdl2::rect::{Point, Rect};
+use sdl2::keyboard::Keycode;
+use sdl2::rect::Rect;
+use crate::structs::widget::{Widget, WidgetEvent, WidgetResponse, WidgetState};
 
 pub const BUTTON_WIDTH: u32 = 200;
 pub const BUTTON_HEIGHT: u32 = 50;
@@ -7,6 +9,7 @@ pub struct Button {
     pub rect: Rect,
     pub text: String,
     pub text_rect: Rect,
+    pub state: WidgetState,
 }
 
 impl Button {
@@ -19,7 +22,7 @@ impl Button {
             rect,
             text: text.to_string(),
             text_rect,
-            
+            state: WidgetState::default(),
         }
     }
 
@@ -29,7 +32,32 @@ impl Button {
         self.text_rect = Rect::new(text_x, text_y, text_width, text_height);
     }
 
-    pub fn is_clicked(&self, point: Point) -> bool {
-        self.rect.contains_point(point)
+    // Moves the button, keeping its text centred
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
+        self.update_text_position(self.text_rect.width(), self.text_rect.height());
+    }
+}
+
+impl Widget for Button {
+    fn bounds(&self) -> Rect {
+        self.rect
+    }
+
+    fn state(&self) -> &WidgetState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut WidgetState {
+        &mut self.state
+    }
+
+    fn handle_event(&mut self, event: &WidgetEvent) -> WidgetResponse {
+        match *event {
+            // Releasing outside the button cancels the click
+            WidgetEvent::MouseUp(point) if self.hit_test(point) => WidgetResponse::Clicked,
+            WidgetEvent::Key(Keycode::Return | Keycode::KpEnter) => WidgetResponse::Clicked,
+            _ => WidgetResponse::None,
+        }
     }
 }
@@ -0,0 +1,40 @@
+use symphonia::core::audio::Channels;
+
+// Describes how the interleaved samples in a `SampleWrapper` are arranged.
+// Symphonia interleaves channels in bit order, so when both front channels
+// are present left is always index 0 and right index 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelLayout {
+    Mono,
+    Stereo,
+    Surround { channels: usize, has_front_pair: bool },
+}
+
+impl ChannelLayout {
+    pub fn from_channels(channels: Channels) -> Self {
+        let count = channels.count();
+        let has_front_pair = channels.contains(Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+        match count {
+            0 | 1 => ChannelLayout::Mono,
+            2 if has_front_pair => ChannelLayout::Stereo,
+            _ => ChannelLayout::Surround { channels: count, has_front_pair },
+        }
+    }
+
+    pub fn channel_count(&self) -> usize {
+        match self {
+            ChannelLayout::Mono => 1,
+            ChannelLayout::Stereo => 2,
+            ChannelLayout::Surround { channels, .. } => *channels,
+        }
+    }
+
+    // Indices of the left and right channels within a frame, if the layout has them
+    pub fn stereo_pair(&self) -> Option<(usize, usize)> {
+        match self {
+            ChannelLayout::Mono => None,
+            ChannelLayout::Stereo => Some((0, 1)),
+            ChannelLayout::Surround { has_front_pair, .. } => has_front_pair.then_some((0, 1)),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+
+pub const PITCH_CLASSES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+// Columns kept for the chromagram view
+pub const CHROMAGRAM_HISTORY: usize = 256;
+
+// Bins outside this range are mostly percussion and harmonics, which blur the
+// pitch classes
+const MIN_FREQUENCY: f32 = 55.0;
+const MAX_FREQUENCY: f32 = 5000.0;
+// Seconds of audio the key is estimated over
+const KEY_WINDOW_SECONDS: f32 = 15.0;
+// How quickly the tuning estimate follows new frames
+const TUNING_SMOOTHING: f32 = 0.02;
+// Spectral peaks quieter than this share of the loudest are ignored when
+// estimating the tuning
+const PEAK_THRESHOLD: f32 = 0.1;
+
+// Krumhansl-Kessler key profiles, starting from the tonic
+const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Key {
+    // Pitch class of the tonic, 0 for C
+    pub tonic: usize,
+    pub minor: bool,
+    // Correlation with the key's profile, -1.0..=1.0
+    pub confidence: f32,
+}
+
+impl Key {
+    pub fn label(&self) -> String {
+        format!("{} {}", PITCH_CLASSES[self.tonic], if self.minor { "minor" } else { "major" })
+    }
+}
+
+// Turns spectra into chroma vectors and keeps the rolling history used for the
+// chromagram view and key estimate
+pub struct ChromaAnalyzer {
+    // Offset of the music's tuning from A440, in cents
+    pub tuning_cents: f32,
+    // Latest chroma vector, normalised so the strongest pitch class is 1.0
+    pub chroma: [f32; 12],
+    pub history: VecDeque<[f32; 12]>,
+    // Chroma vectors in the key window, with the number of audio frames each covers
+    window: VecDeque<([f32; 12], usize)>,
+    window_frames: usize,
+    pub key: Option<Key>,
+}
+
+impl ChromaAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            tuning_cents: 0.0,
+            chroma: [0.0; 12],
+            history: VecDeque::with_capacity(CHROMAGRAM_HISTORY),
+            window: VecDeque::new(),
+            window_frames: 0,
+            key: None,
+        }
+    }
+
+    // `frames` is how many audio frames arrived since the previous spectrum
+    pub fn process(&mut self, spectrum: &[f32], sample_rate: u32, frames: usize) {
+        if let Some(offset) = estimate_tuning(spectrum, sample_rate) {
+            // Offsets wrap at a semitone, so move the estimate along the shortest way
+            let difference = wrap_cents(offset - self.tuning_cents);
+            self.tuning_cents = wrap_cents(self.tuning_cents + difference * TUNING_SMOOTHING);
+        }
+        self.chroma = chroma_vector(spectrum, sample_rate, self.tuning_cents);
+
+        if self.history.len() == CHROMAGRAM_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.chroma);
+
+        self.window.push_back((self.chroma, frames));
+        self.window_frames += frames;
+        let window_limit = (KEY_WINDOW_SECONDS * sample_rate as f32) as usize;
+        while self.window_frames > window_limit {
+            match self.window.pop_front() {
+                Some((_, count)) => self.window_frames -= count,
+                None => break,
+            }
+        }
+
+        // Weighting by duration keeps the estimate independent of packet size
+        let mut total = [0.0; 12];
+        for (chroma, count) in &self.window {
+            for (sum, value) in total.iter_mut().zip(chroma) {
+                *sum += value * *count as f32;
+            }
+        }
+        self.key = estimate_key(&total);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+// Fractional MIDI note number of a frequency, 69 being A440
+fn midi_note(frequency: f32) -> f32 {
+    69.0 + 12.0 * (frequency / 440.0).log2()
+}
+
+fn wrap_cents(cents: f32) -> f32 {
+    (cents + 50.0).rem_euclid(100.0) - 50.0
+}
+
+// Energy in each pitch class, folding every octave together. The spectrum is
+// `AudioAnalyzer` output, so values are squared twice to get back to energy.
+pub fn chroma_vector(spectrum: &[f32], sample_rate: u32, tuning_cents: f32) -> [f32; 12] {
+    let mut chroma = [0.0; 12];
+    let bin_width = sample_rate as f32 / (spectrum.len() * 2) as f32;
+    for (bin, value) in spectrum.iter().enumerate().skip(1) {
+        let frequency = bin as f32 * bin_width;
+        if !(MIN_FREQUENCY..=MAX_FREQUENCY).contains(&frequency) {
+            continue;
+        }
+        let note = (midi_note(frequency) - tuning_cents / 100.0).round() as i32;
+        chroma[note.rem_euclid(12) as usize] += value.powi(4);
+    }
+    let strongest = chroma.iter().copied().fold(0.0, f32::max);
+    if strongest > f32::EPSILON {
+        for value in chroma.iter_mut() {
+            *value /= strongest;
+        }
+    }
+    chroma
+}
+
+// How far the spectrum's peaks sit from equal-tempered pitches at A440, in
+// cents (-50.0..50.0), or None if there are no clear peaks. Peak positions
+// are refined by parabolic interpolation and averaged on a circle, since an
+// offset of +49 cents is next to one of -49.
+pub fn estimate_tuning(spectrum: &[f32], sample_rate: u32) -> Option<f32> {
+    let bin_width = sample_rate as f32 / (spectrum.len() * 2) as f32;
+    let loudest = spectrum.iter().copied().fold(0.0, f32::max);
+    if loudest <= f32::EPSILON {
+        return None;
+    }
+    let (mut x, mut y) = (0.0, 0.0);
+    for bin in 1..spectrum.len().saturating_sub(1) {
+        let (before, here, after) = (spectrum[bin - 1], spectrum[bin], spectrum[bin + 1]);
+        if here < loudest * PEAK_THRESHOLD || here <= before || here < after {
+            continue;
+        }
+        let curvature = before - 2.0 * here + after;
+        let offset = if curvature < 0.0 { 0.5 * (before - after) / curvature } else { 0.0 };
+        let frequency = (bin as f32 + offset) * bin_width;
+        if !(MIN_FREQUENCY..=MAX_FREQUENCY).contains(&frequency) {
+            continue;
+        }
+        let note = midi_note(frequency);
+        let angle = (note - note.round()) * std::f32::consts::TAU;
+        x += here * angle.cos();
+        y += here * angle.sin();
+    }
+    if x == 0.0 && y == 0.0 {
+        return None;
+    }
+    Some(y.atan2(x) / std::f32::consts::TAU * 100.0)
+}
+
+// Picks the major or minor key whose Krumhansl-Schmuckler profile correlates
+// best with the chroma vector
+pub fn estimate_key(chroma: &[f32; 12]) -> Option<Key> {
+    if chroma.iter().all(|&value| value <= f32::EPSILON) {
+        return None;
+    }
+    let mut best: Option<Key> = None;
+    for tonic in 0..12 {
+        for (profile, minor) in [(&MAJOR_PROFILE, false), (&MINOR_PROFILE, true)] {
+            let rotated: Vec<f32> = (0..12).map(|pitch| profile[(pitch + 12 - tonic) % 12]).collect();
+            let confidence = correlation(chroma, &rotated);
+            if best.is_none_or(|key| confidence > key.confidence) {
+                best = Some(Key { tonic, minor, confidence });
+            }
+        }
+    }
+    best
+}
+
+// Pearson correlation coefficient
+fn correlation(a: &[f32], b: &[f32]) -> f32 {
+    let mean_a = a.iter().sum::<f32>() / a.len() as f32;
+    let mean_b = b.iter().sum::<f32>() / b.len() as f32;
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a).powi(2);
+        variance_b += (y - mean_b).powi(2);
+    }
+    if variance_a <= f32::EPSILON || variance_b <= f32::EPSILON {
+        return 0.0;
+    }
+    covariance / (variance_a * variance_b).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::audio_analyzer::AudioAnalyzer;
+    use crate::structs::test_signals::{sines, SAMPLE_RATE};
+
+    const FFT_SIZE: usize = 8192;
+
+    fn spectrum_of(frequencies: &[f32]) -> Vec<f32> {
+        let amplitude = 1.0 / frequencies.len() as f32;
+        let partials: Vec<(f32, f32)> = frequencies.iter().map(|&frequency| (frequency, amplitude)).collect();
+        AudioAnalyzer::new(FFT_SIZE).process(&sines(&partials, FFT_SIZE))
+    }
+
+    fn note_frequency(midi: f32) -> f32 {
+        440.0 * 2f32.powf((midi - 69.0) / 12.0)
+    }
+
+    #[test]
+    fn a440_lands_in_the_a_bin() {
+        let chroma = chroma_vector(&spectrum_of(&[440.0]), SAMPLE_RATE, 0.0);
+        let strongest = (0..12).max_by(|&a, &b| chroma[a].total_cmp(&chroma[b])).unwrap();
+        assert_eq!(PITCH_CLASSES[strongest], "A");
+        assert_eq!(chroma[strongest], 1.0);
+    }
+
+    #[test]
+    fn tuning_offset_is_measured_in_cents() {
+        // An A a quarter tone sharp is 25 cents above A440
+        let offset = estimate_tuning(&spectrum_of(&[note_frequency(69.25)]), SAMPLE_RATE).unwrap();
+        assert!((offset - 25.0).abs() < 3.0, "offset {}", offset);
+        let offset = estimate_tuning(&spectrum_of(&[note_frequency(60.0 - 0.1)]), SAMPLE_RATE).unwrap();
+        assert!((offset + 10.0).abs() < 3.0, "offset {}", offset);
+    }
+
+    #[test]
+    fn triads_give_their_key() {
+        // C major: C E G, A minor: A C E
+        let c_major = chroma_vector(&spectrum_of(&[261.63, 329.63, 392.0]), SAMPLE_RATE, 0.0);
+        assert_eq!(estimate_key(&c_major).unwrap().label(), "C major");
+        let a_minor = chroma_vector(&spectrum_of(&[220.0, 261.63, 329.63]), SAMPLE_RATE, 0.0);
+        assert_eq!(estimate_key(&a_minor).unwrap().label(), "A minor");
+    }
+
+    #[test]
+    fn key_profiles_recognise_every_tonic() {
+        for tonic in 0..12 {
+            for (profile, minor) in [(&MAJOR_PROFILE, false), (&MINOR_PROFILE, true)] {
+                let mut chroma = [0.0; 12];
+                for (pitch, value) in chroma.iter_mut().enumerate() {
+                    *value = profile[(pitch + 12 - tonic) % 12];
+                }
+                let key = estimate_key(&chroma).unwrap();
+                assert_eq!((key.tonic, key.minor), (tonic, minor));
+                assert!((key.confidence - 1.0).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn silence_has_no_key() {
+        assert_eq!(estimate_key(&[0.0; 12]), None);
+        assert_eq!(estimate_tuning(&vec![0.0; FFT_SIZE / 2], SAMPLE_RATE), None);
+    }
+}
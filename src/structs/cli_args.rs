@@ -0,0 +1,204 @@
+use std::path::PathBuf;
+use crate::structs::visualisation_mode::VisualisationMode;
+
+pub const USAGE: &str = "Usage: music_visualiser [OPTIONS] [FILE|DIRECTORY|PLAYLIST.m3u]...
+
+Options:
+      --autoplay          Start playing the first track straight away
+      --mode <MODE>       bars, oscilloscope, vectorscope, spectrogram, radial,
+                          particles, chromagram or tuner
+      --preset <NAME>     Start with the named preset
+      --size <WxH>        Window size, e.g. 1920x1080
+      --fullscreen        Start fullscreen
+      --device <NAME>     Audio output device (see --list-devices)
+      --headless          Play the tracks without opening a window
+      --render <PATH>     Render one track to a video (.mp4, .mkv, .mov, .webm,
+                          .avi, needs ffmpeg) or to a folder of PNG frames
+      --fps <N>           Frame rate for --render (default 60)
+      --export-features <PATH>
+                          Write per-frame analysis of the first track to a .csv,
+                          .jsonl or .npy file
+      --list-devices      List audio output devices and exit
+  -h, --help              Show this message and exit
+  -V, --version           Show the version and exit";
+
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    pub paths: Vec<PathBuf>,
+    pub autoplay: bool,
+    pub mode: Option<VisualisationMode>,
+    pub preset: Option<String>,
+    pub window_size: Option<(u32, u32)>,
+    pub fullscreen: bool,
+    pub device: Option<String>,
+    pub headless: bool,
+    pub render: Option<PathBuf>,
+    pub fps: Option<u32>,
+    pub export_features: Option<PathBuf>,
+    pub list_devices: bool,
+    pub show_help: bool,
+    pub show_version: bool,
+}
+
+impl CliArgs {
+    // Parses the arguments after the program name. Options take their value
+    // either as the next argument or after `=`; everything after `--` is a path.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut parsed = CliArgs::default();
+        let mut args = args.into_iter();
+        let mut only_paths = false;
+
+        while let Some(arg) = args.next() {
+            if only_paths || !arg.starts_with('-') || arg == "-" {
+                parsed.paths.push(PathBuf::from(arg));
+                continue;
+            }
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            let mut value = |name: &str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} needs a value", name))
+            };
+
+            let is_flag = matches!(
+                name.as_str(),
+                "--" | "--autoplay" | "--fullscreen" | "--headless" | "--list-devices" | "-h" | "--help" | "-V" | "--version"
+            );
+            if is_flag && inline_value.is_some() {
+                return Err(format!("{} does not take a value", name));
+            }
+
+            match name.as_str() {
+                "--" => only_paths = true,
+                "--autoplay" => parsed.autoplay = true,
+                "--mode" => {
+                    let mode = value(&name)?;
+                    parsed.mode = Some(
+                        VisualisationMode::from_name(&mode).ok_or_else(|| format!("unknown mode '{}'", mode))?,
+                    );
+                }
+                "--preset" => parsed.preset = Some(value(&name)?),
+                "--size" => parsed.window_size = Some(parse_size(&value(&name)?)?),
+                "--fullscreen" => parsed.fullscreen = true,
+                "--device" => parsed.device = Some(value(&name)?),
+                "--headless" => parsed.headless = true,
+                "--render" => parsed.render = Some(PathBuf::from(value(&name)?)),
+                "--fps" => parsed.fps = Some(parse_fps(&value(&name)?)?),
+                "--export-features" => parsed.export_features = Some(PathBuf::from(value(&name)?)),
+                "--list-devices" => parsed.list_devices = true,
+                "-h" | "--help" => parsed.show_help = true,
+                "-V" | "--version" => parsed.show_version = true,
+                _ => return Err(format!("unknown option '{}'", name)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn parse_size(text: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid window size '{}', expected WIDTHxHEIGHT", text);
+    let (width, height) = text.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width: u32 = width.trim().parse().map_err(|_| invalid())?;
+    let height: u32 = height.trim().parse().map_err(|_| invalid())?;
+    if width < 640 || height < 480 {
+        return Err("the window must be at least 640x480".to_string());
+    }
+    Ok((width, height))
+}
+
+fn parse_fps(text: &str) -> Result<u32, String> {
+    match text.trim().parse::<u32>() {
+        Ok(fps) if (1..=240).contains(&fps) => Ok(fps),
+        _ => Err(format!("invalid frame rate '{}', expected 1 to 240", text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, String> {
+        CliArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn options_and_paths() {
+        let args = parse(&[
+            "--autoplay",
+            "song.mp3",
+            "--mode=radial",
+            "--size",
+            "1920x1080",
+            "--fps=30",
+            "--preset",
+            "Fire bars",
+            "-",
+            "--",
+            "--fullscreen",
+        ])
+        .unwrap();
+        assert!(args.autoplay);
+        assert_eq!(args.mode, Some(VisualisationMode::Radial));
+        assert_eq!(args.window_size, Some((1920, 1080)));
+        assert_eq!(args.fps, Some(30));
+        assert_eq!(args.preset.as_deref(), Some("Fire bars"));
+        // Anything after `--` is a path, even if it looks like an option
+        assert!(!args.fullscreen);
+        assert_eq!(args.paths, [PathBuf::from("song.mp3"), PathBuf::from("-"), PathBuf::from("--fullscreen")]);
+
+        assert!(parse(&[]).unwrap().paths.is_empty());
+        assert!(parse(&["-h"]).unwrap().show_help);
+        assert!(parse(&["--version"]).unwrap().show_version);
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        // Each of these makes main exit with status 2
+        let cases: [(&[&str], &str); 10] = [
+            (&["--bogus"], "unknown option '--bogus'"),
+            (&["-x"], "unknown option '-x'"),
+            (&["--mode"], "--mode needs a value"),
+            (&["--mode", "waterfall"], "unknown mode 'waterfall'"),
+            (&["--size=800"], "invalid window size '800'"),
+            (&["--size", "320x240"], "at least 640x480"),
+            (&["--fps", "0"], "invalid frame rate '0'"),
+            (&["--fps=fast"], "invalid frame rate 'fast'"),
+            (&["--render"], "--render needs a value"),
+            (&["--fullscreen=no"], "--fullscreen does not take a value"),
+        ];
+        for (args, expected) in cases {
+            let error = parse(args).unwrap_err();
+            assert!(error.contains(expected), "{:?} gave '{}'", args, error);
+        }
+    }
+
+    #[test]
+    fn window_sizes() {
+        let cases = [
+            ("1280x720", Ok((1280, 720))),
+            ("1920X1080", Ok((1920, 1080))),
+            (" 640 x 480 ", Ok((640, 480))),
+            ("639x480", Err(())),
+            ("1280", Err(())),
+            ("x720", Err(())),
+            ("1280x", Err(())),
+            ("-1280x720", Err(())),
+            ("1280x720x2", Err(())),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_size(text).map_err(|_| ()), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn frame_rates() {
+        let cases = [("1", Ok(1)), ("60", Ok(60)), (" 240 ", Ok(240)), ("0", Err(())), ("241", Err(())), ("29.97", Err(())), ("", Err(()))];
+        for (text, expected) in cases {
+            assert_eq!(parse_fps(text).map_err(|_| ()), expected, "{:?}", text);
+        }
+    }
+}
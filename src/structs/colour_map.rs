@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use sdl2::pixels::Color;
+
+// Nine evenly spaced samples of the matplotlib maps, interpolated linearly
+const VIRIDIS: [(u8, u8, u8); 9] = [
+    (0x44, 0x01, 0x54), (0x47, 0x2c, 0x7a), (0x3b, 0x51, 0x8b),
+    (0x2c, 0x71, 0x8e), (0x21, 0x90, 0x8d), (0x27, 0xad, 0x81),
+    (0x5c, 0xc8, 0x63), (0xaa, 0xdc, 0x32), (0xfd, 0xe7, 0x25),
+];
+const MAGMA: [(u8, u8, u8); 9] = [
+    (0x00, 0x00, 0x04), (0x1c, 0x10, 0x44), (0x4f, 0x12, 0x7b),
+    (0x81, 0x25, 0x81), (0xb5, 0x36, 0x7a), (0xe5, 0x50, 0x64),
+    (0xfb, 0x87, 0x61), (0xfe, 0xc2, 0x87), (0xfc, 0xfd, 0xbf),
+];
+const INFERNO: [(u8, u8, u8); 9] = [
+    (0x00, 0x00, 0x04), (0x1f, 0x0c, 0x48), (0x55, 0x0f, 0x6d),
+    (0x88, 0x22, 0x6a), (0xba, 0x36, 0x55), (0xe3, 0x59, 0x33),
+    (0xf9, 0x8e, 0x09), (0xf8, 0xc9, 0x32), (0xfc, 0xff, 0xa4),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColourMap {
+    Viridis,
+    Magma,
+    Inferno,
+    Greyscale,
+    // Black through the current bar colour to white
+    Theme,
+}
+
+impl ColourMap {
+    pub const ALL: [ColourMap; 5] = [
+        ColourMap::Viridis,
+        ColourMap::Magma,
+        ColourMap::Inferno,
+        ColourMap::Greyscale,
+        ColourMap::Theme,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&map| map == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColourMap::Viridis => "Viridis",
+            ColourMap::Magma => "Magma",
+            ColourMap::Inferno => "Inferno",
+            ColourMap::Greyscale => "Greyscale",
+            ColourMap::Theme => "Theme",
+        }
+    }
+
+    // Maps `value` in 0.0..=1.0 to a colour; `theme` is only used by `ColourMap::Theme`
+    pub fn colour_at(self, value: f32, theme: Color) -> Color {
+        let value = value.clamp(0.0, 1.0);
+        match self {
+            ColourMap::Viridis => interpolate_stops(&VIRIDIS, value),
+            ColourMap::Magma => interpolate_stops(&MAGMA, value),
+            ColourMap::Inferno => interpolate_stops(&INFERNO, value),
+            ColourMap::Greyscale => {
+                let level = (value * 255.0) as u8;
+                Color::RGB(level, level, level)
+            }
+            ColourMap::Theme => {
+                let stops = [(0, 0, 0), (theme.r, theme.g, theme.b), (255, 255, 255)];
+                interpolate_stops(&stops, value)
+            }
+        }
+    }
+}
+
+fn interpolate_stops(stops: &[(u8, u8, u8)], value: f32) -> Color {
+    let position = value * (stops.len() - 1) as f32;
+    let index = (position as usize).min(stops.len() - 2);
+    let fraction = position - index as f32;
+    let (r0, g0, b0) = stops[index];
+    let (r1, g1, b1) = stops[index + 1];
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+    Color::RGB(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+}
@@ -0,0 +1,234 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sdl2::pixels::Color;
+use crate::structs::colour_space::{oklch_in_gamut, oklch_to_rgb, relative_luminance};
+use crate::structs::palette::{interpolate, Interpolation};
+
+// WCAG contrast ratio every generated colour must reach against black
+const MIN_CONTRAST: f32 = 4.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HarmonyRule {
+    Complementary,
+    Analogous,
+    Triadic,
+    SplitComplementary,
+}
+
+impl HarmonyRule {
+    pub const ALL: [HarmonyRule; 4] = [
+        HarmonyRule::Complementary,
+        HarmonyRule::Analogous,
+        HarmonyRule::Triadic,
+        HarmonyRule::SplitComplementary,
+    ];
+
+    // Hue offsets in degrees from the base hue
+    fn offsets(self) -> &'static [f32] {
+        match self {
+            HarmonyRule::Complementary => &[0.0, 180.0],
+            HarmonyRule::Analogous => &[-30.0, 0.0, 30.0],
+            HarmonyRule::Triadic => &[0.0, 120.0, 240.0],
+            HarmonyRule::SplitComplementary => &[0.0, 150.0, 210.0],
+        }
+    }
+}
+
+struct Transition {
+    from: Vec<Color>,
+    to: Vec<Color>,
+    elapsed: f32,
+}
+
+// Generates colour schemes from harmony rules and animates the palette towards them
+pub struct ColourRandomiser {
+    rng: StdRng,
+    transition: Option<Transition>,
+    // Length of each colour change in seconds
+    pub transition_duration: f32,
+}
+
+impl ColourRandomiser {
+    // Passing a seed makes every generated scheme reproducible. Each colour
+    // change takes `transition_duration` seconds, or is instant at zero
+    pub fn new(seed: Option<u64>, transition_duration: f32) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+        Self {
+            rng,
+            transition: None,
+            transition_duration: transition_duration.max(0.0),
+        }
+    }
+
+    // Picks a random rule and base hue and returns `count` colours following it
+    pub fn generate(&mut self, count: usize) -> Vec<Color> {
+        let rule = HarmonyRule::ALL[self.rng.random_range(0..HarmonyRule::ALL.len())];
+        let base_hue = self.rng.random_range(0.0..360.0);
+        self.generate_with_rule(rule, base_hue, count)
+    }
+
+    pub fn generate_with_rule(&mut self, rule: HarmonyRule, base_hue: f32, count: usize) -> Vec<Color> {
+        let offsets = rule.offsets();
+        (0..count)
+            .map(|index| {
+                let hue = (base_hue + offsets[index % offsets.len()]).rem_euclid(360.0);
+                // Later laps round the rule step the lightness so repeated hues stay distinct
+                let lap = (index / offsets.len()) as f32;
+                let lightness = self.rng.random_range(0.68..0.82) - lap * 0.08;
+                let chroma = self.rng.random_range(0.12..0.2);
+                readable_colour(lightness, chroma, hue)
+            })
+            .collect()
+    }
+
+    // Starts animating from the `current` colours to a freshly generated scheme
+    pub fn start(&mut self, current: &[Color]) {
+        let to = self.generate(current.len());
+        self.transition = Some(Transition {
+            from: current.to_vec(),
+            to,
+            elapsed: 0.0,
+        });
+    }
+
+    pub fn cancel(&mut self) {
+        self.transition = None;
+    }
+
+    // Advances the running transition by `dt` seconds and returns the colours
+    // to show this frame, or `None` when nothing is animating
+    pub fn advance(&mut self, dt: f32) -> Option<Vec<Color>> {
+        let transition = self.transition.as_mut()?;
+        transition.elapsed += dt;
+
+        let progress = if self.transition_duration > 0.0 {
+            (transition.elapsed / self.transition_duration).min(1.0)
+        } else {
+            1.0
+        };
+        // The last frame is the target itself, not a round trip through OKLCH
+        if progress >= 1.0 {
+            return self.transition.take().map(|transition| transition.to);
+        }
+        // Smoothstep easing so colours ease in and out of the change
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        let colours = transition
+            .from
+            .iter()
+            .zip(transition.to.iter())
+            .map(|(&from, &to)| interpolate(from, to, eased, Interpolation::Oklch))
+            .collect();
+        Some(colours)
+    }
+}
+
+// Raises the lightness until the colour is readable against the black
+// background, and lowers the chroma into the sRGB gamut so clipping does not
+// pull the hue away from the rule
+fn readable_colour(lightness: f32, chroma: f32, hue: f32) -> Color {
+    let min_luminance = MIN_CONTRAST * 0.05 - 0.05;
+    let displayable = |lightness: f32| {
+        let mut chroma = chroma;
+        while chroma > 0.0 && !oklch_in_gamut(lightness, chroma, hue) {
+            chroma -= 0.005;
+        }
+        oklch_to_rgb(lightness, chroma.max(0.0), hue)
+    };
+    let mut lightness = lightness;
+    let mut colour = displayable(lightness);
+    while relative_luminance(colour) < min_luminance && lightness < 1.0 {
+        lightness += 0.02;
+        colour = displayable(lightness);
+    }
+    colour
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::colour_space::rgb_to_oklch;
+
+    const SEEDS: [u64; 4] = [1, 7, 42, 2024];
+
+    fn contrast_against_black(colour: Color) -> f32 {
+        (relative_luminance(colour) + 0.05) / 0.05
+    }
+
+    // Signed difference between two hues, in -180.0..180.0
+    fn hue_difference(from: Color, to: Color) -> f32 {
+        let (_, _, from) = rgb_to_oklch(from);
+        let (_, _, to) = rgb_to_oklch(to);
+        (to - from + 540.0).rem_euclid(360.0) - 180.0
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_schemes() {
+        for seed in SEEDS {
+            let mut first = ColourRandomiser::new(Some(seed), 0.8);
+            let mut second = ColourRandomiser::new(Some(seed), 0.8);
+            for _ in 0..5 {
+                assert_eq!(first.generate(6), second.generate(6));
+            }
+        }
+        let mut other = ColourRandomiser::new(Some(SEEDS[0] + 1), 0.8);
+        assert_ne!(ColourRandomiser::new(Some(SEEDS[0]), 0.8).generate(6), other.generate(6));
+    }
+
+    #[test]
+    fn colours_are_readable_against_black() {
+        for seed in SEEDS {
+            let mut randomiser = ColourRandomiser::new(Some(seed), 0.8);
+            for _ in 0..50 {
+                for colour in randomiser.generate(8) {
+                    let contrast = contrast_against_black(colour);
+                    assert!(contrast >= MIN_CONTRAST, "{:?} has contrast {}", colour, contrast);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn schemes_follow_their_harmony_rule() {
+        // Rounding to bytes moves hues slightly, so allow some slack
+        let close = |actual: f32, expected: f32| ((actual - expected + 540.0).rem_euclid(360.0) - 180.0).abs() < 4.0;
+        for seed in SEEDS {
+            let mut randomiser = ColourRandomiser::new(Some(seed), 0.8);
+            for base_hue in [20.0, 140.0, 260.0] {
+                let colours = randomiser.generate_with_rule(HarmonyRule::Complementary, base_hue, 2);
+                let difference = hue_difference(colours[0], colours[1]);
+                assert!(close(difference, 180.0), "complementary {} at {}", difference, base_hue);
+
+                let colours = randomiser.generate_with_rule(HarmonyRule::Analogous, base_hue, 3);
+                let (left, right) = (hue_difference(colours[1], colours[0]), hue_difference(colours[1], colours[2]));
+                assert!(close(left, -30.0) && close(right, 30.0), "analogous {} {} at {}", left, right, base_hue);
+
+                let colours = randomiser.generate_with_rule(HarmonyRule::Triadic, base_hue, 3);
+                let (second, third) = (hue_difference(colours[0], colours[1]), hue_difference(colours[0], colours[2]));
+                assert!(close(second, 120.0) && close(third, 240.0), "triadic {} {} at {}", second, third, base_hue);
+            }
+        }
+    }
+
+    #[test]
+    fn transition_lands_on_its_target_at_the_duration() {
+        let current = vec![Color::RGB(200, 40, 40); 4];
+        let mut randomiser = ColourRandomiser::new(Some(SEEDS[0]), 0.5);
+        let target = ColourRandomiser::new(Some(SEEDS[0]), 0.5).generate(current.len());
+        randomiser.start(&current);
+
+        let halfway = randomiser.advance(0.25).unwrap();
+        assert_ne!(halfway, current);
+        assert_ne!(halfway, target);
+        assert_eq!(randomiser.advance(0.25).unwrap(), target);
+        assert_eq!(randomiser.advance(0.1), None);
+
+        // A zero duration jumps straight to the new scheme
+        let mut instant = ColourRandomiser::new(Some(SEEDS[0]), 0.0);
+        instant.start(&current);
+        assert_eq!(instant.advance(0.0).unwrap(), target);
+        assert_eq!(instant.advance(0.0), None);
+    }
+}
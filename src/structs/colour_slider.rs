@@ -1,40 +1,93 @@
+use sdl2::keyboard::Keycode;
 use sdl2::rect::Rect;
 use sdl2::pixels::Color;
+use crate::structs::widget::{Widget, WidgetEvent, WidgetResponse, WidgetState};
+
+// How far the arrow keys move a focused slider
+const KEY_STEP: u8 = 5;
 
 pub struct ColourSlider {
     pub rect: Rect,
     pub value: u8,
-    pub is_dragging: bool,
     pub label: String,
     pub color: Color,
     pub background_rect: Rect,
+    pub state: WidgetState,
 }
 
 impl ColourSlider {
     pub fn new(x: i32, y: i32, width: u32, height: u32, label: &str) -> Self {
-        let padding = 10;
-        ColourSlider {
-            rect: Rect::new(x + padding, y, width - (padding * 2) as u32, height),
-            background_rect: Rect::new(
-                x, 
-                y - padding, 
-                width, 
-                height + (padding * 2) as u32
-            ),
+        let mut slider = ColourSlider {
+            rect: Rect::new(x, y, width, height),
+            background_rect: Rect::new(x, y, width, height),
             value: 0,
-            is_dragging: false,
             label: label.to_string(),
             color: Color::RGB(0, 0, 0),
-        }
+            state: WidgetState::default(),
+        };
+        slider.set_rect(Rect::new(x, y, width, height), 10);
+        slider
+    }
+
+    // `rect` is the track including `padding` on the left and right
+    pub fn set_rect(&mut self, rect: Rect, padding: u32) {
+        self.rect = Rect::new(
+            rect.x() + padding as i32,
+            rect.y(),
+            rect.width().saturating_sub(padding * 2).max(1),
+            rect.height(),
+        );
+        self.background_rect = Rect::new(
+            rect.x(),
+            rect.y() - padding as i32,
+            rect.width(),
+            rect.height() + padding * 2,
+        );
     }
 
     pub fn update(&mut self, mouse_x: i32) {
-        if self.is_dragging {
-            let relative_x = mouse_x - self.rect.x;
-            self.value = ((relative_x as f32 / self.rect.width() as f32) * 255.0)
-                .max(0.0)
-                .min(255.0) as u8;
-            self.color = Color::RGB(self.value, self.value, self.value);
+        let relative_x = mouse_x - self.rect.x;
+        let value = ((relative_x as f32 / self.rect.width() as f32) * 255.0)
+            .max(0.0)
+            .min(255.0) as u8;
+        self.set_value(value);
+    }
+
+    pub fn set_value(&mut self, value: u8) {
+        self.value = value;
+        self.color = Color::RGB(self.value, self.value, self.value);
+    }
+}
+
+impl Widget for ColourSlider {
+    fn bounds(&self) -> Rect {
+        self.background_rect
+    }
+
+    fn state(&self) -> &WidgetState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut WidgetState {
+        &mut self.state
+    }
+
+    fn handle_event(&mut self, event: &WidgetEvent) -> WidgetResponse {
+        match *event {
+            // Pressed means dragging, so moves outside the slider still count
+            WidgetEvent::MouseDown(point) | WidgetEvent::MouseMove(point) => {
+                self.update(point.x());
+                WidgetResponse::Changed(self.value)
+            }
+            WidgetEvent::Key(Keycode::Left | Keycode::Down) => {
+                self.set_value(self.value.saturating_sub(KEY_STEP));
+                WidgetResponse::Changed(self.value)
+            }
+            WidgetEvent::Key(Keycode::Right | Keycode::Up) => {
+                self.set_value(self.value.saturating_add(KEY_STEP));
+                WidgetResponse::Changed(self.value)
+            }
+            _ => WidgetResponse::None,
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,168 @@
+use sdl2::pixels::Color;
+
+// Conversions between sRGB and the spaces used for palette interpolation.
+// Hues are in degrees (0.0..360.0), every other component in 0.0..=1.0
+// except OKLCH chroma, which stays below roughly 0.4 for displayable colours.
+
+pub fn rgb_to_hsv(colour: Color) -> (f32, f32, f32) {
+    let (r, g, b) = unit_rgb(colour);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let saturation = if max > 0.0 { delta / max } else { 0.0 };
+    (hue_from_rgb(r, g, b, max, delta), saturation, max)
+}
+
+pub fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> Color {
+    let chroma = value * saturation;
+    rgb_from_hue_chroma(hue, chroma, value - chroma)
+}
+
+pub fn rgb_to_hsl(colour: Color) -> (f32, f32, f32) {
+    let (r, g, b) = unit_rgb(colour);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let lightness = (max + min) / 2.0;
+    let saturation = if delta == 0.0 {
+        0.0
+    } else {
+        delta / (1.0 - (2.0 * lightness - 1.0).abs())
+    };
+    (hue_from_rgb(r, g, b, max, delta), saturation.clamp(0.0, 1.0), lightness)
+}
+
+pub fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> Color {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    rgb_from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+}
+
+pub fn rgb_to_oklch(colour: Color) -> (f32, f32, f32) {
+    let (r, g, b) = unit_rgb(colour);
+    let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+
+    let l = (0.41222147 * r + 0.53633254 * g + 0.051445993 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+
+    let lightness = 0.21045426 * l + 0.7936178 * m - 0.004072047 * s;
+    let a = 1.9779985 * l - 2.4285922 * m + 0.4505937 * s;
+    let b = 0.025904037 * l + 0.78277177 * m - 0.80867577 * s;
+
+    let chroma = (a * a + b * b).sqrt();
+    let hue = b.atan2(a).to_degrees().rem_euclid(360.0);
+    (lightness, chroma, hue)
+}
+
+// Out-of-gamut results are clipped per channel
+pub fn oklch_to_rgb(lightness: f32, chroma: f32, hue: f32) -> Color {
+    let (r, g, b) = oklch_to_linear(lightness, chroma, hue);
+    Color::RGB(
+        to_byte(linear_to_srgb(r)),
+        to_byte(linear_to_srgb(g)),
+        to_byte(linear_to_srgb(b)),
+    )
+}
+
+// Whether the colour can be shown without clipping, which would shift its hue
+pub fn oklch_in_gamut(lightness: f32, chroma: f32, hue: f32) -> bool {
+    const TOLERANCE: f32 = 1e-4;
+    let (r, g, b) = oklch_to_linear(lightness, chroma, hue);
+    [r, g, b].iter().all(|channel| (-TOLERANCE..=1.0 + TOLERANCE).contains(channel))
+}
+
+// Linear sRGB channels, unclipped
+fn oklch_to_linear(lightness: f32, chroma: f32, hue: f32) -> (f32, f32, f32) {
+    let (sin, cos) = hue.to_radians().sin_cos();
+    let (a, b) = (chroma * cos, chroma * sin);
+
+    let l = (lightness + 0.39633778 * a + 0.21580376 * b).powi(3);
+    let m = (lightness - 0.105561346 * a - 0.06385417 * b).powi(3);
+    let s = (lightness - 0.08948418 * a - 1.2914855 * b).powi(3);
+
+    (
+        4.0767417 * l - 3.3077116 * m + 0.23096993 * s,
+        -1.268438 * l + 2.6097574 * m - 0.3413194 * s,
+        -0.0041960863 * l - 0.7034186 * m + 1.7076147 * s,
+    )
+}
+
+// Interpolates between two hues along the shorter way round the colour wheel
+pub fn lerp_hue(from: f32, to: f32, t: f32) -> f32 {
+    let difference = (to - from + 540.0).rem_euclid(360.0) - 180.0;
+    (from + difference * t).rem_euclid(360.0)
+}
+
+// WCAG relative luminance, used to keep colours readable on the black background
+pub fn relative_luminance(colour: Color) -> f32 {
+    let (r, g, b) = unit_rgb(colour);
+    0.2126 * srgb_to_linear(r) + 0.7152 * srgb_to_linear(g) + 0.0722 * srgb_to_linear(b)
+}
+
+// Formats a colour as `#rrggbb` for the human-editable preset and config files
+pub fn format_hex_colour(colour: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour.r, colour.g, colour.b)
+}
+
+pub fn parse_hex_colour(text: &str) -> Result<Color, String> {
+    let digits = text.trim().trim_start_matches('#');
+    if digits.len() != 6 || !digits.is_ascii() {
+        return Err(format!("invalid colour '{}', expected #rrggbb", text));
+    }
+    let channel = |range: std::ops::Range<usize>| {
+        u8::from_str_radix(&digits[range], 16).map_err(|_| format!("invalid colour '{}', expected #rrggbb", text))
+    };
+    Ok(Color::RGB(channel(0..2)?, channel(2..4)?, channel(4..6)?))
+}
+
+fn unit_rgb(colour: Color) -> (f32, f32, f32) {
+    (colour.r as f32 / 255.0, colour.g as f32 / 255.0, colour.b as f32 / 255.0)
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn hue_from_rgb(r: f32, g: f32, b: f32, max: f32, delta: f32) -> f32 {
+    if delta == 0.0 {
+        return 0.0;
+    }
+    let hue = if max == r {
+        60.0 * ((g - b) / delta)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    hue.rem_euclid(360.0)
+}
+
+fn rgb_from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Color {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    Color::RGB(to_byte(r + offset), to_byte(g + offset), to_byte(b + offset))
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.max(0.0).powf(1.0 / 2.4) - 0.055
+    }
+}
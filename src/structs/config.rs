@@ -0,0 +1,429 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use serde::{Deserialize, Serialize};
+use crate::structs::audio_analyzer::{is_valid_fft_size, Transform, MAX_FFT_SIZE, MIN_FFT_SIZE};
+use crate::structs::config_paths::config_path;
+use crate::structs::constant_q::{MAX_BINS_PER_OCTAVE, MIN_BINS_PER_OCTAVE};
+use crate::structs::keybindings::Action;
+use crate::structs::visualisation_mode::VisualisationMode;
+
+// Settings loaded from config.toml. Every field has a default so a partial
+// file, or no file at all, is fine.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub audio: AudioConfig,
+    pub analyzer: AnalyzerConfig,
+    pub visualisation: VisualisationConfig,
+    pub ui: UiConfig,
+    pub keybindings: KeybindingConfig,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    pub volume: f32,
+    // Output device name; the system default is used when unset or not found
+    pub device: Option<String>,
+    pub last_directory: Option<PathBuf>,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self { volume: 1.0, device: None, last_directory: None }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalyzerConfig {
+    pub fft_size: usize,
+    pub bar_count: usize,
+    pub gain: f32,
+    pub smoothing: f32,
+    pub transform: Transform,
+    // Resolution of the constant-Q transform
+    pub bins_per_octave: usize,
+}
+
+impl Default for AnalyzerConfig {
+    fn default() -> Self {
+        Self {
+            fft_size: 1024,
+            bar_count: 16,
+            gain: 5.0,
+            smoothing: 0.0,
+            transform: Transform::Fft,
+            bins_per_octave: 12,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VisualisationConfig {
+    pub mode: VisualisationMode,
+    // A built-in palette, or the palette of a preset in presets.toml
+    pub palette: String,
+    // Stop colours of the palette as last edited; empty uses the palette's own
+    #[serde(with = "hex_colour_list")]
+    pub colours: Vec<Color>,
+    pub transition_duration: f32,
+    pub scope_trigger_level: f32,
+    // Level and loudness meters beside the bars
+    pub show_meters: bool,
+}
+
+impl Default for VisualisationConfig {
+    fn default() -> Self {
+        Self {
+            mode: VisualisationMode::Bars,
+            palette: "Classic".to_string(),
+            colours: Vec::new(),
+            transition_duration: 0.8,
+            scope_trigger_level: 0.0,
+            show_meters: true,
+        }
+    }
+}
+
+// CJK and emoji fonts commonly installed on Linux, Windows and macOS. Only
+// the ones found are loaded.
+const DEFAULT_FALLBACK_FONTS: [&str; 12] = [
+    "Noto Sans CJK",
+    "Droid Sans Fallback",
+    "WenQuanYi Micro Hei",
+    "msyh",
+    "malgun",
+    "PingFang",
+    "Hiragino Sans GB",
+    "Noto Emoji",
+    "seguiemj",
+    "Symbola",
+    "Noto Color Emoji",
+    "Apple Color Emoji",
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    pub window_width: u32,
+    pub window_height: u32,
+    // A font file, which takes priority over `font_family`
+    pub font_path: Option<PathBuf>,
+    // Looked up by file name in the system font folders
+    pub font_family: Option<String>,
+    pub font_size: u16,
+    // Files or families tried in order for characters the main font lacks
+    pub fallback_fonts: Vec<String>,
+    // Text and button outline colour
+    #[serde(with = "hex_colour")]
+    pub theme_colour: Color,
+    // Seconds without mouse movement before controls fade out when fullscreen
+    pub controls_hide_delay: f32,
+    // Wait for the display's refresh before presenting each frame
+    pub vsync: bool,
+    // Frames per second to render at most; 0 renders as fast as possible
+    pub fps_limit: u32,
+    // Show the frame rate and frame times in the corner
+    pub show_fps: bool,
+    // Folder for screenshots, defaulting to a folder in the user's pictures
+    pub screenshot_directory: Option<PathBuf>,
+    // Save only the visualisation rather than the whole window
+    pub screenshot_visualisation_only: bool,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            window_width: 1280,
+            window_height: 720,
+            font_path: None,
+            font_family: None,
+            font_size: 24,
+            fallback_fonts: DEFAULT_FALLBACK_FONTS.iter().map(|name| name.to_string()).collect(),
+            theme_colour: Color::RGB(47, 198, 18),
+            controls_hide_delay: 3.0,
+            vsync: true,
+            fps_limit: 60,
+            show_fps: false,
+            screenshot_directory: None,
+            screenshot_visualisation_only: false,
+        }
+    }
+}
+
+// Keys for each action as SDL key names, e.g. "]", "N" or "F5". Actions
+// missing from the file keep their default keys; an empty list unbinds one.
+#[derive(Clone, Debug, Serialize)]
+#[serde(transparent)]
+pub struct KeybindingConfig {
+    pub bindings: BTreeMap<Action, Vec<String>>,
+}
+
+impl Default for KeybindingConfig {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .map(|&action| (action, action.default_keys().iter().map(|key| key.to_string()).collect()))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeybindingConfig {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // A single key may be written without the list brackets
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Keys {
+            One(String),
+            Many(Vec<String>),
+        }
+
+        let overrides = BTreeMap::<Action, Keys>::deserialize(deserializer)?;
+        let mut config = Self::default();
+        for (action, keys) in overrides {
+            let keys = match keys {
+                Keys::One(key) => vec![key],
+                Keys::Many(keys) => keys,
+            };
+            config.bindings.insert(action, keys);
+        }
+        Ok(config)
+    }
+}
+
+impl Config {
+    // Loads config.toml from the config dir. A file that fails to parse or
+    // validate is reported and never overwritten; the defaults are used instead.
+    pub fn load() -> Self {
+        let path = match config_path() {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Self { path: Some(path), ..Self::default() };
+            }
+            Err(e) => {
+                eprintln!("Could not read {}: {}", path.display(), e);
+                return Self::default();
+            }
+        };
+        let config: Config = match toml::from_str(&contents) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Could not parse {}: {}", path.display(), e);
+                return Self::default();
+            }
+        };
+        if let Err(e) = config.validate() {
+            eprintln!("Invalid setting in {}: {}", path.display(), e);
+            return Self::default();
+        }
+        Self { path: Some(path), ..config }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Err("no writable config file".into()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.audio.volume) {
+            return Err("audio.volume must be between 0.0 and 1.0".to_string());
+        }
+        if !is_valid_fft_size(self.analyzer.fft_size) {
+            return Err(format!(
+                "analyzer.fft_size must be a power of two between {} and {}",
+                MIN_FFT_SIZE, MAX_FFT_SIZE
+            ));
+        }
+        if !(1..=256).contains(&self.analyzer.bar_count) {
+            return Err("analyzer.bar_count must be between 1 and 256".to_string());
+        }
+        // Written so that NaN fails the checks as well
+        if !(self.analyzer.gain > 0.0 && self.analyzer.gain.is_finite()) {
+            return Err("analyzer.gain must be positive".to_string());
+        }
+        if !(0.0..1.0).contains(&self.analyzer.smoothing) {
+            return Err("analyzer.smoothing must be at least 0.0 and below 1.0".to_string());
+        }
+        if !(MIN_BINS_PER_OCTAVE..=MAX_BINS_PER_OCTAVE).contains(&self.analyzer.bins_per_octave) {
+            return Err(format!(
+                "analyzer.bins_per_octave must be between {} and {}",
+                MIN_BINS_PER_OCTAVE, MAX_BINS_PER_OCTAVE
+            ));
+        }
+        if self.visualisation.palette.is_empty() {
+            return Err("visualisation.palette cannot be empty".to_string());
+        }
+        if !(0.0..=f32::MAX).contains(&self.visualisation.transition_duration) {
+            return Err("visualisation.transition_duration must be a number of seconds, 0.0 or more".to_string());
+        }
+        if !(-1.0..=1.0).contains(&self.visualisation.scope_trigger_level) {
+            return Err("visualisation.scope_trigger_level must be between -1.0 and 1.0".to_string());
+        }
+        if self.ui.window_width < 640 || self.ui.window_height < 480 {
+            return Err("the window must be at least 640x480".to_string());
+        }
+        if !(0.0..=f32::MAX).contains(&self.ui.controls_hide_delay) {
+            return Err("ui.controls_hide_delay must be a number of seconds, 0.0 or more".to_string());
+        }
+        if !(6..=200).contains(&self.ui.font_size) {
+            return Err("ui.font_size must be between 6 and 200".to_string());
+        }
+        if self.ui.fps_limit > 1000 {
+            return Err("ui.fps_limit must be 1000 or less (0 for no limit)".to_string());
+        }
+        let mut bound: HashMap<Keycode, Action> = HashMap::new();
+        for (&action, keys) in &self.keybindings.bindings {
+            for key in keys {
+                let keycode = Keycode::from_name(key).ok_or_else(|| format!("unknown key '{}'", key))?;
+                if let Some(other) = bound.insert(keycode, action) {
+                    return Err(format!(
+                        "'{}' is bound to both '{}' and '{}'",
+                        key,
+                        other.description(),
+                        action.description()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+mod hex_colour {
+    use sdl2::pixels::Color;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use crate::structs::colour_space::{format_hex_colour, parse_hex_colour};
+
+    pub fn serialize<S: Serializer>(colour: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_hex_colour(*colour))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse_hex_colour(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+mod hex_colour_list {
+    use sdl2::pixels::Color;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use crate::structs::colour_space::{format_hex_colour, parse_hex_colour};
+
+    pub fn serialize<S: Serializer>(colours: &[Color], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(colours.iter().map(|&colour| format_hex_colour(colour)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Color>, D::Error> {
+        let texts = Vec::<String>::deserialize(deserializer)?;
+        texts
+            .iter()
+            .map(|text| parse_hex_colour(text).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Config {
+        toml::from_str(contents).unwrap()
+    }
+
+    #[test]
+    fn missing_settings_keep_their_defaults() {
+        let config = parse(
+            r#"
+            [analyzer]
+            bar_count = 64
+
+            [keybindings]
+            play_pause = "P"
+            track_previous = []
+            "#,
+        );
+        let defaults = Config::default();
+        assert_eq!(config.analyzer.bar_count, 64);
+        assert_eq!(config.analyzer.fft_size, defaults.analyzer.fft_size);
+        assert_eq!(config.audio.volume, defaults.audio.volume);
+        assert_eq!(config.visualisation.palette, defaults.visualisation.palette);
+        assert_eq!(config.ui.theme_colour, defaults.ui.theme_colour);
+        assert_eq!(config.keybindings.bindings[&Action::PlayPause], vec!["P"]);
+        assert!(config.keybindings.bindings[&Action::TrackPrevious].is_empty());
+        assert_eq!(config.keybindings.bindings[&Action::TrackNext], vec!["N"]);
+        assert!(config.validate().is_ok());
+
+        assert!(parse("").validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let invalid = [
+            "[audio]\nvolume = 1.5",
+            "[analyzer]\nfft_size = 1000",
+            "[analyzer]\nbar_count = 0",
+            "[analyzer]\ngain = 0.0",
+            "[analyzer]\ngain = nan",
+            "[analyzer]\nsmoothing = 1.0",
+            "[analyzer]\nbins_per_octave = 37",
+            "[visualisation]\npalette = \"\"",
+            "[visualisation]\ntransition_duration = -1.0",
+            "[visualisation]\ntransition_duration = nan",
+            "[visualisation]\nscope_trigger_level = 2.0",
+            "[ui]\nwindow_width = 320",
+            "[ui]\ncontrols_hide_delay = nan",
+            "[ui]\nfont_size = 4",
+            "[ui]\nfps_limit = 5000",
+            "[keybindings]\nrandomise = \"NotAKey\"",
+        ];
+        for contents in invalid {
+            assert!(parse(contents).validate().is_err(), "accepted {:?}", contents);
+        }
+    }
+
+    #[test]
+    fn conflicting_keybindings_are_rejected() {
+        // Moving a default key to another action needs the old binding changed too
+        let error = parse("[keybindings]\nrandomise = \"Space\"").validate().unwrap_err();
+        assert!(error.contains("'Space' is bound to both"), "{}", error);
+
+        let moved = parse("[keybindings]\nrandomise = \"Space\"\nplay_pause = \"K\"");
+        assert!(moved.validate().is_ok());
+    }
+
+    #[test]
+    fn colours_are_written_as_hex() {
+        let mut config = Config::default();
+        config.ui.theme_colour = Color::RGB(255, 16, 0);
+        config.visualisation.colours = vec![Color::RGB(1, 2, 3), Color::RGB(171, 205, 239)];
+        let written = toml::to_string_pretty(&config).unwrap();
+        assert!(written.contains("theme_colour = \"#ff1000\""), "{}", written);
+        assert!(written.contains("\"#010203\""), "{}", written);
+
+        let read = parse(&written);
+        assert_eq!(read.ui.theme_colour, config.ui.theme_colour);
+        assert_eq!(read.visualisation.colours, config.visualisation.colours);
+
+        assert!(toml::from_str::<Config>("[ui]\ntheme_colour = \"green\"").is_err());
+        assert!(toml::from_str::<Config>("[visualisation]\ncolours = [\"#000000\", \"#12\"]").is_err());
+    }
+}
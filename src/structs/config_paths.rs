@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+// Everything the application persists lives in one directory under the
+// platform config dir (`$XDG_CONFIG_HOME/music_visualiser` on Linux)
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("music_visualiser"))
+}
+
+pub fn presets_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("presets.toml"))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+// Where screenshots go unless the config says otherwise
+pub fn default_screenshot_dir() -> Option<PathBuf> {
+    dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .map(|dir| dir.join("music_visualiser"))
+}
@@ -0,0 +1,273 @@
+use std::sync::mpsc;
+use std::thread;
+use rustfft::{FftPlanner, num_complex::Complex};
+
+// Bins start at C1 and cover eight octaves, up to just below C9
+pub const MIN_FREQUENCY: f32 = 32.703_197;
+const OCTAVES: usize = 8;
+// Resolutions accepted from the config file
+pub const MIN_BINS_PER_OCTAVE: usize = 1;
+pub const MAX_BINS_PER_OCTAVE: usize = 36;
+// Bins are kept below this fraction of the sample rate to stay clear of aliasing
+const MAX_FREQUENCY_FRACTION: f32 = 0.45;
+// Spectral kernel values smaller than this share of a bin's largest are
+// dropped, which is what makes the transform cheap
+const SPARSITY_THRESHOLD: f32 = 0.005;
+
+// Constant-Q transform using the spectral kernel method (Brown and Puckette,
+// 1992): each bin's windowed complex exponential is transformed once, up front,
+// and thinned to its largest values, so a frame costs one FFT and a short
+// multiply-add per bin. Bins are spaced evenly in pitch, `bins_per_octave` to
+// the octave, and every bin spans the same number of cycles, so the bass gets
+// long windows and fine resolution while the treble stays quick.
+pub struct ConstantQ {
+    fft: std::sync::Arc<dyn rustfft::Fft<f32>>,
+    buffer: Vec<Complex<f32>>,
+    // Conjugated kernel spectra, already divided by the FFT size
+    kernels: Vec<Vec<(usize, Complex<f32>)>>,
+    window_size: usize,
+    bins_per_octave: usize,
+    sample_rate: u32,
+}
+
+impl ConstantQ {
+    pub fn new(sample_rate: u32, bins_per_octave: usize) -> Self {
+        let bins_per_octave = bins_per_octave.clamp(MIN_BINS_PER_OCTAVE, MAX_BINS_PER_OCTAVE);
+        let rate = sample_rate as f32;
+        let q = 1.0 / (2f32.powf(1.0 / bins_per_octave as f32) - 1.0);
+        let bin_count = (0..OCTAVES * bins_per_octave)
+            .take_while(|&bin| bin_frequency(bin, bins_per_octave) < rate * MAX_FREQUENCY_FRACTION)
+            .count();
+        // The lowest bin has the longest kernel
+        let window_size = ((q * rate / MIN_FREQUENCY).ceil() as usize).next_power_of_two();
+
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(window_size);
+        let kernels = (0..bin_count)
+            .map(|bin| {
+                let frequency = bin_frequency(bin, bins_per_octave);
+                let length = ((q * rate / frequency).ceil() as usize).min(window_size);
+                // Kernels end with the frame so every bin reacts to the newest audio
+                let start = window_size - length;
+                let mut kernel = vec![Complex::new(0.0, 0.0); window_size];
+                let window: Vec<f32> = (0..length)
+                    .map(|n| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * n as f32 / length as f32).cos())
+                    .collect();
+                let window_sum: f32 = window.iter().sum();
+                for (n, weight) in window.iter().enumerate() {
+                    let phase = 2.0 * std::f32::consts::PI * frequency * n as f32 / rate;
+                    kernel[start + n] = Complex::from_polar(weight / window_sum, phase);
+                }
+                fft.process(&mut kernel);
+
+                let largest = kernel.iter().map(|value| value.norm()).fold(0.0, f32::max);
+                kernel
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, value)| value.norm() >= largest * SPARSITY_THRESHOLD)
+                    .map(|(index, value)| (index, value.conj() / window_size as f32))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            fft,
+            buffer: vec![Complex::new(0.0, 0.0); window_size],
+            kernels,
+            window_size,
+            bins_per_octave,
+            sample_rate,
+        }
+    }
+
+    // Samples needed per frame
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    fn matches(&self, sample_rate: u32, bins_per_octave: usize) -> bool {
+        self.sample_rate == sample_rate && self.bins_per_octave == bins_per_octave
+    }
+
+    // Magnitude of every bin, on the same square-root scale as `AudioAnalyzer`
+    // so the bar gain means the same with either analyzer
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        // Short input is treated as silence before it, like `latest_mono` does
+        let padding = self.window_size.saturating_sub(samples.len());
+        let recent = &samples[samples.len().saturating_sub(self.window_size)..];
+        for (slot, &sample) in self.buffer.iter_mut().zip(std::iter::repeat_n(&0.0, padding).chain(recent)) {
+            *slot = Complex::new(sample, 0.0);
+        }
+        self.fft.process(&mut self.buffer);
+
+        self.kernels
+            .iter()
+            .map(|kernel| {
+                let value: Complex<f32> = kernel.iter().map(|&(index, weight)| self.buffer[index] * weight).sum();
+                // A full-scale sine gives a magnitude of 0.5 in its bin
+                (value.norm() * 2.0).sqrt()
+            })
+            .collect()
+    }
+}
+
+// Transforms built so far, one per sample rate and resolution. Building one
+// plans and transforms a kernel per bin, which takes long enough to drop
+// frames, so it happens on a background thread and the caller carries on
+// without it until it arrives.
+pub struct ConstantQCache {
+    ready: Vec<ConstantQ>,
+    building: Vec<(u32, usize, mpsc::Receiver<ConstantQ>)>,
+}
+
+impl ConstantQCache {
+    pub fn new() -> Self {
+        Self { ready: Vec::new(), building: Vec::new() }
+    }
+
+    // Starts building the transform unless it is built or on its way
+    pub fn prepare(&mut self, sample_rate: u32, bins_per_octave: usize) {
+        let bins_per_octave = bins_per_octave.clamp(MIN_BINS_PER_OCTAVE, MAX_BINS_PER_OCTAVE);
+        let known = self.ready.iter().any(|transform| transform.matches(sample_rate, bins_per_octave))
+            || self.building.iter().any(|&(rate, bins, _)| (rate, bins) == (sample_rate, bins_per_octave));
+        if known {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            // The receiver is gone if the app quit first, which is fine
+            tx.send(ConstantQ::new(sample_rate, bins_per_octave)).ok();
+        });
+        self.building.push((sample_rate, bins_per_octave, rx));
+    }
+
+    // The transform for this format if it has been built. Otherwise starts
+    // building it and returns None.
+    pub fn get(&mut self, sample_rate: u32, bins_per_octave: usize) -> Option<&mut ConstantQ> {
+        self.collect_finished();
+        self.prepare(sample_rate, bins_per_octave);
+        let bins_per_octave = bins_per_octave.clamp(MIN_BINS_PER_OCTAVE, MAX_BINS_PER_OCTAVE);
+        self.ready.iter_mut().find(|transform| transform.matches(sample_rate, bins_per_octave))
+    }
+
+    // Like `get`, but blocks until the transform is ready
+    pub fn wait(&mut self, sample_rate: u32, bins_per_octave: usize) -> &mut ConstantQ {
+        self.prepare(sample_rate, bins_per_octave);
+        let bins_per_octave = bins_per_octave.clamp(MIN_BINS_PER_OCTAVE, MAX_BINS_PER_OCTAVE);
+        let building = self.building.iter().position(|&(rate, bins, _)| (rate, bins) == (sample_rate, bins_per_octave));
+        if let Some(index) = building {
+            let (_, _, rx) = self.building.swap_remove(index);
+            // A build thread that panicked is retried here, where the panic shows
+            let transform = rx.recv().unwrap_or_else(|_| ConstantQ::new(sample_rate, bins_per_octave));
+            self.ready.push(transform);
+        }
+        self.ready
+            .iter_mut()
+            .find(|transform| transform.matches(sample_rate, bins_per_octave))
+            .expect("transform was just built")
+    }
+
+    fn collect_finished(&mut self) {
+        let mut index = 0;
+        while index < self.building.len() {
+            match self.building[index].2.try_recv() {
+                Ok(transform) => {
+                    self.ready.push(transform);
+                    self.building.swap_remove(index);
+                }
+                // A build that died is dropped so the next request starts another
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.building.swap_remove(index);
+                }
+                Err(mpsc::TryRecvError::Empty) => index += 1,
+            }
+        }
+    }
+}
+
+// Centre frequency of `bin` in Hz
+pub fn bin_frequency(bin: usize, bins_per_octave: usize) -> f32 {
+    MIN_FREQUENCY * 2f32.powf(bin as f32 / bins_per_octave as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::audio_analyzer::AudioAnalyzer;
+    use crate::structs::test_signals::{sine, SAMPLE_RATE};
+
+    fn strongest(values: &[f32]) -> usize {
+        (0..values.len()).max_by(|&a, &b| values[a].total_cmp(&values[b])).unwrap()
+    }
+
+    #[test]
+    fn bins_are_spaced_by_pitch() {
+        let transform = ConstantQ::new(SAMPLE_RATE, 12);
+        assert_eq!(transform.kernels.len(), 96);
+        // A1 is nine semitones above C1, A4 three octaves higher
+        assert!((bin_frequency(9, transform.bins_per_octave) - 55.0).abs() < 0.01);
+        assert!((bin_frequency(45, transform.bins_per_octave) - 440.0).abs() < 0.05);
+
+        // Bins stop short of the Nyquist frequency at low sample rates
+        let low_rate = ConstantQ::new(8000, 12);
+        let top = bin_frequency(low_rate.kernels.len() - 1, low_rate.bins_per_octave);
+        assert!(top < 8000.0 * MAX_FREQUENCY_FRACTION);
+    }
+
+    #[test]
+    fn notes_land_in_their_own_bin() {
+        let mut transform = ConstantQ::new(SAMPLE_RATE, 12);
+        for bin in [0, 9, 24, 45, 80] {
+            let frequency = bin_frequency(bin, transform.bins_per_octave);
+            let magnitudes = transform.process(&sine(frequency, 1.0, transform.window_size()));
+            assert_eq!(strongest(&magnitudes), bin, "{} Hz", frequency);
+            // Full scale reads 1.0, as it does from the FFT analyzer
+            assert!((magnitudes[bin] - 1.0).abs() < 0.05, "level {}", magnitudes[bin]);
+        }
+    }
+
+    #[test]
+    fn bass_notes_a_semitone_apart_are_resolved() {
+        // At 1024 points each FFT bin is 47 Hz wide, so E1 and F1 share one
+        let (e1, f1) = (41.2, 43.65);
+        let mut fft = AudioAnalyzer::new(1024);
+        let e1_fft = strongest(&fft.process(&sine(e1, 1.0, 1024)));
+        let f1_fft = strongest(&fft.process(&sine(f1, 1.0, 1024)));
+        assert_eq!(e1_fft, f1_fft);
+
+        let mut transform = ConstantQ::new(SAMPLE_RATE, 12);
+        let e1_bin = strongest(&transform.process(&sine(e1, 1.0, transform.window_size())));
+        let f1_bin = strongest(&transform.process(&sine(f1, 1.0, transform.window_size())));
+        assert_eq!(f1_bin, e1_bin + 1);
+        assert!((bin_frequency(e1_bin, transform.bins_per_octave) - e1).abs() < 0.1);
+    }
+
+    #[test]
+    fn cache_builds_each_format_once() {
+        let mut cache = ConstantQCache::new();
+        cache.prepare(8000, 12);
+        cache.prepare(8000, 12);
+        assert_eq!(cache.building.len(), 1);
+
+        let window_size = cache.wait(8000, 12).window_size();
+        assert!(cache.building.is_empty());
+        assert_eq!(cache.get(8000, 12).unwrap().window_size(), window_size);
+        assert_eq!(cache.ready.len(), 1);
+
+        // A new rate is built in the background while the old one stays usable
+        assert!(cache.get(11025, 12).is_none());
+        assert!(cache.get(8000, 12).is_some());
+        cache.wait(11025, 12);
+        assert_eq!(cache.ready.len(), 2);
+    }
+
+    #[test]
+    fn silence_and_short_input() {
+        let mut transform = ConstantQ::new(SAMPLE_RATE, 24);
+        assert!(transform.process(&[]).iter().all(|&value| value == 0.0));
+        // Only the newest window's worth of samples counts
+        let long = sine(440.0, 1.0, transform.window_size() * 2);
+        let bin = strongest(&transform.process(&long));
+        assert!((bin_frequency(bin, transform.bins_per_octave) - 440.0).abs() < 1.0);
+    }
+}
@@ -0,0 +1,34 @@
+use std::time::Instant;
+
+const FADE_SECONDS: f32 = 0.5;
+
+// Tracks mouse activity so controls can fade out in presentation mode
+pub struct ControlVisibility {
+    // Only hide controls while this is set
+    pub auto_hide: bool,
+    pub hide_delay: f32,
+    last_activity: Instant,
+}
+
+impl ControlVisibility {
+    pub fn new(hide_delay: f32) -> Self {
+        Self { auto_hide: false, hide_delay, last_activity: Instant::now() }
+    }
+
+    pub fn wake(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    // 1.0 while the mouse is active, fading to 0.0 once it has been still for `hide_delay`
+    pub fn opacity(&self) -> f32 {
+        if !self.auto_hide {
+            return 1.0;
+        }
+        let idle = self.last_activity.elapsed().as_secs_f32() - self.hide_delay;
+        (1.0 - idle / FADE_SECONDS).clamp(0.0, 1.0)
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.opacity() <= 0.0
+    }
+}
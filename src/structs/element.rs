@@ -2,7 +2,11 @@ use sdl2::rect::Rect;
 
 pub struct Element {
     pub rect: Rect,
-    pub text: String
+    // Area the text is centred in; `rect` is the text itself
+    pub bounds: Rect,
+    pub text: String,
+    // Horizontal offset from the centred position, for scrolling long text
+    pub scroll: i32,
 }
 
 impl Element {
@@ -11,18 +15,34 @@ impl Element {
         
         Element {
             rect,
+            bounds: rect,
             text: text.to_string(),
+            scroll: 0,
         }
     }
 
     pub fn update_text_position(&mut self, text_width: u32, text_height: u32) {
-        let text_x = self.rect.x + (self.rect.width() as i32 - text_width as i32) / 2;
-        let text_y = self.rect.y + (self.rect.height() as i32 - text_height as i32) / 2;
+        let text_x = self.bounds.x + (self.bounds.width() as i32 - text_width as i32) / 2 + self.scroll;
+        let text_y = self.bounds.y + (self.bounds.height() as i32 - text_height as i32) / 2;
         self.rect = Rect::new(text_x, text_y, text_width, text_height);
     }
 
-    pub fn move_element(&mut self, x: i32, y: i32, shift_x: i32) {
-        self.rect = Rect::new(x + shift_x, y, self.rect.width(), self.rect.height());
+    pub fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+        self.update_text_position(self.rect.width(), self.rect.height());
+    }
+
+    // New text starts back in the centre
+    pub fn set_text(&mut self, text: &str) {
+        if self.text != text {
+            self.text = text.to_string();
+            self.scroll = 0;
+        }
+    }
+
+    pub fn scroll_by(&mut self, shift_x: i32) {
+        self.scroll += shift_x;
+        self.rect.offset(shift_x, 0);
     }
 
     pub fn get_element_current_position(&self) -> i32 {
@@ -0,0 +1,270 @@
+// Share of the spectrum's total magnitude below the rolloff frequency
+pub const ROLLOFF_FRACTION: f32 = 0.85;
+// Centroids between these map onto the full 0.0..=1.0 brightness range
+const BRIGHTNESS_LOW_HZ: f32 = 100.0;
+const BRIGHTNESS_HIGH_HZ: f32 = 8000.0;
+// Keeps the logarithm in the flatness measure finite for empty bins
+const FLATNESS_FLOOR: f32 = 1e-10;
+
+// Descriptors of one analysis frame. Level features come from the time-domain
+// samples, the spectral ones from the analyzer's spectrum, with frequencies
+// in Hz.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AnalysisFrame {
+    pub rms: f32,
+    pub peak: f32,
+    pub centroid: f32,
+    // Standard deviation of the spectrum around the centroid
+    pub spread: f32,
+    pub rolloff: f32,
+    // 1.0 for a flat (noise-like) spectrum, near 0.0 for a pure tone
+    pub flatness: f32,
+    // Positive spectral change since the previous frame, an onset strength
+    pub flux: f32,
+    // Sign changes per sample, 0.0..=1.0
+    pub zero_crossing_rate: f32,
+}
+
+impl AnalysisFrame {
+    // `spectrum` is `AudioAnalyzer` output, which is square-root compressed
+    // for display. The shape features are measured on linear magnitudes, while
+    // flux stays on the compressed values, which track perceived onsets better.
+    pub fn compute(samples: &[f32], spectrum: &[f32], previous_spectrum: Option<&[f32]>, sample_rate: u32) -> Self {
+        let magnitudes: Vec<f32> = spectrum.iter().map(|value| value * value).collect();
+        let centroid = spectral_centroid(&magnitudes, sample_rate);
+        AnalysisFrame {
+            rms: rms(samples),
+            peak: peak(samples),
+            centroid,
+            spread: spectral_spread(&magnitudes, sample_rate, centroid),
+            rolloff: spectral_rolloff(&magnitudes, sample_rate, ROLLOFF_FRACTION),
+            flatness: spectral_flatness(&magnitudes),
+            flux: previous_spectrum.map(|previous| spectral_flux(previous, spectrum)).unwrap_or(0.0),
+            zero_crossing_rate: zero_crossing_rate(samples),
+        }
+    }
+
+    // The centroid on a log scale from 0.0 (dull) to 1.0 (bright), for
+    // driving colours
+    pub fn brightness(&self) -> f32 {
+        if self.centroid <= BRIGHTNESS_LOW_HZ {
+            return 0.0;
+        }
+        ((self.centroid / BRIGHTNESS_LOW_HZ).log2() / (BRIGHTNESS_HIGH_HZ / BRIGHTNESS_LOW_HZ).log2()).min(1.0)
+    }
+}
+
+// Remembers the previous spectrum so consecutive frames can be compared
+#[derive(Default)]
+pub struct FeatureExtractor {
+    previous_spectrum: Vec<f32>,
+}
+
+impl FeatureExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn process(&mut self, samples: &[f32], spectrum: &[f32], sample_rate: u32) -> AnalysisFrame {
+        // Flux is meaningless across a change of FFT size
+        let previous = (self.previous_spectrum.len() == spectrum.len()).then_some(self.previous_spectrum.as_slice());
+        let frame = AnalysisFrame::compute(samples, spectrum, previous, sample_rate);
+        self.previous_spectrum.clear();
+        self.previous_spectrum.extend_from_slice(spectrum);
+        frame
+    }
+
+    pub fn reset(&mut self) {
+        self.previous_spectrum.clear();
+    }
+}
+
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+pub fn peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0, |max, &x| f32::max(max, x.abs()))
+}
+
+// Frequency of each bin of a spectrum holding the first half of an FFT
+fn bin_frequency(bin: usize, spectrum_len: usize, sample_rate: u32) -> f32 {
+    bin as f32 * sample_rate as f32 / (spectrum_len * 2) as f32
+}
+
+// Magnitude-weighted mean frequency
+pub fn spectral_centroid(spectrum: &[f32], sample_rate: u32) -> f32 {
+    let total: f32 = spectrum.iter().sum();
+    if total <= f32::EPSILON {
+        return 0.0;
+    }
+    spectrum
+        .iter()
+        .enumerate()
+        .map(|(bin, magnitude)| bin_frequency(bin, spectrum.len(), sample_rate) * magnitude)
+        .sum::<f32>()
+        / total
+}
+
+pub fn spectral_spread(spectrum: &[f32], sample_rate: u32, centroid: f32) -> f32 {
+    let total: f32 = spectrum.iter().sum();
+    if total <= f32::EPSILON {
+        return 0.0;
+    }
+    let variance = spectrum
+        .iter()
+        .enumerate()
+        .map(|(bin, magnitude)| (bin_frequency(bin, spectrum.len(), sample_rate) - centroid).powi(2) * magnitude)
+        .sum::<f32>()
+        / total;
+    variance.sqrt()
+}
+
+// Frequency below which `fraction` of the spectrum's magnitude lies
+pub fn spectral_rolloff(spectrum: &[f32], sample_rate: u32, fraction: f32) -> f32 {
+    let total: f32 = spectrum.iter().sum();
+    if total <= f32::EPSILON {
+        return 0.0;
+    }
+    let threshold = total * fraction;
+    let mut running = 0.0;
+    for (bin, magnitude) in spectrum.iter().enumerate() {
+        running += magnitude;
+        if running >= threshold {
+            return bin_frequency(bin, spectrum.len(), sample_rate);
+        }
+    }
+    bin_frequency(spectrum.len().saturating_sub(1), spectrum.len(), sample_rate)
+}
+
+// Geometric mean over arithmetic mean of the magnitudes
+pub fn spectral_flatness(spectrum: &[f32]) -> f32 {
+    if spectrum.is_empty() {
+        return 0.0;
+    }
+    let mean = spectrum.iter().sum::<f32>() / spectrum.len() as f32;
+    if mean <= f32::EPSILON {
+        return 0.0;
+    }
+    let log_mean = spectrum.iter().map(|magnitude| (magnitude + FLATNESS_FLOOR).ln()).sum::<f32>() / spectrum.len() as f32;
+    (log_mean.exp() / mean).min(1.0)
+}
+
+// How much the spectrum grew since the previous frame, with decreases ignored,
+// averaged over the bins
+pub fn spectral_flux(previous: &[f32], current: &[f32]) -> f32 {
+    previous
+        .iter()
+        .zip(current)
+        .map(|(before, now)| (now - before).max(0.0))
+        .sum::<f32>()
+        / current.len().max(1) as f32
+}
+
+pub fn zero_crossing_rate(samples: &[f32]) -> f32 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let crossings = samples
+        .windows(2)
+        .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+        .count();
+    crossings as f32 / (samples.len() - 1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::audio_analyzer::AudioAnalyzer;
+    use crate::structs::test_signals::{sine, white_noise, SAMPLE_RATE};
+
+    const FFT_SIZE: usize = 4096;
+
+    fn analyse(samples: &[f32]) -> AnalysisFrame {
+        let spectrum = AudioAnalyzer::new(FFT_SIZE).process(samples);
+        AnalysisFrame::compute(samples, &spectrum, None, SAMPLE_RATE)
+    }
+
+    // A frequency that falls exactly on an FFT bin
+    fn bin_centred(bin: usize) -> f32 {
+        bin as f32 * SAMPLE_RATE as f32 / FFT_SIZE as f32
+    }
+
+    #[test]
+    fn sine_levels_match_amplitude() {
+        let frame = analyse(&sine(bin_centred(100), 0.5, FFT_SIZE));
+        assert!((frame.rms - 0.5 / 2f32.sqrt()).abs() < 1e-3, "rms {}", frame.rms);
+        assert!((frame.peak - 0.5).abs() < 1e-3, "peak {}", frame.peak);
+    }
+
+    #[test]
+    fn sine_spectral_shape_is_centred_on_its_frequency() {
+        let frequency = bin_centred(100);
+        let frame = analyse(&sine(frequency, 0.5, FFT_SIZE));
+        let bin_width = bin_centred(1);
+        assert!((frame.centroid - frequency).abs() < 2.0 * bin_width, "centroid {}", frame.centroid);
+        assert!((frame.rolloff - frequency).abs() < 2.0 * bin_width, "rolloff {}", frame.rolloff);
+        // Window leakage and rounding leave a little energy across the band
+        assert!(frame.spread < SAMPLE_RATE as f32 * 0.01, "spread {}", frame.spread);
+        assert!(frame.flatness < 0.1, "flatness {}", frame.flatness);
+    }
+
+    #[test]
+    fn sine_zero_crossing_rate_is_twice_its_frequency() {
+        let frequency = 1000.0;
+        let frame = analyse(&sine(frequency, 0.5, FFT_SIZE));
+        let expected = 2.0 * frequency / SAMPLE_RATE as f32;
+        assert!((frame.zero_crossing_rate - expected).abs() < 1e-3, "zcr {}", frame.zero_crossing_rate);
+    }
+
+    #[test]
+    fn white_noise_is_flat_and_centred_mid_band() {
+        let frame = analyse(&white_noise(1.0, FFT_SIZE));
+        let nyquist = SAMPLE_RATE as f32 / 2.0;
+        assert!(frame.flatness > 0.5, "flatness {}", frame.flatness);
+        assert!((frame.centroid - nyquist / 2.0).abs() < nyquist * 0.1, "centroid {}", frame.centroid);
+        // A uniform distribution over 0..nyquist has a standard deviation of nyquist / sqrt(12)
+        assert!((frame.spread - nyquist / 12f32.sqrt()).abs() < nyquist * 0.1, "spread {}", frame.spread);
+        assert!((frame.rolloff - nyquist * ROLLOFF_FRACTION).abs() < nyquist * 0.1, "rolloff {}", frame.rolloff);
+        // Uniform noise has an rms of 1 / sqrt(3)
+        assert!((frame.rms - 1.0 / 3f32.sqrt()).abs() < 0.05, "rms {}", frame.rms);
+    }
+
+    #[test]
+    fn silence_has_no_features() {
+        assert_eq!(analyse(&vec![0.0; FFT_SIZE]), AnalysisFrame::default());
+    }
+
+    #[test]
+    fn flux_only_counts_increases() {
+        let quiet = [0.0, 1.0, 0.0, 0.0];
+        let loud = [1.0, 0.0, 1.0, 0.0];
+        assert_eq!(spectral_flux(&quiet, &quiet), 0.0);
+        assert_eq!(spectral_flux(&quiet, &loud), 0.5);
+        assert_eq!(spectral_flux(&loud, &quiet), 0.25);
+    }
+
+    #[test]
+    fn extractor_compares_with_the_previous_frame() {
+        let mut extractor = FeatureExtractor::new();
+        let samples = sine(bin_centred(50), 0.5, FFT_SIZE);
+        let spectrum = AudioAnalyzer::new(FFT_SIZE).process(&samples);
+        assert_eq!(extractor.process(&samples, &spectrum, SAMPLE_RATE).flux, 0.0);
+        assert_eq!(extractor.process(&samples, &spectrum, SAMPLE_RATE).flux, 0.0);
+        let louder: Vec<f32> = spectrum.iter().map(|magnitude| magnitude * 2.0).collect();
+        assert!(extractor.process(&samples, &louder, SAMPLE_RATE).flux > 0.0);
+    }
+
+    #[test]
+    fn brightness_follows_the_centroid_on_a_log_scale() {
+        let at = |centroid| AnalysisFrame { centroid, ..Default::default() }.brightness();
+        assert_eq!(at(0.0), 0.0);
+        assert_eq!(at(BRIGHTNESS_LOW_HZ), 0.0);
+        assert_eq!(at(20000.0), 1.0);
+        let middle = (BRIGHTNESS_LOW_HZ * BRIGHTNESS_HIGH_HZ).sqrt();
+        assert!((at(middle) - 0.5).abs() < 1e-4);
+    }
+}
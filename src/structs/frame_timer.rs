@@ -0,0 +1,93 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+// A single frame longer than this is treated as a stall (window drag, file
+// dialog) so animations do not jump ahead
+const MAX_FRAME_TIME: f32 = 0.25;
+// Sleep this much short of the deadline and spin for the rest, since the OS
+// scheduler can oversleep by a millisecond or more
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+// How often the averaged statistics for the overlay are refreshed
+const STATS_INTERVAL: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    pub fps: f32,
+    pub average_ms: f32,
+    pub worst_ms: f32,
+}
+
+pub struct FrameTimer {
+    // None when the frame rate is uncapped
+    frame_duration: Option<Duration>,
+    last_frame: Instant,
+    next_deadline: Instant,
+    stats: FrameStats,
+    interval_elapsed: f32,
+    interval_frames: u32,
+    interval_worst: f32,
+}
+
+impl FrameTimer {
+    // A limit of 0 leaves the frame rate uncapped
+    pub fn new(fps_limit: u32) -> Self {
+        let now = Instant::now();
+        Self {
+            frame_duration: (fps_limit > 0).then(|| Duration::from_secs_f64(1.0 / fps_limit as f64)),
+            last_frame: now,
+            next_deadline: now,
+            stats: FrameStats::default(),
+            interval_elapsed: 0.0,
+            interval_frames: 0,
+            interval_worst: 0.0,
+        }
+    }
+
+    // Starts a new frame and returns the seconds since the previous one
+    pub fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let frame_time = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        self.interval_elapsed += frame_time;
+        self.interval_frames += 1;
+        self.interval_worst = self.interval_worst.max(frame_time);
+        if self.interval_elapsed >= STATS_INTERVAL {
+            self.stats = FrameStats {
+                fps: self.interval_frames as f32 / self.interval_elapsed,
+                average_ms: self.interval_elapsed * 1000.0 / self.interval_frames as f32,
+                worst_ms: self.interval_worst * 1000.0,
+            };
+            self.interval_elapsed = 0.0;
+            self.interval_frames = 0;
+            self.interval_worst = 0.0;
+        }
+
+        frame_time.min(MAX_FRAME_TIME)
+    }
+
+    // Blocks until the next frame is due under the frame rate cap
+    pub fn wait(&mut self) {
+        let Some(frame_duration) = self.frame_duration else {
+            return;
+        };
+        self.next_deadline += frame_duration;
+        let now = Instant::now();
+        if self.next_deadline <= now {
+            // Running behind: start a fresh schedule rather than rushing to catch up
+            self.next_deadline = now;
+            return;
+        }
+        let remaining = self.next_deadline - now;
+        if remaining > SPIN_MARGIN {
+            thread::sleep(remaining - SPIN_MARGIN);
+        }
+        while Instant::now() < self.next_deadline {
+            thread::yield_now();
+        }
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::VecDeque;
+
+use crate::structs::sample_wrapper::SampleWrapper;
+
+// Number of packets kept on screen; older ones are drawn dimmer to mimic the
+// afterglow of a phosphor display
+pub const TRAIL_LENGTH: usize = 8;
+const MAX_POINTS_PER_PACKET: usize = 1024;
+// Smoothing applied to the correlation reading so the meter stays readable
+const CORRELATION_SMOOTHING: f32 = 0.85;
+
+pub struct Goniometer {
+    // Each entry holds (side, mid) pairs in the range -1.0..=1.0
+    pub trail: VecDeque<Vec<(f32, f32)>>,
+    pub correlation: f32,
+}
+
+impl Goniometer {
+    pub fn new() -> Self {
+        Self {
+            trail: VecDeque::with_capacity(TRAIL_LENGTH),
+            correlation: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, sample_wrapper: &SampleWrapper) {
+        let (left_index, right_index) = match sample_wrapper.layout.stereo_pair() {
+            Some(pair) => pair,
+            None => {
+                // Mono sources sit on the vertical axis with perfect correlation
+                self.push_points(sample_wrapper.frames().map(|frame| (frame[0], frame[0])));
+                self.correlation = 1.0;
+                return;
+            }
+        };
+
+        let mut sum_lr = 0.0;
+        let mut sum_ll = 0.0;
+        let mut sum_rr = 0.0;
+        for frame in sample_wrapper.frames() {
+            let (left, right) = (frame[left_index], frame[right_index]);
+            sum_lr += left * right;
+            sum_ll += left * left;
+            sum_rr += right * right;
+        }
+
+        // Pearson correlation without mean removal, as on a hardware phase meter.
+        // Silence is reported as neutral rather than in phase.
+        let energy = (sum_ll * sum_rr).sqrt();
+        let packet_correlation = if energy > f32::EPSILON { sum_lr / energy } else { 0.0 };
+        self.correlation = self.correlation * CORRELATION_SMOOTHING
+            + packet_correlation * (1.0 - CORRELATION_SMOOTHING);
+
+        self.push_points(sample_wrapper.frames().map(|frame| (frame[left_index], frame[right_index])));
+    }
+
+    pub fn clear(&mut self) {
+        self.trail.clear();
+        self.correlation = 0.0;
+    }
+
+    // Rotates left/right pairs by 45 degrees so mono content is vertical and
+    // out-of-phase content is horizontal
+    fn push_points(&mut self, pairs: impl ExactSizeIterator<Item = (f32, f32)>) {
+        let step = (pairs.len() / MAX_POINTS_PER_PACKET).max(1);
+        let points = pairs
+            .step_by(step)
+            .map(|(left, right)| {
+                let side = (left - right) * std::f32::consts::FRAC_1_SQRT_2;
+                let mid = (left + right) * std::f32::consts::FRAC_1_SQRT_2;
+                (side.clamp(-1.0, 1.0), mid.clamp(-1.0, 1.0))
+            })
+            .collect();
+
+        if self.trail.len() == TRAIL_LENGTH {
+            self.trail.pop_front();
+        }
+        self.trail.push_back(points);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use sdl2::keyboard::Keycode;
+use serde::{Deserialize, Serialize};
+use crate::structs::config::KeybindingConfig;
+use crate::structs::visualisation_mode::VisualisationMode;
+
+// Everything that can be bound to a key. The snake_case names are the keys
+// of the [keybindings] section in config.toml.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    PlayPause,
+    SeekForward,
+    SeekBackward,
+    VolumeUp,
+    VolumeDown,
+    TrackNext,
+    TrackPrevious,
+    PresetNext,
+    PresetPrevious,
+    Randomise,
+    ToggleFullscreen,
+    ToggleHelp,
+    ToggleFpsOverlay,
+    Screenshot,
+    ModeBars,
+    ModeOscilloscope,
+    ModeVectorscope,
+    ModeSpectrogram,
+    ModeRadial,
+    ModeParticles,
+    ModeChromagram,
+    ModeTuner,
+}
+
+impl Action {
+    pub const ALL: [Action; 22] = [
+        Action::PlayPause,
+        Action::SeekForward,
+        Action::SeekBackward,
+        Action::VolumeUp,
+        Action::VolumeDown,
+        Action::TrackNext,
+        Action::TrackPrevious,
+        Action::PresetNext,
+        Action::PresetPrevious,
+        Action::Randomise,
+        Action::ToggleFullscreen,
+        Action::ToggleHelp,
+        Action::ToggleFpsOverlay,
+        Action::Screenshot,
+        Action::ModeBars,
+        Action::ModeOscilloscope,
+        Action::ModeVectorscope,
+        Action::ModeSpectrogram,
+        Action::ModeRadial,
+        Action::ModeParticles,
+        Action::ModeChromagram,
+        Action::ModeTuner,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::PlayPause => "Play / pause",
+            Action::SeekForward => "Skip forward 5 seconds",
+            Action::SeekBackward => "Skip back 5 seconds",
+            Action::VolumeUp => "Volume up",
+            Action::VolumeDown => "Volume down",
+            Action::TrackNext => "Next track",
+            Action::TrackPrevious => "Previous track",
+            Action::PresetNext => "Next preset",
+            Action::PresetPrevious => "Previous preset",
+            Action::Randomise => "Randomise colours",
+            Action::ToggleFullscreen => "Toggle fullscreen",
+            Action::ToggleHelp => "Show / hide this help",
+            Action::ToggleFpsOverlay => "Show / hide the frame rate",
+            Action::Screenshot => "Save a screenshot",
+            Action::ModeBars => "Bars",
+            Action::ModeOscilloscope => "Oscilloscope",
+            Action::ModeVectorscope => "Stereo vectorscope",
+            Action::ModeSpectrogram => "Spectrogram",
+            Action::ModeRadial => "Radial",
+            Action::ModeParticles => "Particles",
+            Action::ModeChromagram => "Chromagram",
+            Action::ModeTuner => "Tuner",
+        }
+    }
+
+    // SDL key names bound when the config file does not say otherwise
+    pub fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::PlayPause => &["Space"],
+            Action::SeekForward => &["Right"],
+            Action::SeekBackward => &["Left"],
+            Action::VolumeUp => &["Up"],
+            Action::VolumeDown => &["Down"],
+            Action::TrackNext => &["N"],
+            Action::TrackPrevious => &["P"],
+            Action::PresetNext => &["]"],
+            Action::PresetPrevious => &["["],
+            Action::Randomise => &["R"],
+            Action::ToggleFullscreen => &["F"],
+            Action::ToggleHelp => &["H", "F1"],
+            Action::ToggleFpsOverlay => &["F3"],
+            Action::Screenshot => &["F12"],
+            Action::ModeBars => &["1", "Keypad 1"],
+            Action::ModeOscilloscope => &["2", "Keypad 2"],
+            Action::ModeVectorscope => &["3", "Keypad 3"],
+            Action::ModeSpectrogram => &["4", "Keypad 4"],
+            Action::ModeRadial => &["5", "Keypad 5"],
+            Action::ModeParticles => &["6", "Keypad 6"],
+            Action::ModeChromagram => &["7", "Keypad 7"],
+            Action::ModeTuner => &["8", "Keypad 8"],
+        }
+    }
+
+    pub fn mode(self) -> Option<VisualisationMode> {
+        match self {
+            Action::ModeBars => Some(VisualisationMode::Bars),
+            Action::ModeOscilloscope => Some(VisualisationMode::Oscilloscope),
+            Action::ModeVectorscope => Some(VisualisationMode::Vectorscope),
+            Action::ModeSpectrogram => Some(VisualisationMode::Spectrogram),
+            Action::ModeRadial => Some(VisualisationMode::Radial),
+            Action::ModeParticles => Some(VisualisationMode::Particles),
+            Action::ModeChromagram => Some(VisualisationMode::Chromagram),
+            Action::ModeTuner => Some(VisualisationMode::Tuner),
+            _ => None,
+        }
+    }
+}
+
+// Lookup from pressed key to action, built from validated keybinding config
+pub struct Keymap {
+    actions: HashMap<Keycode, Action>,
+}
+
+impl Keymap {
+    pub fn new(config: &KeybindingConfig) -> Self {
+        let mut actions = HashMap::new();
+        for (&action, keys) in &config.bindings {
+            for key in keys {
+                if let Some(keycode) = Keycode::from_name(key) {
+                    actions.insert(keycode, action);
+                }
+            }
+        }
+        Self { actions }
+    }
+
+    pub fn action(&self, keycode: Keycode) -> Option<Action> {
+        self.actions.get(&keycode).copied()
+    }
+}
+
+// One "keys - description" line per action for the help overlay
+pub fn help_lines(config: &KeybindingConfig) -> Vec<String> {
+    Action::ALL
+        .iter()
+        .filter_map(|action| {
+            let keys = config.bindings.get(action).filter(|keys| !keys.is_empty())?;
+            Some(format!("{}  -  {}", keys.join(" / "), action.description()))
+        })
+        .collect()
+}
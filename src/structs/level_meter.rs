@@ -0,0 +1,201 @@
+use crate::structs::loudness::LoudnessMeter;
+use crate::structs::sample_wrapper::SampleWrapper;
+
+// Peak meter fall-back of 20 dB in 1.7 s, as for an IEC 60268-10 type I PPM
+const PEAK_FALL_DB_PER_SECOND: f32 = 20.0 / 1.7;
+const PEAK_HOLD_SECONDS: f32 = 2.0;
+// VU-style averaging that reaches 99% of a step in 300 ms
+const RMS_TIME_CONSTANT: f32 = 0.3 / 4.605;
+// Lowest level the meters show
+pub const METER_FLOOR_DB: f32 = -60.0;
+
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+pub fn to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-6).log10()
+}
+
+// Interpolates each channel at four times the sample rate so peaks between
+// samples show up, as ITU-R BS.1770 true-peak metering does. The filter is a
+// Hann-windowed sinc split into one polyphase branch per output phase.
+#[derive(Clone)]
+struct TruePeakOversampler {
+    phases: [[f32; TAPS_PER_PHASE]; OVERSAMPLING],
+    history: [f32; TAPS_PER_PHASE],
+    position: usize,
+}
+
+impl TruePeakOversampler {
+    fn new() -> Self {
+        let length = OVERSAMPLING * TAPS_PER_PHASE;
+        let centre = (length - 1) as f32 / 2.0;
+        let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+        for n in 0..length {
+            let x = (n as f32 - centre) / OVERSAMPLING as f32;
+            let sinc = if x == 0.0 { 1.0 } else { (std::f32::consts::PI * x).sin() / (std::f32::consts::PI * x) };
+            let window = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * (n as f32 + 0.5) / length as f32).cos();
+            phases[n % OVERSAMPLING][n / OVERSAMPLING] = sinc * window;
+        }
+        // Each branch should pass DC at unity gain
+        for phase in phases.iter_mut() {
+            let sum: f32 = phase.iter().sum();
+            for tap in phase.iter_mut() {
+                *tap /= sum;
+            }
+        }
+        Self { phases, history: [0.0; TAPS_PER_PHASE], position: 0 }
+    }
+
+    // Largest absolute value among the interpolated points for this sample
+    fn process(&mut self, sample: f32) -> f32 {
+        self.history[self.position] = sample;
+        self.position = (self.position + 1) % TAPS_PER_PHASE;
+        let mut peak: f32 = 0.0;
+        for phase in &self.phases {
+            // Tap k multiplies the sample k steps back
+            let value: f32 = phase
+                .iter()
+                .enumerate()
+                .map(|(k, tap)| tap * self.history[(self.position + TAPS_PER_PHASE - 1 - k) % TAPS_PER_PHASE])
+                .sum();
+            peak = peak.max(value.abs());
+        }
+        peak
+    }
+}
+
+// Levels of one channel as linear amplitudes, 1.0 being full scale
+#[derive(Clone)]
+pub struct ChannelMeter {
+    pub peak: f32,
+    pub peak_hold: f32,
+    pub rms: f32,
+    pub true_peak: f32,
+    // Highest true peak since the last reset
+    pub true_peak_max: f32,
+    hold_time: f32,
+    mean_square: f32,
+    oversampler: TruePeakOversampler,
+}
+
+impl ChannelMeter {
+    pub fn new() -> Self {
+        Self {
+            peak: 0.0,
+            peak_hold: 0.0,
+            rms: 0.0,
+            true_peak: 0.0,
+            true_peak_max: 0.0,
+            hold_time: 0.0,
+            mean_square: 0.0,
+            oversampler: TruePeakOversampler::new(),
+        }
+    }
+}
+
+// Per-channel peak, RMS and true-peak meters with ballistics, plus the
+// programme loudness
+pub struct LevelMeters {
+    pub channels: Vec<ChannelMeter>,
+    pub loudness: LoudnessMeter,
+}
+
+impl LevelMeters {
+    pub fn new() -> Self {
+        Self { channels: Vec::new(), loudness: LoudnessMeter::new() }
+    }
+
+    pub fn process(&mut self, sample_wrapper: &SampleWrapper) {
+        let channel_count = sample_wrapper.channels();
+        if self.channels.len() != channel_count {
+            self.channels = vec![ChannelMeter::new(); channel_count];
+        }
+        let sample_rate = sample_wrapper.sample_rate as f32;
+        let fall = 10f32.powf(-PEAK_FALL_DB_PER_SECOND / 20.0 / sample_rate);
+        let rms_follow = 1.0 - (-1.0 / (RMS_TIME_CONSTANT * sample_rate)).exp();
+        let frame_seconds = 1.0 / sample_rate;
+
+        for frame in sample_wrapper.frames() {
+            for (meter, &sample) in self.channels.iter_mut().zip(frame) {
+                let level = sample.abs();
+                meter.peak = level.max(meter.peak * fall);
+                meter.mean_square += (sample * sample - meter.mean_square) * rms_follow;
+
+                let true_peak = meter.oversampler.process(sample).max(level);
+                meter.true_peak = true_peak.max(meter.true_peak * fall);
+                meter.true_peak_max = meter.true_peak_max.max(true_peak);
+
+                // The hold marker stays put for a while, then falls like the peak
+                if meter.peak >= meter.peak_hold {
+                    meter.peak_hold = meter.peak;
+                    meter.hold_time = 0.0;
+                } else {
+                    meter.hold_time += frame_seconds;
+                    if meter.hold_time > PEAK_HOLD_SECONDS {
+                        meter.peak_hold = (meter.peak_hold * fall).max(meter.peak);
+                    }
+                }
+            }
+        }
+        for meter in self.channels.iter_mut() {
+            meter.rms = meter.mean_square.sqrt();
+        }
+        self.loudness.process(sample_wrapper);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    // Highest true peak of any channel since the last reset
+    pub fn true_peak_max(&self) -> f32 {
+        self.channels.iter().map(|meter| meter.true_peak_max).fold(0.0, f32::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::channel_layout::ChannelLayout;
+    use crate::structs::test_signals::{frames, sine, sine_with_phase, SAMPLE_RATE};
+    use std::f32::consts::PI;
+
+    fn mono(samples: Vec<f32>) -> SampleWrapper {
+        SampleWrapper { samples, layout: ChannelLayout::Mono, sample_rate: SAMPLE_RATE }
+    }
+
+    #[test]
+    fn true_peak_finds_peaks_between_samples() {
+        // At a quarter of the sample rate with a 45 degree phase, every sample
+        // lands at 0.707 of the real peak
+        let mut meters = LevelMeters::new();
+        meters.process(&mono(sine_with_phase(SAMPLE_RATE as f32 / 4.0, PI / 4.0, 1.0, frames(0.1))));
+        let meter = &meters.channels[0];
+        assert!((to_db(meter.peak) + 3.01).abs() < 0.05, "sample peak {}", to_db(meter.peak));
+        assert!(to_db(meters.true_peak_max()).abs() < 0.5, "true peak {}", to_db(meters.true_peak_max()));
+    }
+
+    #[test]
+    fn rms_of_a_sine_settles_3_db_below_its_peak() {
+        let mut meters = LevelMeters::new();
+        meters.process(&mono(sine(1000.0, 0.5, frames(1.0))));
+        let meter = &meters.channels[0];
+        assert!((to_db(meter.rms) - (to_db(0.5) - 3.01)).abs() < 0.1, "rms {}", to_db(meter.rms));
+        assert!((meter.peak - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn peak_falls_back_at_the_ppm_rate_after_the_hold() {
+        let mut meters = LevelMeters::new();
+        meters.process(&mono(vec![1.0]));
+        meters.process(&mono(vec![0.0; SAMPLE_RATE as usize]));
+        let meter = &meters.channels[0];
+        assert!((to_db(meter.peak) + PEAK_FALL_DB_PER_SECOND).abs() < 0.1, "peak {}", to_db(meter.peak));
+        assert_eq!(meter.peak_hold, 1.0);
+
+        meters.process(&mono(vec![0.0; (SAMPLE_RATE * 2) as usize]));
+        let meter = &meters.channels[0];
+        assert!(meter.peak_hold < 1.0 && meter.peak_hold >= meter.peak);
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::VecDeque;
+use crate::structs::channel_layout::ChannelLayout;
+use crate::structs::sample_wrapper::SampleWrapper;
+
+// EBU R128 / ITU-R BS.1770 windows, in 100 ms blocks
+const BLOCK_SECONDS: f64 = 0.1;
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+// Weight of the rear channels of a 5.1 layout
+const SURROUND_WEIGHT: f64 = 1.41;
+
+// Transposed direct form II biquad
+#[derive(Clone, Copy)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+}
+
+// The BS.1770 K-weighting curve: a high shelf modelling the head, then a
+// high pass. Coefficients are derived for any sample rate from the analogue
+// prototypes, matching the published 48 kHz values.
+#[derive(Clone, Copy)]
+struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+
+        let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad {
+            b0: (vh + vb * k / q + k * k) / a0,
+            b1: 2.0 * (k * k - vh) / a0,
+            b2: (vh - vb * k / q + k * k) / a0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            z1: 0.0,
+            z2: 0.0,
+        };
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad {
+            b0: 1.0,
+            b1: -2.0,
+            b2: 1.0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            z1: 0.0,
+            z2: 0.0,
+        };
+        Self { shelf, high_pass }
+    }
+
+    fn process(&mut self, sample: f64) -> f64 {
+        self.high_pass.process(self.shelf.process(sample))
+    }
+}
+
+// How much each channel counts towards the loudness. The layout does not
+// record channel positions, so only the usual 5.1 order (L, R, C, LFE, Ls,
+// Rs) gets the surround weights and the LFE left out; anything else counts
+// every channel equally.
+fn channel_weight(layout: ChannelLayout, channel: usize) -> f64 {
+    match (layout, channel) {
+        (ChannelLayout::Surround { channels: 6, has_front_pair: true }, 3) => 0.0,
+        (ChannelLayout::Surround { channels: 6, has_front_pair: true }, 4 | 5) => SURROUND_WEIGHT,
+        _ => 1.0,
+    }
+}
+
+fn lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+// EBU R128 loudness: momentary (400 ms), short-term (3 s) and gated
+// integrated loudness since the last reset, all in LUFS. Readings are None
+// until enough audio has arrived, or for digital silence.
+pub struct LoudnessMeter {
+    pub momentary: Option<f32>,
+    pub short_term: Option<f32>,
+    pub integrated: Option<f32>,
+    layout: ChannelLayout,
+    sample_rate: u32,
+    filters: Vec<KWeighting>,
+    // Weighted sum of squares of the block being filled
+    block_sum: f64,
+    block_frames: usize,
+    // Mean power of the most recent 100 ms blocks, newest last
+    blocks: VecDeque<f64>,
+    // Power of every 400 ms gating block since the reset, overlapping by 75%
+    gating_blocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new() -> Self {
+        Self {
+            momentary: None,
+            short_term: None,
+            integrated: None,
+            layout: ChannelLayout::Stereo,
+            sample_rate: 0,
+            filters: Vec::new(),
+            block_sum: 0.0,
+            block_frames: 0,
+            blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+            gating_blocks: Vec::new(),
+        }
+    }
+
+    pub fn process(&mut self, sample_wrapper: &SampleWrapper) {
+        // The filters and block length depend on the format, so a change starts over
+        if sample_wrapper.layout != self.layout || sample_wrapper.sample_rate != self.sample_rate {
+            self.reset();
+            self.layout = sample_wrapper.layout;
+            self.sample_rate = sample_wrapper.sample_rate;
+            self.filters = vec![KWeighting::new(self.sample_rate); self.layout.channel_count()];
+        }
+        let block_length = ((self.sample_rate as f64 * BLOCK_SECONDS).round() as usize).max(1);
+        let weights: Vec<f64> = (0..self.filters.len()).map(|channel| channel_weight(self.layout, channel)).collect();
+
+        for frame in sample_wrapper.frames() {
+            for ((filter, &weight), &sample) in self.filters.iter_mut().zip(&weights).zip(frame) {
+                let filtered = filter.process(sample as f64);
+                self.block_sum += weight * filtered * filtered;
+            }
+            self.block_frames += 1;
+            if self.block_frames == block_length {
+                self.finish_block(self.block_sum / block_length as f64);
+                self.block_sum = 0.0;
+                self.block_frames = 0;
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn finish_block(&mut self, power: f64) {
+        if self.blocks.len() == SHORT_TERM_BLOCKS {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back(power);
+
+        let window_loudness = |count: usize| {
+            if self.blocks.len() < count {
+                return None;
+            }
+            let mean = self.blocks.iter().rev().take(count).sum::<f64>() / count as f64;
+            (mean > 0.0).then(|| lufs(mean) as f32)
+        };
+        self.momentary = window_loudness(MOMENTARY_BLOCKS);
+        self.short_term = window_loudness(SHORT_TERM_BLOCKS);
+
+        if self.blocks.len() >= MOMENTARY_BLOCKS {
+            let gating_power = self.blocks.iter().rev().take(MOMENTARY_BLOCKS).sum::<f64>() / MOMENTARY_BLOCKS as f64;
+            self.gating_blocks.push(gating_power);
+            self.integrated = integrated_loudness(&self.gating_blocks);
+        }
+    }
+}
+
+// Mean of the gating blocks above the absolute gate, then of those within
+// 10 LU of that mean
+fn integrated_loudness(gating_blocks: &[f64]) -> Option<f32> {
+    let gated_mean = |threshold: f64| {
+        let (sum, count) = gating_blocks
+            .iter()
+            .filter(|&&power| power > 0.0 && lufs(power) > threshold)
+            .fold((0.0, 0), |(sum, count), &power| (sum + power, count + 1));
+        (count > 0).then(|| sum / count as f64)
+    };
+    let absolute = gated_mean(ABSOLUTE_GATE_LUFS)?;
+    let relative = gated_mean(lufs(absolute) + RELATIVE_GATE_LU)?;
+    Some(lufs(relative) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::test_signals::{frames, sine, SAMPLE_RATE};
+
+    // Stereo sine with the same peak level in both channels
+    fn stereo_sine(frequency: f32, level_db: f32, seconds: f32) -> SampleWrapper {
+        let amplitude = 10f32.powf(level_db / 20.0);
+        let samples = sine(frequency, amplitude, frames(seconds)).into_iter().flat_map(|value| [value, value]).collect();
+        SampleWrapper { samples, layout: ChannelLayout::Stereo, sample_rate: SAMPLE_RATE }
+    }
+
+    #[test]
+    fn k_weighting_matches_the_published_coefficients() {
+        let filter = KWeighting::new(48000);
+        assert!((filter.shelf.b0 - 1.53512485958697).abs() < 1e-9);
+        assert!((filter.shelf.a1 + 1.69065929318241).abs() < 1e-9);
+        assert!((filter.high_pass.a1 + 1.99004745483398).abs() < 1e-9);
+        assert!((filter.high_pass.a2 - 0.99007225036621).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reference_sine_reads_minus_23_lufs() {
+        // EBU Tech 3341 test 1: a 1 kHz sine at -23 dBFS in both channels
+        let mut meter = LoudnessMeter::new();
+        meter.process(&stereo_sine(1000.0, -23.0, 20.0));
+        for reading in [meter.momentary, meter.short_term, meter.integrated] {
+            let value = reading.unwrap();
+            assert!((value + 23.0).abs() < 0.1, "read {}", value);
+        }
+    }
+
+    #[test]
+    fn relative_gate_ignores_quiet_passages() {
+        // EBU Tech 3341 test 3: -36 dBFS for 10 s, -23 for 60 s, -36 for 10 s
+        let mut meter = LoudnessMeter::new();
+        for (level, seconds) in [(-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0)] {
+            meter.process(&stereo_sine(1000.0, level, seconds));
+        }
+        let integrated = meter.integrated.unwrap();
+        assert!((integrated + 23.0).abs() < 0.1, "read {}", integrated);
+    }
+
+    #[test]
+    fn readings_wait_for_enough_audio() {
+        let mut meter = LoudnessMeter::new();
+        meter.process(&stereo_sine(1000.0, -23.0, 0.35));
+        assert_eq!(meter.momentary, None);
+        meter.process(&stereo_sine(1000.0, -23.0, 0.1));
+        assert!(meter.momentary.is_some());
+        assert_eq!(meter.short_term, None);
+
+        let mut silent = LoudnessMeter::new();
+        silent.process(&stereo_sine(1000.0, -200.0, 5.0));
+        assert_eq!(silent.integrated, None);
+    }
+}
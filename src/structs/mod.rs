@@ -6,3 +6,34 @@ pub mod colour_slider;
 pub mod visualisation_state;
 pub mod element;
 pub mod buttons;
+pub mod visualisation_mode;
+pub mod waveform_buffer;
+pub mod oscilloscope;
+pub mod channel_layout;
+pub mod goniometer;
+pub mod colour_map;
+pub mod spectrogram;
+pub mod beat_detector;
+pub mod radial;
+pub mod particle_system;
+pub mod colour_space;
+pub mod palette;
+pub mod colour_randomiser;
+pub mod config_paths;
+pub mod config;
+pub mod preset;
+pub mod playlist;
+pub mod cli_args;
+pub mod keybindings;
+pub mod control_visibility;
+pub mod widget;
+pub mod frame_timer;
+pub mod tempo_estimator;
+pub mod features;
+pub mod chroma;
+pub mod pitch_detector;
+pub mod loudness;
+pub mod level_meter;
+pub mod constant_q;
+#[cfg(test)]
+pub mod test_signals;
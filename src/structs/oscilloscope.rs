@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use crate::structs::waveform_buffer::WaveformBuffer;
+
+// Visible window widths in milliseconds, cycled by the timebase button
+pub const TIMEBASES_MS: [u32; 5] = [5, 10, 20, 50, 100];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScopeChannels {
+    Summed,
+    PerChannel,
+}
+
+impl ScopeChannels {
+    pub fn next(self) -> Self {
+        match self {
+            ScopeChannels::Summed => ScopeChannels::PerChannel,
+            ScopeChannels::PerChannel => ScopeChannels::Summed,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ScopeChannels::Summed => "Summed",
+            ScopeChannels::PerChannel => "Per channel",
+        }
+    }
+}
+
+// Finds the first frame of the window to display. Searches backwards from
+// the newest complete window for a rising crossing of `trigger_level` on the
+// summed signal so consecutive frames line up; free-runs on the newest
+// samples when no crossing is found (silence, DC, very low frequencies).
+pub fn find_trigger_frame(buffer: &WaveformBuffer, window_frames: usize, trigger_level: f32) -> usize {
+    let frame_count = buffer.frame_count();
+    if frame_count <= window_frames {
+        return 0;
+    }
+
+    let newest_start = frame_count - window_frames;
+    // Look back at most one window so the trace never lags far behind the audio
+    let oldest_start = newest_start.saturating_sub(window_frames).max(1);
+
+    for start in (oldest_start..=newest_start).rev() {
+        let previous = buffer.summed(start - 1);
+        let current = buffer.summed(start);
+        if previous < trigger_level && current >= trigger_level {
+            return start;
+        }
+    }
+
+    newest_start
+}
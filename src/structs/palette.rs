@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use sdl2::pixels::Color;
+use crate::structs::colour_space::{
+    hsl_to_rgb, hsv_to_rgb, lerp_hue, oklch_to_rgb, rgb_to_hsl, rgb_to_hsv, rgb_to_oklch,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColourStop {
+    // Position along the gradient, 0.0..=1.0
+    pub position: f32,
+    pub colour: Color,
+}
+
+// What the gradient position of a bar is taken from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GradientMode {
+    BarIndex,
+    Amplitude,
+    Height,
+}
+
+impl GradientMode {
+    pub fn next(self) -> Self {
+        match self {
+            GradientMode::BarIndex => GradientMode::Amplitude,
+            GradientMode::Amplitude => GradientMode::Height,
+            GradientMode::Height => GradientMode::BarIndex,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GradientMode::BarIndex => "Index",
+            GradientMode::Amplitude => "Amplitude",
+            GradientMode::Height => "Height",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    Rgb,
+    Hsv,
+    Hsl,
+    Oklch,
+}
+
+impl Interpolation {
+    pub fn next(self) -> Self {
+        match self {
+            Interpolation::Rgb => Interpolation::Hsv,
+            Interpolation::Hsv => Interpolation::Hsl,
+            Interpolation::Hsl => Interpolation::Oklch,
+            Interpolation::Oklch => Interpolation::Rgb,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Interpolation::Rgb => "RGB",
+            Interpolation::Hsv => "HSV",
+            Interpolation::Hsl => "HSL",
+            Interpolation::Oklch => "OKLCH",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub name: String,
+    // Always sorted by position and never empty
+    pub stops: Vec<ColourStop>,
+    pub interpolation: Interpolation,
+}
+
+impl Palette {
+    pub fn new(name: &str, colours: &[Color], interpolation: Interpolation) -> Self {
+        let last = colours.len().saturating_sub(1).max(1) as f32;
+        let mut stops: Vec<ColourStop> = colours
+            .iter()
+            .enumerate()
+            .map(|(index, &colour)| ColourStop { position: index as f32 / last, colour })
+            .collect();
+        if stops.is_empty() {
+            stops.push(ColourStop { position: 0.0, colour: Color::RGB(47, 198, 18) });
+        }
+        Self { name: name.to_string(), stops, interpolation }
+    }
+
+    pub fn built_in() -> Vec<Palette> {
+        vec![
+            Palette::new("Classic", &[Color::RGB(47, 198, 18)], Interpolation::Rgb),
+            Palette::new(
+                "Sunset",
+                &[Color::RGB(255, 94, 77), Color::RGB(255, 166, 0), Color::RGB(255, 231, 122)],
+                Interpolation::Oklch,
+            ),
+            Palette::new(
+                "Ocean",
+                &[Color::RGB(0, 48, 143), Color::RGB(0, 150, 199), Color::RGB(144, 224, 239)],
+                Interpolation::Oklch,
+            ),
+            Palette::new(
+                "Fire",
+                &[Color::RGB(128, 0, 0), Color::RGB(230, 60, 0), Color::RGB(255, 200, 40)],
+                Interpolation::Hsl,
+            ),
+            Palette::new(
+                "Neon",
+                &[Color::RGB(255, 0, 170), Color::RGB(0, 229, 255)],
+                Interpolation::Hsv,
+            ),
+            Palette::new(
+                "Rainbow",
+                &[Color::RGB(255, 0, 0), Color::RGB(0, 255, 0), Color::RGB(0, 0, 255)],
+                Interpolation::Hsv,
+            ),
+        ]
+    }
+
+    pub fn sample(&self, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let first = self.stops[0];
+        if t <= first.position || self.stops.len() == 1 {
+            return first.colour;
+        }
+        for pair in self.stops.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if t <= to.position {
+                let span = to.position - from.position;
+                let local = if span > 0.0 { (t - from.position) / span } else { 1.0 };
+                return interpolate(from.colour, to.colour, local, self.interpolation);
+            }
+        }
+        self.stops[self.stops.len() - 1].colour
+    }
+
+    pub fn stop_colours(&self) -> Vec<Color> {
+        self.stops.iter().map(|stop| stop.colour).collect()
+    }
+
+    pub fn set_stop_colour(&mut self, index: usize, colour: Color) {
+        if let Some(stop) = self.stops.get_mut(index) {
+            stop.colour = colour;
+        }
+    }
+
+    pub fn set_stop_colours(&mut self, colours: &[Color]) {
+        for (stop, &colour) in self.stops.iter_mut().zip(colours) {
+            stop.colour = colour;
+        }
+    }
+}
+
+pub fn interpolate(from: Color, to: Color, t: f32, interpolation: Interpolation) -> Color {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    match interpolation {
+        Interpolation::Rgb => Color::RGB(
+            lerp(from.r as f32, to.r as f32).round() as u8,
+            lerp(from.g as f32, to.g as f32).round() as u8,
+            lerp(from.b as f32, to.b as f32).round() as u8,
+        ),
+        Interpolation::Hsv => {
+            let (h0, s0, v0) = rgb_to_hsv(from);
+            let (h1, s1, v1) = rgb_to_hsv(to);
+            let (h0, h1) = settle_greys(h0, s0, h1, s1);
+            hsv_to_rgb(lerp_hue(h0, h1, t), lerp(s0, s1), lerp(v0, v1))
+        }
+        Interpolation::Hsl => {
+            let (h0, s0, l0) = rgb_to_hsl(from);
+            let (h1, s1, l1) = rgb_to_hsl(to);
+            let (h0, h1) = settle_greys(h0, s0, h1, s1);
+            hsl_to_rgb(lerp_hue(h0, h1, t), lerp(s0, s1), lerp(l0, l1))
+        }
+        Interpolation::Oklch => {
+            let (l0, c0, h0) = rgb_to_oklch(from);
+            let (l1, c1, h1) = rgb_to_oklch(to);
+            let (h0, h1) = settle_greys(h0, c0 * 10.0, h1, c1 * 10.0);
+            oklch_to_rgb(lerp(l0, l1), lerp(c0, c1), lerp_hue(h0, h1, t))
+        }
+    }
+}
+
+// Greys have no meaningful hue; borrow the other end's so the blend does not
+// sweep through unrelated colours
+fn settle_greys(h0: f32, s0: f32, h1: f32, s1: f32) -> (f32, f32) {
+    const GREY: f32 = 0.02;
+    match (s0 < GREY, s1 < GREY) {
+        (true, false) => (h1, h1),
+        (false, true) => (h0, h0),
+        _ => (h0, h1),
+    }
+}
@@ -0,0 +1,154 @@
+use rand::rngs::ThreadRng;
+use rand::Rng;
+
+// Hard cap on live particles; the pool is allocated once up front
+pub const MAX_PARTICLES: usize = 2000;
+
+const BASE_SPAWN_RATE: f32 = 40.0;
+const ENERGY_SPAWN_RATE: f32 = 400.0;
+const BEAT_BURST: usize = 60;
+const BASE_SPEED: f32 = 0.25;
+const ENERGY_SPEED: f32 = 0.6;
+const GRAVITY: f32 = 0.3;
+const DRAG: f32 = 0.6;
+
+// Positions and velocities are normalised to the visualisation area, so
+// (0, 0) is its top left corner and (1, 1) the bottom right
+#[derive(Clone, Copy, Default)]
+pub struct Particle {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub age: f32,
+    pub lifetime: f32,
+    pub size: f32,
+    // Palette position, taken from the music's brightness at spawn
+    pub tint: f32,
+    pub alive: bool,
+}
+
+impl Particle {
+    // 0.0 when spawned, 1.0 when about to die
+    pub fn life_fraction(&self) -> f32 {
+        (self.age / self.lifetime).clamp(0.0, 1.0)
+    }
+}
+
+pub struct Emitter {
+    pub x: f32,
+    pub y: f32,
+    // Direction of travel in radians, 0 pointing right and -PI/2 straight up
+    pub direction: f32,
+    pub spread: f32,
+    spawn_debt: f32,
+}
+
+impl Emitter {
+    pub fn new(x: f32, y: f32, direction: f32, spread: f32) -> Self {
+        Self { x, y, direction, spread, spawn_debt: 0.0 }
+    }
+}
+
+pub struct ParticleSystem {
+    pub particles: Vec<Particle>,
+    free_slots: Vec<usize>,
+    pub emitters: Vec<Emitter>,
+    last_beat_count: u64,
+    rng: ThreadRng,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        let up = -std::f32::consts::FRAC_PI_2;
+        Self {
+            particles: vec![Particle::default(); MAX_PARTICLES],
+            free_slots: (0..MAX_PARTICLES).rev().collect(),
+            emitters: vec![
+                Emitter::new(0.2, 1.0, up, 0.5),
+                Emitter::new(0.5, 1.0, up, 0.3),
+                Emitter::new(0.8, 1.0, up, 0.5),
+            ],
+            last_beat_count: 0,
+            rng: rand::rng(),
+        }
+    }
+
+    pub fn live_count(&self) -> usize {
+        MAX_PARTICLES - self.free_slots.len()
+    }
+
+    // `energy` is the bass energy from the beat detector (0.0..=1.0) and
+    // `beat_count` its running total, so each beat triggers exactly one burst.
+    // New particles are tinted by `brightness` (0.0..=1.0).
+    pub fn update(&mut self, dt: f32, energy: f32, beat_count: u64, brightness: f32) {
+        // Long stalls (window drags, mode switches) would otherwise spawn a flood
+        let dt = dt.min(0.1);
+        for (index, particle) in self.particles.iter_mut().enumerate() {
+            if !particle.alive {
+                continue;
+            }
+            particle.age += dt;
+            if particle.age >= particle.lifetime || particle.y > 1.2 {
+                particle.alive = false;
+                self.free_slots.push(index);
+                continue;
+            }
+            particle.vy += GRAVITY * dt;
+            particle.vx -= particle.vx * DRAG * dt;
+            particle.vy -= particle.vy * DRAG * dt;
+            particle.x += particle.vx * dt;
+            particle.y += particle.vy * dt;
+        }
+
+        let is_beat = beat_count != self.last_beat_count;
+        self.last_beat_count = beat_count;
+
+        for emitter_index in 0..self.emitters.len() {
+            let emitter = &mut self.emitters[emitter_index];
+            emitter.spawn_debt += (BASE_SPAWN_RATE + energy * ENERGY_SPAWN_RATE) * dt;
+            let mut count = emitter.spawn_debt as usize;
+            emitter.spawn_debt -= count as f32;
+            if is_beat {
+                count += BEAT_BURST;
+            }
+            for _ in 0..count {
+                if !self.spawn(emitter_index, energy, is_beat, brightness) {
+                    // Budget exhausted, drop the rest of this frame's spawns
+                    return;
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.alive = false;
+        }
+        self.free_slots = (0..MAX_PARTICLES).rev().collect();
+    }
+
+    fn spawn(&mut self, emitter_index: usize, energy: f32, is_beat: bool, brightness: f32) -> bool {
+        let slot = match self.free_slots.pop() {
+            Some(slot) => slot,
+            None => return false,
+        };
+        let emitter = &self.emitters[emitter_index];
+        let angle = emitter.direction + self.rng.random_range(-emitter.spread..=emitter.spread);
+        let boost = if is_beat { 1.5 } else { 1.0 };
+        let speed = (BASE_SPEED + energy * ENERGY_SPEED) * boost * self.rng.random_range(0.6..=1.2);
+
+        self.particles[slot] = Particle {
+            x: emitter.x,
+            y: emitter.y,
+            vx: angle.cos() * speed,
+            vy: angle.sin() * speed,
+            age: 0.0,
+            lifetime: self.rng.random_range(0.8..=2.0),
+            size: self.rng.random_range(2.0..=5.0),
+            tint: brightness,
+            alive: true,
+        };
+        true
+    }
+}
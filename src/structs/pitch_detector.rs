@@ -0,0 +1,196 @@
+use crate::structs::chroma::PITCH_CLASSES;
+
+// Just below the low E of a bass guitar, up to well past the top of a voice
+const MIN_FREQUENCY: f32 = 40.0;
+const MAX_FREQUENCY: f32 = 2000.0;
+// YIN's absolute threshold on the normalised difference function
+const YIN_THRESHOLD: f32 = 0.15;
+// Readings less certain than this are not shown
+const MIN_CONFIDENCE: f32 = 0.8;
+// Frames quieter than this RMS are treated as silence
+const SILENCE_RMS: f32 = 0.01;
+// Seconds the last note stays up after the signal stops being pitched
+const HOLD_SECONDS: f32 = 0.5;
+// Time constant of the needle, in seconds
+const NEEDLE_TIME_CONSTANT: f32 = 0.08;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pitch {
+    pub frequency: f32,
+    // MIDI note number of the nearest equal-tempered note, 69 being A440
+    pub note: i32,
+    // Distance from that note, -50.0..=50.0
+    pub cents: f32,
+}
+
+impl Pitch {
+    pub fn from_frequency(frequency: f32) -> Self {
+        let exact = 69.0 + 12.0 * (frequency / 440.0).log2();
+        let note = exact.round() as i32;
+        Self { frequency, note, cents: (exact - note as f32) * 100.0 }
+    }
+
+    // Scientific pitch notation, e.g. "A4"
+    pub fn note_name(&self) -> String {
+        format!("{}{}", PITCH_CLASSES[self.note.rem_euclid(12) as usize], self.note.div_euclid(12) - 1)
+    }
+}
+
+// Follows the pitch of a monophonic signal for the tuner, holding the last
+// note briefly through gaps and easing the needle between readings
+pub struct PitchDetector {
+    pub pitch: Option<Pitch>,
+    // Smoothed certainty of the current reading, 0.0..=1.0
+    pub confidence: f32,
+    since_last_reading: f32,
+}
+
+impl PitchDetector {
+    pub fn new() -> Self {
+        Self { pitch: None, confidence: 0.0, since_last_reading: 0.0 }
+    }
+
+    // `elapsed` is the seconds of audio since the previous call
+    pub fn process(&mut self, samples: &[f32], sample_rate: u32, elapsed: f32) {
+        let follow = 1.0 - (-elapsed / NEEDLE_TIME_CONSTANT).exp();
+        match detect_pitch(samples, sample_rate) {
+            Some((frequency, confidence)) if confidence >= MIN_CONFIDENCE => {
+                let mut reading = Pitch::from_frequency(frequency);
+                // Ease the needle while the note stays the same, jump when it changes
+                if let Some(previous) = self.pitch.filter(|previous| previous.note == reading.note) {
+                    reading.cents = previous.cents + (reading.cents - previous.cents) * follow;
+                    reading.frequency = previous.frequency + (reading.frequency - previous.frequency) * follow;
+                }
+                self.pitch = Some(reading);
+                self.confidence += (confidence - self.confidence) * follow;
+                self.since_last_reading = 0.0;
+            }
+            _ => {
+                self.confidence -= self.confidence * follow;
+                self.since_last_reading += elapsed;
+                if self.since_last_reading > HOLD_SECONDS {
+                    self.pitch = None;
+                }
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+// Samples needed to detect the lowest supported pitch
+pub fn window_size(sample_rate: u32) -> usize {
+    2 * (sample_rate as f32 / MIN_FREQUENCY).ceil() as usize
+}
+
+// Fundamental frequency and confidence (0.0..=1.0) of the samples, using the
+// YIN algorithm (de Cheveigné and Kawahara, 2002). Returns None for silence
+// or when no period fits in the window.
+pub fn detect_pitch(samples: &[f32], sample_rate: u32) -> Option<(f32, f32)> {
+    let rms = (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len().max(1) as f32).sqrt();
+    if rms < SILENCE_RMS {
+        return None;
+    }
+    let width = samples.len() / 2;
+    let min_lag = ((sample_rate as f32 / MAX_FREQUENCY) as usize).max(2);
+    let max_lag = ((sample_rate as f32 / MIN_FREQUENCY).ceil() as usize).min(width.saturating_sub(1));
+    if min_lag + 2 > max_lag {
+        return None;
+    }
+
+    // Cumulative mean normalised difference, which starts at 1.0 and dips
+    // towards 0.0 at lags matching the period
+    let mut normalised = vec![1.0; max_lag + 1];
+    let mut running_sum = 0.0;
+    for lag in 1..=max_lag {
+        let difference: f32 = (0..width)
+            .map(|i| {
+                let delta = samples[i] - samples[i + lag];
+                delta * delta
+            })
+            .sum();
+        running_sum += difference;
+        normalised[lag] = if running_sum > 0.0 { difference * lag as f32 / running_sum } else { 1.0 };
+    }
+
+    // The first dip under the threshold avoids picking a multiple of the
+    // period; failing that, the deepest dip is used
+    let lag = match (min_lag..max_lag).find(|&lag| normalised[lag] < YIN_THRESHOLD) {
+        Some(mut lag) => {
+            while lag + 1 < max_lag && normalised[lag + 1] < normalised[lag] {
+                lag += 1;
+            }
+            lag
+        }
+        None => (min_lag..max_lag).min_by(|&a, &b| normalised[a].total_cmp(&normalised[b]))?,
+    };
+
+    let (before, here, after) = (normalised[lag - 1], normalised[lag], normalised[lag + 1]);
+    let curvature = before - 2.0 * here + after;
+    let offset = if curvature > 0.0 { 0.5 * (before - after) / curvature } else { 0.0 };
+    let period = lag as f32 + offset.clamp(-0.5, 0.5);
+    Some((sample_rate as f32 / period, (1.0 - here).clamp(0.0, 1.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::test_signals::{sines, white_noise, SAMPLE_RATE};
+
+    // Harmonics fall off as 1/n, like a bowed or plucked string
+    fn tone(frequency: f32, harmonics: usize) -> Vec<f32> {
+        let partials: Vec<(f32, f32)> = (1..=harmonics).map(|n| (frequency * n as f32, 0.5 / n as f32)).collect();
+        sines(&partials, window_size(SAMPLE_RATE))
+    }
+
+    #[test]
+    fn sine_frequency_is_found() {
+        for frequency in [82.41, 196.0, 440.0, 1318.5] {
+            let (detected, confidence) = detect_pitch(&tone(frequency, 1), SAMPLE_RATE).unwrap();
+            assert!((detected - frequency).abs() < frequency * 0.002, "{} detected as {}", frequency, detected);
+            assert!(confidence > 0.9);
+        }
+    }
+
+    #[test]
+    fn harmonics_do_not_fool_it() {
+        let (detected, _) = detect_pitch(&tone(110.0, 8), SAMPLE_RATE).unwrap();
+        assert!((detected - 110.0).abs() < 0.5, "detected {}", detected);
+    }
+
+    #[test]
+    fn notes_and_cents() {
+        let a = Pitch::from_frequency(440.0);
+        assert_eq!(a.note_name(), "A4");
+        assert!(a.cents.abs() < 1e-3);
+        let sharp = Pitch::from_frequency(445.0);
+        assert_eq!(sharp.note_name(), "A4");
+        assert!((sharp.cents - 19.56).abs() < 0.1, "cents {}", sharp.cents);
+        let flat_c = Pitch::from_frequency(259.0);
+        assert_eq!(flat_c.note_name(), "C4");
+        assert!(flat_c.cents < 0.0);
+        assert_eq!(Pitch::from_frequency(41.2).note_name(), "E1");
+    }
+
+    #[test]
+    fn silence_and_noise_have_no_reading() {
+        assert_eq!(detect_pitch(&vec![0.0; window_size(SAMPLE_RATE)], SAMPLE_RATE), None);
+        let mut detector = PitchDetector::new();
+        detector.process(&white_noise(0.5, window_size(SAMPLE_RATE)), SAMPLE_RATE, 0.05);
+        assert_eq!(detector.pitch, None);
+    }
+
+    #[test]
+    fn reading_is_held_through_short_gaps() {
+        let mut detector = PitchDetector::new();
+        detector.process(&tone(440.0, 1), SAMPLE_RATE, 0.05);
+        assert_eq!(detector.pitch.unwrap().note_name(), "A4");
+        let silence = vec![0.0; window_size(SAMPLE_RATE)];
+        detector.process(&silence, SAMPLE_RATE, HOLD_SECONDS / 2.0);
+        assert!(detector.pitch.is_some());
+        detector.process(&silence, SAMPLE_RATE, HOLD_SECONDS);
+        assert!(detector.pitch.is_none());
+    }
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// File types the decoder is built with support for
+pub const AUDIO_EXTENSIONS: [&str; 3] = ["mp3", "wav", "ogg"];
+
+pub struct Playlist {
+    pub tracks: Vec<PathBuf>,
+    pub current: usize,
+}
+
+impl Playlist {
+    pub fn new() -> Self {
+        Self { tracks: Vec::new(), current: 0 }
+    }
+
+    pub fn single(path: PathBuf) -> Self {
+        Self { tracks: vec![path], current: 0 }
+    }
+
+    // Expands each argument in order: audio files are added as they are,
+    // directories contribute their audio files sorted by name and .m3u/.m3u8
+    // playlists their entries
+    pub fn from_paths(paths: &[PathBuf]) -> Result<Self, String> {
+        let mut tracks = Vec::new();
+        for path in paths {
+            if path.is_dir() {
+                tracks.extend(directory_tracks(path)?);
+            } else if has_extension(path, &["m3u", "m3u8"]) {
+                tracks.extend(m3u_tracks(path)?);
+            } else if path.is_file() {
+                tracks.push(path.clone());
+            } else {
+                return Err(format!("{} does not exist", path.display()));
+            }
+        }
+        Ok(Self { tracks, current: 0 })
+    }
+
+    pub fn current(&self) -> Option<&PathBuf> {
+        self.tracks.get(self.current)
+    }
+
+    // Moves to the next track, returning None at the end of the list
+    pub fn advance(&mut self) -> Option<&PathBuf> {
+        if self.current + 1 >= self.tracks.len() {
+            return None;
+        }
+        self.current += 1;
+        self.current()
+    }
+
+    // Moves to the previous track, returning None at the start of the list
+    pub fn previous(&mut self) -> Option<&PathBuf> {
+        if self.current == 0 || self.tracks.is_empty() {
+            return None;
+        }
+        self.current -= 1;
+        self.current()
+    }
+}
+
+pub fn is_audio_file(path: &Path) -> bool {
+    has_extension(path, &AUDIO_EXTENSIONS)
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extensions.iter().any(|&e| e.eq_ignore_ascii_case(extension)))
+}
+
+fn directory_tracks(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(directory).map_err(|e| format!("{}: {}", directory.display(), e))?;
+    let mut tracks: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_audio_file(path))
+        .collect();
+    tracks.sort();
+    Ok(tracks)
+}
+
+fn m3u_tracks(playlist: &Path) -> Result<Vec<PathBuf>, String> {
+    let contents = fs::read_to_string(playlist).map_err(|e| format!("{}: {}", playlist.display(), e))?;
+    let base = playlist.parent().unwrap_or(Path::new("."));
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        // Entries are relative to the playlist unless absolute
+        .map(|line| base.join(line))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A scratch folder removed when the test ends
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("music_visualiser_{}_{}", name, std::process::id()));
+            fs::remove_dir_all(&path).ok();
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    #[test]
+    fn directories_and_playlists_are_expanded() {
+        let temp = TempDir::new("playlist");
+        let album = temp.0.join("album");
+        fs::create_dir_all(album.join("extras")).unwrap();
+        for name in ["02 b.ogg", "01 a.mp3", "03 c.WAV", "cover.jpg", "extras/04 d.mp3"] {
+            fs::write(album.join(name), b"").unwrap();
+        }
+        let single = temp.0.join("single.mp3");
+        fs::write(&single, b"").unwrap();
+
+        let lists = temp.0.join("lists");
+        fs::create_dir_all(&lists).unwrap();
+        let m3u = lists.join("mix.m3u");
+        fs::write(
+            &m3u,
+            format!("#EXTM3U\n#EXTINF:123,Artist - Title\n../album/01 a.mp3\n\n  {}  \nnew.mp3\n", single.display()),
+        )
+        .unwrap();
+
+        let playlist = Playlist::from_paths(&[album.clone(), m3u, single.clone()]).unwrap();
+        let expected = [
+            album.join("01 a.mp3"),
+            album.join("02 b.ogg"),
+            album.join("03 c.WAV"),
+            lists.join("../album/01 a.mp3"),
+            single.clone(),
+            lists.join("new.mp3"),
+            single,
+        ];
+        assert_eq!(playlist.tracks, expected);
+        assert_eq!(playlist.current(), Some(&expected[0]));
+
+        let missing = temp.0.join("missing.mp3");
+        assert!(Playlist::from_paths(&[missing]).is_err());
+        assert!(Playlist::from_paths(&[temp.0.join("missing.m3u")]).is_err());
+    }
+
+    #[test]
+    fn advance_and_previous_stop_at_the_ends() {
+        let mut playlist = Playlist { tracks: vec![PathBuf::from("a.mp3"), PathBuf::from("b.mp3")], current: 0 };
+        assert!(playlist.previous().is_none());
+        assert_eq!(playlist.advance(), Some(&PathBuf::from("b.mp3")));
+        assert!(playlist.advance().is_none());
+        assert_eq!(playlist.previous(), Some(&PathBuf::from("a.mp3")));
+        assert!(Playlist::new().advance().is_none());
+        assert!(Playlist::new().previous().is_none());
+    }
+}
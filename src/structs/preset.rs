@@ -0,0 +1,529 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::structs::audio_analyzer::{is_valid_fft_size, MAX_FFT_SIZE, MIN_FFT_SIZE};
+use crate::structs::colour_map::ColourMap;
+use crate::structs::colour_space::{format_hex_colour, parse_hex_colour};
+use crate::structs::config_paths::presets_path;
+use crate::structs::oscilloscope::{ScopeChannels, TIMEBASES_MS};
+use crate::structs::palette::{ColourStop, GradientMode, Interpolation, Palette};
+use crate::structs::spectrogram::HISTORY_LENGTHS;
+use crate::structs::visualisation_mode::VisualisationMode;
+use crate::structs::visualisation_state::VisualisationState;
+
+// Number of beats between preset changes when cycling on the beat
+pub const BEATS_PER_CYCLE: u64 = 16;
+
+// A named look: everything the user can dial in, stored in presets.toml
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub mode: VisualisationMode,
+    #[serde(default = "default_gradient")]
+    pub gradient: GradientMode,
+    #[serde(default)]
+    pub smoothing: f32,
+    pub palette: PresetPalette,
+    #[serde(default)]
+    pub analyzer: PresetAnalyzer,
+    #[serde(default)]
+    pub layout: PresetLayout,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresetPalette {
+    pub name: String,
+    #[serde(default = "default_interpolation")]
+    pub interpolation: Interpolation,
+    pub stops: Vec<PresetStop>,
+}
+
+impl PresetPalette {
+    // None when no stop has a readable colour
+    pub fn to_palette(&self) -> Option<Palette> {
+        let mut stops: Vec<ColourStop> = self
+            .stops
+            .iter()
+            .filter_map(|stop| {
+                parse_hex_colour(&stop.colour)
+                    .ok()
+                    .map(|colour| ColourStop { position: stop.position.clamp(0.0, 1.0), colour })
+            })
+            .collect();
+        if stops.is_empty() {
+            return None;
+        }
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Some(Palette {
+            name: self.name.clone(),
+            stops,
+            interpolation: self.interpolation,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresetStop {
+    pub position: f32,
+    // `#rrggbb`
+    pub colour: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresetAnalyzer {
+    pub fft_size: usize,
+    pub gain: f32,
+}
+
+impl Default for PresetAnalyzer {
+    fn default() -> Self {
+        Self { fft_size: 1024, gain: 5.0 }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresetLayout {
+    pub bar_count: usize,
+    pub scope_timebase_ms: u32,
+    pub scope_channels: ScopeChannels,
+    pub spectrogram_colour_map: ColourMap,
+    pub spectrogram_history: usize,
+    pub radial_mirror: bool,
+    pub radial_inner_ring: bool,
+}
+
+impl Default for PresetLayout {
+    fn default() -> Self {
+        let state = VisualisationState::new();
+        Self {
+            bar_count: state.get_bar_count(),
+            scope_timebase_ms: state.get_scope_timebase_ms(),
+            scope_channels: state.get_scope_channels(),
+            spectrogram_colour_map: state.get_spectrogram_colour_map(),
+            spectrogram_history: state.get_spectrogram_history_length(),
+            radial_mirror: state.get_radial_mirror(),
+            radial_inner_ring: state.get_radial_inner_ring(),
+        }
+    }
+}
+
+fn default_gradient() -> GradientMode {
+    GradientMode::BarIndex
+}
+
+fn default_interpolation() -> Interpolation {
+    Interpolation::Rgb
+}
+
+impl Preset {
+    pub fn from_state(name: &str, state: &VisualisationState, fft_size: usize) -> Self {
+        let palette = state.get_palette();
+        Self {
+            name: name.to_string(),
+            mode: state.get_mode(),
+            gradient: state.get_gradient_mode(),
+            smoothing: state.get_smoothing(),
+            palette: PresetPalette {
+                name: palette.name.clone(),
+                interpolation: palette.interpolation,
+                stops: palette
+                    .stops
+                    .iter()
+                    .map(|stop| PresetStop {
+                        position: stop.position,
+                        colour: format_hex_colour(stop.colour),
+                    })
+                    .collect(),
+            },
+            analyzer: PresetAnalyzer {
+                fft_size,
+                gain: state.get_bar_gain(),
+            },
+            layout: PresetLayout {
+                bar_count: state.get_bar_count(),
+                scope_timebase_ms: state.get_scope_timebase_ms(),
+                scope_channels: state.get_scope_channels(),
+                spectrogram_colour_map: state.get_spectrogram_colour_map(),
+                spectrogram_history: state.get_spectrogram_history_length(),
+                radial_mirror: state.get_radial_mirror(),
+                radial_inner_ring: state.get_radial_inner_ring(),
+            },
+        }
+    }
+
+    // Checks everything that serde cannot, so a hand-edited file fails with a useful message
+    pub fn validate(&self) -> Result<(), String> {
+        if self.palette.stops.is_empty() {
+            return Err(format!("preset '{}' has no palette stops", self.name));
+        }
+        for stop in &self.palette.stops {
+            parse_hex_colour(&stop.colour).map_err(|e| format!("preset '{}': {}", self.name, e))?;
+            if !(0.0..=1.0).contains(&stop.position) {
+                return Err(format!("preset '{}': stop position {} is outside 0.0..=1.0", self.name, stop.position));
+            }
+        }
+        if !is_valid_fft_size(self.analyzer.fft_size) {
+            return Err(format!(
+                "preset '{}': fft_size must be a power of two between {} and {}",
+                self.name, MIN_FFT_SIZE, MAX_FFT_SIZE
+            ));
+        }
+        // Written so that NaN fails every range check
+        if !(0.0..1.0).contains(&self.smoothing) {
+            return Err(format!("preset '{}': smoothing must be at least 0.0 and below 1.0", self.name));
+        }
+        if !(self.analyzer.gain > 0.0 && self.analyzer.gain.is_finite()) {
+            return Err(format!("preset '{}': gain must be a positive number", self.name));
+        }
+        if !(1..=256).contains(&self.layout.bar_count) {
+            return Err(format!("preset '{}': bar_count must be between 1 and 256", self.name));
+        }
+        let timebases = TIMEBASES_MS[0]..=TIMEBASES_MS[TIMEBASES_MS.len() - 1];
+        if !timebases.contains(&self.layout.scope_timebase_ms) {
+            return Err(format!(
+                "preset '{}': scope_timebase_ms must be between {} and {}",
+                self.name,
+                timebases.start(),
+                timebases.end()
+            ));
+        }
+        let histories = HISTORY_LENGTHS[0]..=HISTORY_LENGTHS[HISTORY_LENGTHS.len() - 1];
+        if !histories.contains(&self.layout.spectrogram_history) {
+            return Err(format!(
+                "preset '{}': spectrogram_history must be between {} and {}",
+                self.name,
+                histories.start(),
+                histories.end()
+            ));
+        }
+        Ok(())
+    }
+
+    // Applies the look to `state`; the caller rebuilds the analyzer if
+    // `analyzer.fft_size` differs from the current one
+    pub fn apply(&self, state: &mut VisualisationState) {
+        let palette = match self.palette.to_palette() {
+            Some(palette) => palette,
+            None => return,
+        };
+        state.set_palette(palette);
+        state.set_mode(self.mode);
+        state.gradient_mode = self.gradient;
+        state.set_smoothing(self.smoothing);
+        state.set_bar_gain(self.analyzer.gain);
+        state.set_bar_count(self.layout.bar_count);
+        state.scope_timebase_index = nearest_index(&TIMEBASES_MS.map(|ms| ms as usize), self.layout.scope_timebase_ms as usize);
+        state.scope_channels = self.layout.scope_channels;
+        state.spectrogram_colour_map = self.layout.spectrogram_colour_map;
+        state.spectrogram_history_index = nearest_index(&HISTORY_LENGTHS, self.layout.spectrogram_history);
+        state.set_radial_mirror(self.layout.radial_mirror);
+        state.set_radial_inner_ring(self.layout.radial_inner_ring);
+    }
+}
+
+// The look asked for on the command line. The preset brings its own mode,
+// so an explicit mode goes on top of it.
+pub fn apply_startup_look(state: &mut VisualisationState, preset: Option<&Preset>, mode: Option<VisualisationMode>) {
+    if let Some(preset) = preset {
+        preset.apply(state);
+    }
+    if let Some(mode) = mode {
+        state.set_mode(mode);
+    }
+}
+
+fn nearest_index(options: &[usize], value: usize) -> usize {
+    options
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, &option)| option.abs_diff(value))
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresetCycle {
+    Off,
+    OnTrackChange,
+    OnBeats,
+}
+
+impl PresetCycle {
+    pub fn next(self) -> Self {
+        match self {
+            PresetCycle::Off => PresetCycle::OnTrackChange,
+            PresetCycle::OnTrackChange => PresetCycle::OnBeats,
+            PresetCycle::OnBeats => PresetCycle::Off,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            PresetCycle::Off => "Cycle: Off".to_string(),
+            PresetCycle::OnTrackChange => "Cycle: Track".to_string(),
+            PresetCycle::OnBeats => format!("Cycle: {} beats", BEATS_PER_CYCLE),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PresetFile {
+    #[serde(default)]
+    preset: Vec<Preset>,
+}
+
+pub struct PresetLibrary {
+    pub presets: Vec<Preset>,
+    pub current: usize,
+    pub cycle: PresetCycle,
+    last_cycle_beat: u64,
+    path: Option<PathBuf>,
+}
+
+impl PresetLibrary {
+    // Loads presets.toml from the config dir. A missing file is created from
+    // the built-in presets so there is something to edit; a broken file is
+    // reported and left untouched while the built-ins are used instead.
+    pub fn load() -> Self {
+        let path = presets_path();
+        let mut library = Self {
+            presets: built_in_presets(),
+            current: 0,
+            cycle: PresetCycle::Off,
+            last_cycle_beat: 0,
+            path: path.clone(),
+        };
+
+        let path = match path {
+            Some(path) => path,
+            None => return library,
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => match parse_presets(&contents) {
+                Ok(presets) if !presets.is_empty() => library.presets = presets,
+                Ok(_) => eprintln!("{} contains no presets, using built-in presets", path.display()),
+                Err(e) => {
+                    eprintln!("Could not load presets from {}: {}", path.display(), e);
+                    // Never overwrite a file the user may be halfway through editing
+                    library.path = None;
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if let Err(e) = library.save() {
+                    eprintln!("Could not write default presets to {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => {
+                eprintln!("Could not read {}: {}", path.display(), e);
+                library.path = None;
+            }
+        }
+        library
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Err("no writable preset file".into()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = PresetFile { preset: self.presets.clone() };
+        fs::write(path, toml::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+
+    // The palette of the first preset using `name`, so a custom palette
+    // remembered in config.toml can be restored
+    pub fn palette_named(&self, name: &str) -> Option<Palette> {
+        self.presets
+            .iter()
+            .find(|preset| preset.palette.name == name)
+            .and_then(|preset| preset.palette.to_palette())
+    }
+
+    pub fn current(&self) -> Option<&Preset> {
+        self.presets.get(self.current)
+    }
+
+    pub fn next(&mut self) -> Option<&Preset> {
+        if self.presets.is_empty() {
+            return None;
+        }
+        self.current = (self.current + 1) % self.presets.len();
+        self.current()
+    }
+
+    pub fn previous(&mut self) -> Option<&Preset> {
+        if self.presets.is_empty() {
+            return None;
+        }
+        self.current = (self.current + self.presets.len() - 1) % self.presets.len();
+        self.current()
+    }
+
+    // Adds the preset, selects it and writes the file
+    pub fn add(&mut self, preset: Preset) -> Result<(), Box<dyn std::error::Error>> {
+        self.presets.push(preset);
+        self.current = self.presets.len() - 1;
+        self.save()
+    }
+
+    pub fn next_custom_name(&self) -> String {
+        (1..)
+            .map(|number| format!("Custom {}", number))
+            .find(|name| self.presets.iter().all(|preset| &preset.name != name))
+            .unwrap_or_default()
+    }
+
+    // True when beat cycling is on and enough beats have passed since the last change
+    pub fn should_cycle_on_beat(&mut self, beat_count: u64) -> bool {
+        if self.cycle != PresetCycle::OnBeats {
+            self.last_cycle_beat = beat_count;
+            return false;
+        }
+        if beat_count < self.last_cycle_beat {
+            // Beat detector was reset for a new song
+            self.last_cycle_beat = beat_count;
+        }
+        if beat_count - self.last_cycle_beat >= BEATS_PER_CYCLE {
+            self.last_cycle_beat = beat_count;
+            return true;
+        }
+        false
+    }
+}
+
+fn parse_presets(contents: &str) -> Result<Vec<Preset>, Box<dyn std::error::Error>> {
+    let file: PresetFile = toml::from_str(contents)?;
+    for preset in &file.preset {
+        preset.validate()?;
+    }
+    Ok(file.preset)
+}
+
+fn built_in_presets() -> Vec<Preset> {
+    let look = |name: &str, mode: VisualisationMode, palette_index: usize, configure: &dyn Fn(&mut VisualisationState)| {
+        let mut state = VisualisationState::new();
+        state.set_palette_index(palette_index);
+        state.set_mode(mode);
+        configure(&mut state);
+        Preset::from_state(name, &state, 1024)
+    };
+
+    vec![
+        look("Classic", VisualisationMode::Bars, 0, &|_| {}),
+        look("Fire bars", VisualisationMode::Bars, 3, &|state| {
+            state.gradient_mode = GradientMode::Height;
+            state.set_bar_count(32);
+            state.set_smoothing(0.7);
+        }),
+        look("Sunset", VisualisationMode::Radial, 1, &|state| state.set_smoothing(0.5)),
+        look("Ocean", VisualisationMode::Spectrogram, 2, &|state| {
+            state.spectrogram_colour_map = ColourMap::Theme;
+        }),
+        look("Neon", VisualisationMode::Particles, 4, &|_| {}),
+        look("Scope", VisualisationMode::Oscilloscope, 0, &|_| {}),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(count: usize) -> PresetLibrary {
+        PresetLibrary {
+            presets: built_in_presets().into_iter().take(count).collect(),
+            current: 0,
+            cycle: PresetCycle::Off,
+            last_cycle_beat: 0,
+            path: None,
+        }
+    }
+
+    #[test]
+    fn presets_survive_a_round_trip_through_toml() {
+        let file = PresetFile { preset: built_in_presets() };
+        let written = toml::to_string_pretty(&file).unwrap();
+        let parsed = parse_presets(&written).unwrap();
+        assert_eq!(parsed.len(), file.preset.len());
+        assert_eq!(toml::to_string_pretty(&PresetFile { preset: parsed }).unwrap(), written);
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        let valid = built_in_presets().remove(0);
+        assert!(valid.validate().is_ok());
+
+        type Breakage = fn(&mut Preset);
+        let broken: [(&str, Breakage); 10] = [
+            ("no stops", |preset| preset.palette.stops.clear()),
+            ("bad colour", |preset| preset.palette.stops[0].colour = "#12345".to_string()),
+            ("stop position", |preset| preset.palette.stops[0].position = f32::NAN),
+            ("fft size", |preset| preset.analyzer.fft_size = 1000),
+            ("smoothing", |preset| preset.smoothing = 1.0),
+            ("NaN smoothing", |preset| preset.smoothing = f32::NAN),
+            ("gain", |preset| preset.analyzer.gain = 0.0),
+            ("infinite gain", |preset| preset.analyzer.gain = f32::INFINITY),
+            ("bar count", |preset| preset.layout.bar_count = 257),
+            ("history", |preset| preset.layout.spectrogram_history = 0),
+        ];
+        for (label, breakage) in broken {
+            let mut preset = valid.clone();
+            breakage(&mut preset);
+            assert!(preset.validate().is_err(), "{} was accepted", label);
+        }
+
+        let mut preset = valid.clone();
+        preset.layout.scope_timebase_ms = 1000;
+        let file = toml::to_string(&PresetFile { preset: vec![preset] }).unwrap();
+        assert!(parse_presets(&file).is_err());
+    }
+
+    #[test]
+    fn next_and_previous_wrap_around() {
+        let mut presets = library(3);
+        assert_eq!(presets.previous().unwrap().name, "Sunset");
+        assert_eq!(presets.next().unwrap().name, "Classic");
+        assert_eq!(presets.next().unwrap().name, "Fire bars");
+
+        let mut empty = library(0);
+        assert!(empty.next().is_none());
+        assert!(empty.previous().is_none());
+    }
+
+    #[test]
+    fn explicit_mode_wins_over_the_preset() {
+        let sunset = built_in_presets().into_iter().find(|preset| preset.name == "Sunset").unwrap();
+        assert_eq!(sunset.mode, VisualisationMode::Radial);
+
+        let mut state = VisualisationState::new();
+        apply_startup_look(&mut state, Some(&sunset), Some(VisualisationMode::Spectrogram));
+        assert_eq!(state.get_mode(), VisualisationMode::Spectrogram);
+        assert_eq!(state.get_palette().name, "Sunset");
+
+        apply_startup_look(&mut state, Some(&sunset), None);
+        assert_eq!(state.get_mode(), VisualisationMode::Radial);
+    }
+
+    #[test]
+    fn custom_palettes_keep_their_name() {
+        let mut preset = built_in_presets().remove(0);
+        preset.palette.name = "Dusk".to_string();
+        let mut state = VisualisationState::new();
+        preset.apply(&mut state);
+        assert_eq!(state.get_palette().name, "Dusk");
+
+        let mut presets = library(1);
+        presets.presets.push(preset);
+        assert_eq!(presets.palette_named("Dusk").unwrap(), *state.get_palette());
+        assert!(presets.palette_named("Dawn").is_none());
+
+        // Cycling leaves the custom palette for the first built-in one
+        state.cycle_palette();
+        assert_eq!(state.get_palette().name, Palette::built_in()[0].name);
+    }
+}
@@ -0,0 +1,57 @@
+// Slowest and fastest steady rotation, in radians per second
+const BASE_SPEED: f32 = 0.077;
+const ENERGY_SPEED: f32 = 0.77;
+// Extra angular velocity in radians per second added on every detected beat
+const BEAT_KICK: f32 = 3.0;
+// Share of the kick still left a second after the beat
+const KICK_DECAY: f32 = 0.018;
+
+pub struct RadialState {
+    pub rotation: f32,
+    kick: f32,
+}
+
+impl RadialState {
+    pub fn new() -> Self {
+        Self { rotation: 0.0, kick: 0.0 }
+    }
+
+    // Advances by `elapsed` seconds of audio, so the spin does not depend on
+    // how the codec splits the track into packets
+    pub fn update(&mut self, energy: f32, is_beat: bool, elapsed: f32) {
+        if is_beat {
+            self.kick += BEAT_KICK;
+        }
+        // The kick decays continuously, so it adds its integral over the packet
+        let decay = KICK_DECAY.powf(elapsed);
+        self.rotation += (BASE_SPEED + energy * ENERGY_SPEED) * elapsed + self.kick * (1.0 - decay) / -KICK_DECAY.ln();
+        self.rotation %= std::f32::consts::TAU;
+        self.kick *= decay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 36864 frames of audio at 44.1 kHz, a whole number of packets for each
+    // size tested, with a beat in the first packet
+    fn spin(frames: usize) -> f32 {
+        let mut radial = RadialState::new();
+        let elapsed = frames as f32 / 44100.0;
+        for packet in 0..36864 / frames {
+            radial.update(0.5, packet == 0, elapsed);
+        }
+        radial.rotation
+    }
+
+    #[test]
+    fn speed_does_not_depend_on_packet_size() {
+        // MP3, AAC and a typical FLAC block
+        let mp3 = spin(1152);
+        for frames in [1024, 4096] {
+            let rotation = spin(frames);
+            assert!((rotation - mp3).abs() < 1e-3, "{} frames turned {} against {}", frames, rotation, mp3);
+        }
+    }
+}
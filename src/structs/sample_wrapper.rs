@@ -1,3 +1,17 @@
+use crate::structs::channel_layout::ChannelLayout;
+
 pub struct SampleWrapper {
     pub samples: Vec<f32>,
-}
\ No newline at end of file
+    pub layout: ChannelLayout,
+    pub sample_rate: u32,
+}
+
+impl SampleWrapper {
+    pub fn channels(&self) -> usize {
+        self.layout.channel_count()
+    }
+
+    pub fn frames(&self) -> std::slice::ChunksExact<'_, f32> {
+        self.samples.chunks_exact(self.channels())
+    }
+}
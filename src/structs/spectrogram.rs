@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+// Vertical resolution of the spectrogram texture; scaled to the visualisation area
+pub const SPECTROGRAM_ROWS: usize = 256;
+// Selectable history lengths in analysis frames, cycled by the history button
+pub const HISTORY_LENGTHS: [usize; 4] = [128, 256, 512, 1024];
+
+const MAX_HISTORY: usize = HISTORY_LENGTHS[HISTORY_LENGTHS.len() - 1];
+
+const MIN_FREQUENCY: f32 = 30.0;
+const MAX_FREQUENCY: f32 = 16000.0;
+// Dynamic range shown, in decades of magnitude below full scale
+const DISPLAY_DECADES: f32 = 2.5;
+
+// Rolling history of spectrum columns resampled onto a log-frequency axis.
+// Values are normalised to 0.0..=1.0 and stored bottom (lowest frequency) first.
+pub struct Spectrogram {
+    pub columns: VecDeque<Vec<f32>>,
+    // Total number of columns ever pushed, used to work out what still needs uploading
+    pub total_columns: u64,
+    // Bumped by every clear so views know to drop what they have drawn
+    pub generation: u64,
+}
+
+impl Spectrogram {
+    pub fn new() -> Self {
+        Self {
+            columns: VecDeque::with_capacity(MAX_HISTORY),
+            total_columns: 0,
+            generation: 0,
+        }
+    }
+
+    pub fn push(&mut self, spectrum: &[f32], sample_rate: u32) {
+        if spectrum.is_empty() {
+            return;
+        }
+        let fft_size = spectrum.len() * 2;
+        let max_frequency = MAX_FREQUENCY.min(sample_rate as f32 / 2.0);
+        let ratio = max_frequency / MIN_FREQUENCY;
+
+        let column = (0..SPECTROGRAM_ROWS)
+            .map(|row| {
+                let frequency = MIN_FREQUENCY * ratio.powf(row as f32 / (SPECTROGRAM_ROWS - 1) as f32);
+                let bin = frequency * fft_size as f32 / sample_rate as f32;
+                let index = (bin as usize).min(spectrum.len() - 1);
+                let next = (index + 1).min(spectrum.len() - 1);
+                let fraction = bin - index as f32;
+                let magnitude = spectrum[index] * (1.0 - fraction) + spectrum[next] * fraction;
+
+                // Same gain as the bar view, then shown on a log scale
+                let level = (magnitude * 5.0).max(f32::MIN_POSITIVE).log10();
+                (level / DISPLAY_DECADES + 1.0).clamp(0.0, 1.0)
+            })
+            .collect();
+
+        if self.columns.len() == MAX_HISTORY {
+            self.columns.pop_front();
+        }
+        self.columns.push_back(column);
+        self.total_columns += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.columns.clear();
+        self.total_columns = 0;
+        self.generation += 1;
+    }
+}
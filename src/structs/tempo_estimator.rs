@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+
+// Long enough to hold several beats at the slowest tempo
+const HISTORY_SECONDS: f32 = 8.0;
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+// A pulse also correlates with itself at twice its period, so scores are
+// weighted towards common tempos (log-normal around 120 BPM, one octave wide)
+// to avoid reporting half the tempo
+const PREFERRED_BPM: f32 = 120.0;
+const PREFERENCE_WIDTH_OCTAVES: f32 = 1.0;
+
+// Estimates tempo from the autocorrelation of a rolling onset strength
+// signal: the lag at which onsets best line up with themselves is the beat
+// period
+pub struct TempoEstimator {
+    onsets: VecDeque<f32>,
+    capacity: usize,
+    // Onset values pushed per second
+    frame_rate: f32,
+}
+
+impl TempoEstimator {
+    pub fn new(frame_rate: f32) -> Self {
+        let capacity = (HISTORY_SECONDS * frame_rate).ceil() as usize;
+        Self {
+            onsets: VecDeque::with_capacity(capacity),
+            capacity,
+            frame_rate,
+        }
+    }
+
+    pub fn push(&mut self, onset_strength: f32) {
+        if self.onsets.len() == self.capacity {
+            self.onsets.pop_front();
+        }
+        self.onsets.push_back(onset_strength);
+    }
+
+    // Beats per minute, or None until there is enough history or while
+    // nothing periodic stands out
+    pub fn estimate(&self) -> Option<f32> {
+        let min_lag = ((60.0 / MAX_BPM) * self.frame_rate).floor().max(1.0) as usize;
+        let max_lag = ((60.0 / MIN_BPM) * self.frame_rate).ceil() as usize;
+        if self.onsets.len() < max_lag * 2 {
+            return None;
+        }
+
+        let mean = self.onsets.iter().sum::<f32>() / self.onsets.len() as f32;
+        let centred: Vec<f32> = self.onsets.iter().map(|onset| onset - mean).collect();
+        let correlation = |lag: usize| -> f32 {
+            let score = centred.iter().zip(&centred[lag..]).map(|(a, b)| a * b).sum::<f32>()
+                / (centred.len() - lag) as f32;
+            let octaves = (60.0 * self.frame_rate / lag.max(1) as f32 / PREFERRED_BPM).log2();
+            score * (-0.5 * (octaves / PREFERENCE_WIDTH_OCTAVES).powi(2)).exp()
+        };
+        let scores: Vec<f32> = (min_lag - 1..=max_lag + 1).map(correlation).collect();
+
+        // Skip the padding entries either side, which are only there for the
+        // interpolation below
+        let (index, &best) = scores[1..scores.len() - 1]
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        if best <= 0.0 {
+            return None;
+        }
+        // Parabolic interpolation between neighbouring lags for sub-frame accuracy
+        let (before, after) = (scores[index], scores[index + 2]);
+        let curvature = before - 2.0 * best + after;
+        let offset = if curvature < 0.0 { 0.5 * (before - after) / curvature } else { 0.0 };
+        let lag = (min_lag + index) as f32 + offset;
+        Some(60.0 * self.frame_rate / lag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Onset strengths as the analysis sees them: 512-sample hops at 44.1 kHz
+    const FRAME_RATE: f32 = 44100.0 / 512.0;
+
+    // A sharp onset on every beat, decaying before the next
+    fn pulse_train(bpm: f32, seconds: f32) -> Vec<f32> {
+        let period = 60.0 / bpm;
+        (0..(seconds * FRAME_RATE) as usize)
+            .map(|frame| (-(frame as f32 / FRAME_RATE).rem_euclid(period) / 0.05).exp())
+            .collect()
+    }
+
+    #[test]
+    fn finds_the_tempo_of_a_pulse_train() {
+        for bpm in [120.0, 90.0, 150.0] {
+            let mut estimator = TempoEstimator::new(FRAME_RATE);
+            for onset in pulse_train(bpm, HISTORY_SECONDS) {
+                estimator.push(onset);
+            }
+            let estimate = estimator.estimate().unwrap();
+            assert!((estimate - bpm).abs() < 2.0, "{} BPM read as {}", bpm, estimate);
+        }
+    }
+
+    #[test]
+    fn waits_for_history_and_a_pulse() {
+        let mut estimator = TempoEstimator::new(FRAME_RATE);
+        for onset in pulse_train(120.0, 1.0) {
+            estimator.push(onset);
+        }
+        assert_eq!(estimator.estimate(), None);
+
+        let mut steady = TempoEstimator::new(FRAME_RATE);
+        for _ in 0..(HISTORY_SECONDS * FRAME_RATE) as usize {
+            steady.push(0.5);
+        }
+        assert_eq!(steady.estimate(), None);
+    }
+}
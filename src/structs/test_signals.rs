@@ -0,0 +1,42 @@
+use std::f32::consts::PI;
+
+// Synthetic signals with known features, shared by the analysis tests
+
+pub const SAMPLE_RATE: u32 = 48000;
+
+// Number of samples in `seconds` of audio at `SAMPLE_RATE`
+pub fn frames(seconds: f32) -> usize {
+    (seconds * SAMPLE_RATE as f32) as usize
+}
+
+pub fn sine(frequency: f32, amplitude: f32, length: usize) -> Vec<f32> {
+    sine_with_phase(frequency, 0.0, amplitude, length)
+}
+
+// `phase` is in radians
+pub fn sine_with_phase(frequency: f32, phase: f32, amplitude: f32, length: usize) -> Vec<f32> {
+    (0..length)
+        .map(|i| amplitude * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32 + phase).sin())
+        .collect()
+}
+
+// Sum of sines given as (frequency, amplitude) pairs
+pub fn sines(partials: &[(f32, f32)], length: usize) -> Vec<f32> {
+    (0..length)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            partials.iter().map(|&(frequency, amplitude)| amplitude * (2.0 * PI * frequency * t).sin()).sum()
+        })
+        .collect()
+}
+
+// Deterministic uniform noise in -amplitude..amplitude from a linear congruential generator
+pub fn white_noise(amplitude: f32, length: usize) -> Vec<f32> {
+    let mut state: u32 = 12345;
+    (0..length)
+        .map(|_| {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            amplitude * ((state >> 8) as f32 / (1u32 << 23) as f32 - 1.0)
+        })
+        .collect()
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisualisationMode {
+    Bars,
+    Oscilloscope,
+    Vectorscope,
+    Spectrogram,
+    Radial,
+    Particles,
+    Chromagram,
+    Tuner,
+}
+
+impl VisualisationMode {
+    pub const ALL: [VisualisationMode; 8] = [
+        VisualisationMode::Bars,
+        VisualisationMode::Oscilloscope,
+        VisualisationMode::Vectorscope,
+        VisualisationMode::Spectrogram,
+        VisualisationMode::Radial,
+        VisualisationMode::Particles,
+        VisualisationMode::Chromagram,
+        VisualisationMode::Tuner,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            VisualisationMode::Bars => "Bars",
+            VisualisationMode::Oscilloscope => "Scope",
+            VisualisationMode::Vectorscope => "Stereo",
+            VisualisationMode::Spectrogram => "Spectrogram",
+            VisualisationMode::Radial => "Radial",
+            VisualisationMode::Particles => "Particles",
+            VisualisationMode::Chromagram => "Chroma",
+            VisualisationMode::Tuner => "Tuner",
+        }
+    }
+
+    // Accepts the names used in config.toml as well as the button labels
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "bars" => Some(VisualisationMode::Bars),
+            "oscilloscope" | "scope" => Some(VisualisationMode::Oscilloscope),
+            "vectorscope" | "stereo" | "goniometer" => Some(VisualisationMode::Vectorscope),
+            "spectrogram" => Some(VisualisationMode::Spectrogram),
+            "radial" => Some(VisualisationMode::Radial),
+            "particles" => Some(VisualisationMode::Particles),
+            "chromagram" | "chroma" => Some(VisualisationMode::Chromagram),
+            "tuner" => Some(VisualisationMode::Tuner),
+            _ => None,
+        }
+    }
+}
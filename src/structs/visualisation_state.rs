@@ -1,43 +1,141 @@
 use sdl2::pixels::Color;
+use crate::structs::colour_map::ColourMap;
+use crate::structs::config::Config;
+use crate::structs::oscilloscope::{ScopeChannels, TIMEBASES_MS};
+use crate::structs::palette::{GradientMode, Interpolation, Palette};
+use crate::structs::spectrogram::HISTORY_LENGTHS;
+use crate::structs::visualisation_mode::VisualisationMode;
 
 pub struct VisualisationState {
-    pub red_slider: u8,
-    pub green_slider: u8,
-    pub blue_slider: u8,
+    // A built-in palette or one from a preset, told apart by name
+    pub palette: Palette,
+    // Stop edited by the RGB sliders
+    pub selected_stop: usize,
+    pub gradient_mode: GradientMode,
+    pub bar_count: usize,
+    // Multiplier applied to band magnitudes before they are clamped to 1.0
+    pub bar_gain: f32,
+    // How much of the previous frame is kept when bars fall, 0.0 (none) to just under 1.0
+    pub smoothing: f32,
     pub is_auto_randomising: bool,
+    pub mode: VisualisationMode,
+    pub scope_timebase_index: usize,
+    pub scope_channels: ScopeChannels,
+    pub scope_trigger_level: f32,
+    pub spectrogram_colour_map: ColourMap,
+    pub spectrogram_history_index: usize,
+    pub radial_mirror: bool,
+    pub radial_inner_ring: bool,
 }
 
 impl VisualisationState {
 
     pub fn new() -> Self {
         Self {
-            red_slider: 0,
-            green_slider: 0,
-            blue_slider: 0,
+            palette: Palette::new("Custom", &[Color::RGB(0, 0, 0)], Interpolation::Rgb),
+            selected_stop: 0,
+            gradient_mode: GradientMode::BarIndex,
+            bar_count: 16,
+            bar_gain: 5.0,
+            smoothing: 0.0,
             is_auto_randomising: false,
+            mode: VisualisationMode::Bars,
+            scope_timebase_index: 2,
+            scope_channels: ScopeChannels::Summed,
+            scope_trigger_level: 0.0,
+            spectrogram_colour_map: ColourMap::Viridis,
+            spectrogram_history_index: 2,
+            radial_mirror: true,
+            radial_inner_ring: true,
         }
     }
 
     pub fn set_red_slider(&mut self, value: u8) {
-        self.red_slider = value;
+        let mut colour = self.get_selected_color();
+        colour.r = value;
+        self.palette.set_stop_colour(self.selected_stop, colour);
     }
 
     pub fn set_green_slider(&mut self, value: u8) {
-        self.green_slider = value;
+        let mut colour = self.get_selected_color();
+        colour.g = value;
+        self.palette.set_stop_colour(self.selected_stop, colour);
     }
 
     pub fn set_blue_slider(&mut self, value: u8) {
-        self.blue_slider = value;
+        let mut colour = self.get_selected_color();
+        colour.b = value;
+        self.palette.set_stop_colour(self.selected_stop, colour);
     }
 
     pub fn set_initial_color(&mut self) {
-        self.red_slider = 47;
-        self.green_slider = 198;
-        self.blue_slider = 18;
+        self.set_palette_index(0);
     }
 
+    // Colour of the stop currently being edited
     pub fn get_selected_color(&self) -> Color {
-        Color::RGB(self.red_slider, self.green_slider, self.blue_slider)
+        self.palette.stops[self.selected_stop].colour
+    }
+
+    pub fn set_palette_index(&mut self, index: usize) {
+        let palettes = Palette::built_in();
+        self.set_palette(palettes[index % palettes.len()].clone());
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.selected_stop = 0;
+    }
+
+    // Startup settings from config.toml, including the colours saved on the
+    // last quit. A palette name that is not built in is left to the caller,
+    // which finds it among the presets.
+    pub fn apply_config(&mut self, config: &Config) {
+        if let Some(index) = Palette::built_in()
+            .iter()
+            .position(|palette| palette.name == config.visualisation.palette)
+        {
+            self.set_palette_index(index);
+        }
+        self.palette.set_stop_colours(&config.visualisation.colours);
+        self.set_mode(config.visualisation.mode);
+        self.scope_trigger_level = config.visualisation.scope_trigger_level;
+        self.set_bar_count(config.analyzer.bar_count);
+        self.set_bar_gain(config.analyzer.gain);
+        self.set_smoothing(config.analyzer.smoothing);
+    }
+
+    // Steps through the built-in palettes; a custom one goes back to the first
+    pub fn cycle_palette(&mut self) {
+        let next = Palette::built_in()
+            .iter()
+            .position(|palette| palette.name == self.palette.name)
+            .map_or(0, |index| index + 1);
+        self.set_palette_index(next);
+    }
+
+    pub fn get_palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn cycle_selected_stop(&mut self) {
+        self.selected_stop = (self.selected_stop + 1) % self.palette.stops.len();
+    }
+
+    pub fn get_selected_stop(&self) -> usize {
+        self.selected_stop
+    }
+
+    pub fn cycle_gradient_mode(&mut self) {
+        self.gradient_mode = self.gradient_mode.next();
+    }
+
+    pub fn get_gradient_mode(&self) -> GradientMode {
+        self.gradient_mode
+    }
+
+    pub fn cycle_interpolation(&mut self) {
+        self.palette.interpolation = self.palette.interpolation.next();
     }
 
     pub fn set_is_auto_randomising(&mut self, value: bool) {
@@ -47,4 +145,88 @@ impl VisualisationState {
     pub fn get_is_auto_randomising(&self) -> bool {
         self.is_auto_randomising
     }
+
+    pub fn set_bar_count(&mut self, count: usize) {
+        self.bar_count = count.clamp(1, 256);
+    }
+
+    pub fn get_bar_count(&self) -> usize {
+        self.bar_count
+    }
+
+    pub fn set_bar_gain(&mut self, gain: f32) {
+        self.bar_gain = gain.max(0.0);
+    }
+
+    pub fn get_bar_gain(&self) -> f32 {
+        self.bar_gain
+    }
+
+    pub fn set_smoothing(&mut self, smoothing: f32) {
+        self.smoothing = smoothing.clamp(0.0, 0.99);
+    }
+
+    pub fn get_smoothing(&self) -> f32 {
+        self.smoothing
+    }
+
+    pub fn set_mode(&mut self, mode: VisualisationMode) {
+        self.mode = mode;
+    }
+
+    pub fn get_mode(&self) -> VisualisationMode {
+        self.mode
+    }
+
+    pub fn cycle_scope_timebase(&mut self) {
+        self.scope_timebase_index = (self.scope_timebase_index + 1) % TIMEBASES_MS.len();
+    }
+
+    pub fn get_scope_timebase_ms(&self) -> u32 {
+        TIMEBASES_MS[self.scope_timebase_index]
+    }
+
+    pub fn cycle_scope_channels(&mut self) {
+        self.scope_channels = self.scope_channels.next();
+    }
+
+    pub fn get_scope_channels(&self) -> ScopeChannels {
+        self.scope_channels
+    }
+
+    pub fn get_scope_trigger_level(&self) -> f32 {
+        self.scope_trigger_level
+    }
+
+    pub fn cycle_spectrogram_colour_map(&mut self) {
+        self.spectrogram_colour_map = self.spectrogram_colour_map.next();
+    }
+
+    pub fn get_spectrogram_colour_map(&self) -> ColourMap {
+        self.spectrogram_colour_map
+    }
+
+    pub fn cycle_spectrogram_history(&mut self) {
+        self.spectrogram_history_index = (self.spectrogram_history_index + 1) % HISTORY_LENGTHS.len();
+    }
+
+    pub fn get_spectrogram_history_length(&self) -> usize {
+        HISTORY_LENGTHS[self.spectrogram_history_index]
+    }
+
+    pub fn set_radial_mirror(&mut self, value: bool) {
+        self.radial_mirror = value;
+    }
+
+    pub fn get_radial_mirror(&self) -> bool {
+        self.radial_mirror
+    }
+
+    pub fn set_radial_inner_ring(&mut self, value: bool) {
+        self.radial_inner_ring = value;
+    }
+
+    pub fn get_radial_inner_ring(&self) -> bool {
+        self.radial_inner_ring
+    }
 }
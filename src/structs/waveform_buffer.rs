@@ -0,0 +1,81 @@
+use std::collections::VecDeque;
+
+use crate::structs::channel_layout::ChannelLayout;
+use crate::structs::sample_wrapper::SampleWrapper;
+
+// Keeps the most recent interleaved samples so time-domain views can look
+// further back than a single decoded packet.
+pub struct WaveformBuffer {
+    samples: VecDeque<f32>,
+    layout: ChannelLayout,
+    channels: usize,
+    max_frames: usize,
+}
+
+impl WaveformBuffer {
+    pub fn new(max_frames: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(max_frames * 2),
+            layout: ChannelLayout::Mono,
+            channels: 1,
+            max_frames,
+        }
+    }
+
+    pub fn push(&mut self, sample_wrapper: &SampleWrapper) {
+        if sample_wrapper.layout != self.layout {
+            self.samples.clear();
+            self.layout = sample_wrapper.layout;
+            self.channels = sample_wrapper.channels();
+        }
+
+        self.samples.extend(sample_wrapper.samples.iter().copied());
+
+        let max_samples = self.max_frames * self.channels;
+        if self.samples.len() > max_samples {
+            let excess = self.samples.len() - max_samples;
+            self.samples.drain(..excess);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    // Makes room for at least `max_frames` of history; never shrinks
+    pub fn ensure_capacity(&mut self, max_frames: usize) {
+        self.max_frames = self.max_frames.max(max_frames);
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.samples.len() / self.channels
+    }
+
+    pub fn sample(&self, frame: usize, channel: usize) -> f32 {
+        self.samples
+            .get(frame * self.channels + channel)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    // The newest `frame_count` frames mixed down to mono, zero-padded at the
+    // start while the buffer is still filling
+    pub fn latest_mono(&self, frame_count: usize) -> Vec<f32> {
+        let available = self.frame_count();
+        let padding = frame_count.saturating_sub(available);
+        let first = available.saturating_sub(frame_count);
+        std::iter::repeat_n(0.0, padding)
+            .chain((first..available).map(|frame| self.summed(frame)))
+            .collect()
+    }
+
+    // Average of every channel in the frame
+    pub fn summed(&self, frame: usize) -> f32 {
+        let total: f32 = (0..self.channels).map(|channel| self.sample(frame, channel)).sum();
+        total / self.channels as f32
+    }
+}
@@ -0,0 +1,291 @@
+use sdl2::keyboard::Keycode;
+use sdl2::rect::{Point, Rect};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WidgetState {
+    pub hovered: bool,
+    pub pressed: bool,
+    pub focused: bool,
+    pub disabled: bool,
+}
+
+// Input as seen by widgets, with mouse positions already in drawable pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WidgetEvent {
+    MouseMove(Point),
+    MouseDown(Point),
+    MouseUp(Point),
+    Key(Keycode),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WidgetResponse {
+    None,
+    Clicked,
+    Changed(u8),
+}
+
+pub trait Widget {
+    fn bounds(&self) -> Rect;
+    fn state(&self) -> &WidgetState;
+    fn state_mut(&mut self) -> &mut WidgetState;
+
+    // Called by `dispatch` once it has decided the event concerns this widget.
+    // Hover, press and focus bookkeeping is already done by then.
+    fn handle_event(&mut self, event: &WidgetEvent) -> WidgetResponse;
+
+    fn hit_test(&self, point: Point) -> bool {
+        !self.state().disabled && self.bounds().contains_point(point)
+    }
+
+    fn is_focusable(&self) -> bool {
+        !self.state().disabled
+    }
+}
+
+// Routes `event` to the widgets it concerns and returns the first meaningful
+// response along with the id of the widget that gave it
+pub fn dispatch<Id: Copy>(
+    widgets: &mut [(Id, &mut dyn Widget)],
+    event: &WidgetEvent,
+) -> Option<(Id, WidgetResponse)> {
+    let mut result = None;
+    let mut respond = |id: Id, response: WidgetResponse| {
+        if response != WidgetResponse::None && result.is_none() {
+            result = Some((id, response));
+        }
+    };
+
+    match *event {
+        WidgetEvent::MouseMove(point) => {
+            for (id, widget) in widgets.iter_mut() {
+                let hovered = widget.hit_test(point);
+                widget.state_mut().hovered = hovered;
+                if widget.state().pressed {
+                    respond(*id, widget.handle_event(event));
+                }
+            }
+        }
+        WidgetEvent::MouseDown(point) => {
+            // Focus follows the click, and clicking empty space clears it
+            let target = widgets.iter().position(|(_, widget)| widget.hit_test(point));
+            for (index, (id, widget)) in widgets.iter_mut().enumerate() {
+                let is_target = Some(index) == target;
+                let state = widget.state_mut();
+                state.focused = is_target;
+                state.pressed = is_target;
+                if is_target {
+                    respond(*id, widget.handle_event(event));
+                }
+            }
+        }
+        WidgetEvent::MouseUp(_) => {
+            for (id, widget) in widgets.iter_mut() {
+                if widget.state().pressed {
+                    widget.state_mut().pressed = false;
+                    respond(*id, widget.handle_event(event));
+                }
+            }
+        }
+        WidgetEvent::Key(_) => {
+            if let Some((id, widget)) = widgets
+                .iter_mut()
+                .find(|(_, widget)| widget.state().focused && widget.is_focusable())
+            {
+                respond(*id, widget.handle_event(event));
+            }
+        }
+    }
+    result
+}
+
+// Moves keyboard focus to the next (or previous) focusable widget, wrapping
+// around at either end
+pub fn focus_next<Id: Copy>(widgets: &mut [(Id, &mut dyn Widget)], backwards: bool) -> Option<Id> {
+    let count = widgets.len();
+    let current = widgets.iter().position(|(_, widget)| widget.state().focused);
+    for (_, widget) in widgets.iter_mut() {
+        widget.state_mut().focused = false;
+    }
+
+    for step in 1..=count {
+        let index = match (current, backwards) {
+            (Some(current), false) => (current + step) % count,
+            (Some(current), true) => (current + count - step) % count,
+            (None, false) => step - 1,
+            (None, true) => count - step,
+        };
+        if widgets[index].1.is_focusable() {
+            widgets[index].1.state_mut().focused = true;
+            return Some(widgets[index].0);
+        }
+    }
+    None
+}
+
+// Returns whether any widget had focus
+pub fn clear_focus<Id>(widgets: &mut [(Id, &mut dyn Widget)]) -> bool {
+    let mut had_focus = false;
+    for (_, widget) in widgets.iter_mut() {
+        had_focus |= widget.state().focused;
+        widget.state_mut().focused = false;
+    }
+    had_focus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Clicks when released over itself, like `Button`
+    struct StubButton {
+        bounds: Rect,
+        state: WidgetState,
+    }
+
+    // Reports every position it is dragged to, like `ColourSlider`
+    struct StubSlider {
+        bounds: Rect,
+        state: WidgetState,
+    }
+
+    impl Widget for StubButton {
+        fn bounds(&self) -> Rect {
+            self.bounds
+        }
+
+        fn state(&self) -> &WidgetState {
+            &self.state
+        }
+
+        fn state_mut(&mut self) -> &mut WidgetState {
+            &mut self.state
+        }
+
+        fn handle_event(&mut self, event: &WidgetEvent) -> WidgetResponse {
+            match *event {
+                WidgetEvent::MouseUp(point) if self.hit_test(point) => WidgetResponse::Clicked,
+                WidgetEvent::Key(Keycode::Return) => WidgetResponse::Clicked,
+                _ => WidgetResponse::None,
+            }
+        }
+    }
+
+    impl Widget for StubSlider {
+        fn bounds(&self) -> Rect {
+            self.bounds
+        }
+
+        fn state(&self) -> &WidgetState {
+            &self.state
+        }
+
+        fn state_mut(&mut self) -> &mut WidgetState {
+            &mut self.state
+        }
+
+        fn handle_event(&mut self, event: &WidgetEvent) -> WidgetResponse {
+            match *event {
+                WidgetEvent::MouseDown(point) | WidgetEvent::MouseMove(point) => {
+                    WidgetResponse::Changed(point.x().clamp(0, 255) as u8)
+                }
+                _ => WidgetResponse::None,
+            }
+        }
+    }
+
+    // Three buttons side by side at x 0, 100 and 200, then a slider below them
+    fn buttons() -> [StubButton; 3] {
+        [0, 100, 200].map(|x| StubButton { bounds: Rect::new(x, 0, 80, 40), state: WidgetState::default() })
+    }
+
+    fn slider() -> StubSlider {
+        StubSlider { bounds: Rect::new(0, 100, 256, 20), state: WidgetState::default() }
+    }
+
+    fn focused(widgets: &[(usize, &mut dyn Widget)]) -> Vec<usize> {
+        widgets.iter().filter(|(_, widget)| widget.state().focused).map(|(id, _)| *id).collect()
+    }
+
+    #[test]
+    fn focus_wraps_around_both_ways() {
+        let [mut first, mut second, mut third] = buttons();
+        let mut widgets: [(usize, &mut dyn Widget); 3] = [(0, &mut first), (1, &mut second), (2, &mut third)];
+
+        assert_eq!(focus_next(&mut widgets, false), Some(0));
+        assert_eq!(focus_next(&mut widgets, false), Some(1));
+        assert_eq!(focus_next(&mut widgets, false), Some(2));
+        assert_eq!(focus_next(&mut widgets, false), Some(0));
+        assert_eq!(focus_next(&mut widgets, true), Some(2));
+        assert_eq!(focused(&widgets), [2]);
+
+        assert!(clear_focus(&mut widgets));
+        assert!(focused(&widgets).is_empty());
+        assert!(!clear_focus(&mut widgets));
+        // With nothing focused, going backwards starts from the end
+        assert_eq!(focus_next(&mut widgets, true), Some(2));
+    }
+
+    #[test]
+    fn disabled_widgets_are_skipped() {
+        let [mut first, mut second, mut third] = buttons();
+        second.state.disabled = true;
+        let mut widgets: [(usize, &mut dyn Widget); 3] = [(0, &mut first), (1, &mut second), (2, &mut third)];
+
+        assert_eq!(focus_next(&mut widgets, false), Some(0));
+        assert_eq!(focus_next(&mut widgets, false), Some(2));
+        assert_eq!(focus_next(&mut widgets, true), Some(0));
+
+        // Clicks go through a disabled widget and clear the focus instead
+        let down = dispatch(&mut widgets, &WidgetEvent::MouseDown(Point::new(110, 10)));
+        assert_eq!(down, None);
+        assert!(focused(&widgets).is_empty());
+        assert!(!widgets[1].1.state().pressed);
+
+        for (_, widget) in widgets.iter_mut() {
+            widget.state_mut().disabled = true;
+        }
+        assert_eq!(focus_next(&mut widgets, false), None);
+        assert!(focused(&widgets).is_empty());
+    }
+
+    #[test]
+    fn releasing_outside_cancels_a_click() {
+        let [mut first, mut second, _] = buttons();
+        let mut widgets: [(usize, &mut dyn Widget); 2] = [(0, &mut first), (1, &mut second)];
+
+        assert_eq!(dispatch(&mut widgets, &WidgetEvent::MouseDown(Point::new(10, 10))), None);
+        assert!(widgets[0].1.state().pressed && widgets[0].1.state().focused);
+        assert_eq!(dispatch(&mut widgets, &WidgetEvent::MouseUp(Point::new(10, 10))), Some((0, WidgetResponse::Clicked)));
+
+        dispatch(&mut widgets, &WidgetEvent::MouseDown(Point::new(10, 10)));
+        dispatch(&mut widgets, &WidgetEvent::MouseMove(Point::new(110, 10)));
+        assert!(!widgets[0].1.state().hovered && widgets[1].1.state().hovered);
+        // Released over the other button, which was never pressed
+        assert_eq!(dispatch(&mut widgets, &WidgetEvent::MouseUp(Point::new(110, 10))), None);
+        assert!(!widgets[0].1.state().pressed);
+
+        // Keys go to the focused widget only
+        assert_eq!(dispatch(&mut widgets, &WidgetEvent::Key(Keycode::Return)), Some((0, WidgetResponse::Clicked)));
+    }
+
+    #[test]
+    fn dragged_slider_follows_the_mouse_outside_its_bounds() {
+        let [mut button, _, _] = buttons();
+        let mut slider = slider();
+        let mut widgets: [(usize, &mut dyn Widget); 2] = [(0, &mut button), (1, &mut slider)];
+
+        // Moving over the slider without pressing changes nothing
+        assert_eq!(dispatch(&mut widgets, &WidgetEvent::MouseMove(Point::new(50, 110))), None);
+
+        let down = dispatch(&mut widgets, &WidgetEvent::MouseDown(Point::new(50, 110)));
+        assert_eq!(down, Some((1, WidgetResponse::Changed(50))));
+        // Dragged up over the button, the slider still gets the moves
+        let moved = dispatch(&mut widgets, &WidgetEvent::MouseMove(Point::new(70, 10)));
+        assert_eq!(moved, Some((1, WidgetResponse::Changed(70))));
+        assert!(widgets[0].1.state().hovered);
+
+        dispatch(&mut widgets, &WidgetEvent::MouseUp(Point::new(70, 10)));
+        assert_eq!(dispatch(&mut widgets, &WidgetEvent::MouseMove(Point::new(90, 110))), None);
+    }
+}
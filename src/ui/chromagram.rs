@@ -0,0 +1,70 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget};
+use crate::structs::chroma::{ChromaAnalyzer, CHROMAGRAM_HISTORY};
+use crate::structs::visualisation_state::VisualisationState;
+
+// Share of the width given to the bars showing the current chroma vector
+const CURRENT_FRACTION: f32 = 0.15;
+const GAP: i32 = 10;
+
+// Scrolling history of the 12 pitch classes, C at the bottom, with the newest
+// column on the right and the current vector beside it as bars. The tonic of
+// the estimated key is outlined.
+pub fn draw_chromagram<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    area: Rect,
+    chroma: &ChromaAnalyzer,
+    state: &VisualisationState,
+) -> Result<(), String> {
+    let palette = state.get_palette();
+    let current_width = (area.width() as f32 * CURRENT_FRACTION) as i32;
+    let history_width = area.width() as i32 - current_width - GAP;
+    if history_width <= 0 {
+        return Ok(());
+    }
+    let row_top = |pitch: usize| area.y() + (area.height() as usize * (11 - pitch) / 12) as i32;
+    let row_height = |pitch: usize| (row_top(pitch.saturating_sub(1)) - row_top(pitch)).max(1) as u32;
+    let last_row_height = (area.bottom() - row_top(0)).max(1) as u32;
+    let height_of = |pitch: usize| if pitch == 0 { last_row_height } else { row_height(pitch) };
+
+    // Columns fill the history width, oldest on the left
+    let offset = CHROMAGRAM_HISTORY - chroma.history.len();
+    for (index, column) in chroma.history.iter().enumerate() {
+        let left = area.x() + (history_width as usize * (offset + index) / CHROMAGRAM_HISTORY) as i32;
+        let right = area.x() + (history_width as usize * (offset + index + 1) / CHROMAGRAM_HISTORY) as i32;
+        for (pitch, &value) in column.iter().enumerate() {
+            if value <= 0.05 {
+                continue;
+            }
+            canvas.set_draw_color(shade(palette.sample(value), value));
+            canvas.fill_rect(Rect::new(left, row_top(pitch), (right - left).max(1) as u32, height_of(pitch)))?;
+        }
+    }
+
+    let bars_left = area.x() + history_width + GAP;
+    for (pitch, &value) in chroma.chroma.iter().enumerate() {
+        let width = (value * current_width as f32) as u32;
+        if width == 0 {
+            continue;
+        }
+        canvas.set_draw_color(palette.sample(value));
+        canvas.fill_rect(Rect::new(bars_left, row_top(pitch) + 1, width, height_of(pitch).saturating_sub(2).max(1)))?;
+    }
+
+    if let Some(key) = chroma.key {
+        canvas.set_draw_color(state.get_selected_color());
+        canvas.draw_rect(Rect::new(bars_left, row_top(key.tonic), current_width as u32, height_of(key.tonic)))?;
+    }
+    Ok(())
+}
+
+// Darkens quiet cells so the strongest pitch classes stand out
+fn shade(colour: Color, value: f32) -> Color {
+    let scale = value.clamp(0.0, 1.0);
+    Color::RGB(
+        (colour.r as f32 * scale) as u8,
+        (colour.g as f32 * scale) as u8,
+        (colour.b as f32 * scale) as u8,
+    )
+}
@@ -0,0 +1,177 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::surface::Surface;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use crate::structs::app_state::AppState;
+use crate::structs::sample_wrapper::SampleWrapper;
+use crate::structs::visualisation_mode::VisualisationMode;
+use crate::ui::screenshot::write_png;
+use crate::ui::spectrogram::SpectrogramView;
+use crate::ui::ui::format_time;
+use crate::ui::visualisation::draw_visualization;
+
+// Outputs with these extensions are encoded by ffmpeg, anything else is
+// treated as a folder for a PNG sequence
+const VIDEO_EXTENSIONS: [&str; 5] = ["mp4", "mkv", "mov", "webm", "avi"];
+
+// Where rendered frames go: numbered PNG files, or raw RGB frames piped to
+// ffmpeg, which also muxes in the audio from the source file
+pub enum FrameWriter {
+    PngSequence { directory: PathBuf, frames_written: u32 },
+    Ffmpeg { child: Child, stdin: Option<ChildStdin> },
+}
+
+impl FrameWriter {
+    pub fn new(output: &Path, audio: &Path, size: (u32, u32), fps: u32) -> Result<Self, Box<dyn std::error::Error>> {
+        let is_video = output
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| VIDEO_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+        if !is_video {
+            fs::create_dir_all(output)?;
+            return Ok(FrameWriter::PngSequence { directory: output.to_path_buf(), frames_written: 0 });
+        }
+
+        if !ffmpeg_available() {
+            return Err(format!(
+                "ffmpeg was not found, so {} cannot be encoded. Give a folder instead to write a PNG sequence",
+                output.display()
+            )
+            .into());
+        }
+        let mut child = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pixel_format", "rgb24"])
+            .args(["-video_size", &format!("{}x{}", size.0, size.1)])
+            .args(["-framerate", &fps.to_string()])
+            .args(["-i", "-"])
+            .arg("-i")
+            .arg(audio)
+            .args(["-map", "0:v", "-map", "1:a", "-pix_fmt", "yuv420p", "-shortest"])
+            .arg(output)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        Ok(FrameWriter::Ffmpeg { child, stdin })
+    }
+
+    // `pixels` is tightly packed RGB24
+    pub fn write_frame(&mut self, pixels: &[u8], size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            FrameWriter::PngSequence { directory, frames_written } => {
+                *frames_written += 1;
+                let path = directory.join(format!("frame_{:06}.png", frames_written));
+                write_png(&path, pixels, size, &[])?;
+            }
+            FrameWriter::Ffmpeg { child, stdin } => {
+                let written = stdin.as_mut().ok_or("ffmpeg input is closed")?.write_all(pixels);
+                // A broken pipe usually means ffmpeg exited, so report why
+                if let Err(error) = written {
+                    *stdin = None;
+                    let status = child.wait()?;
+                    return Err(format!("could not send a frame to ffmpeg ({}): {}", status, error).into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), Box<dyn std::error::Error>> {
+        if let FrameWriter::Ffmpeg { mut child, stdin } = self {
+            // Closing the pipe tells ffmpeg the video is complete
+            drop(stdin);
+            let status = child.wait()?;
+            if !status.success() {
+                return Err(format!("ffmpeg failed ({})", status).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn ffmpeg_available() -> bool {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+// Renders the visualisation of a decoded track frame by frame with the
+// software renderer. Analysis advances by exactly one video frame of audio
+// each time, so the result does not depend on how fast the machine is.
+pub fn render_track(
+    app_state: &mut AppState,
+    track: &SampleWrapper,
+    writer: &mut FrameWriter,
+    size: (u32, u32),
+    fps: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let surface = Surface::new(size.0, size.1, PixelFormatEnum::RGB24)?;
+    let mut canvas = surface.into_canvas()?;
+    let texture_creator = canvas.texture_creator();
+    let mut spectrogram_view = SpectrogramView::new(&texture_creator);
+    let area = Rect::new(0, 0, size.0, size.1);
+
+    let channels = track.channels();
+    let total_frames = track.samples.len() / channels;
+    let duration = total_frames as f64 / track.sample_rate as f64;
+    let video_frames = (duration * fps as f64).ceil() as u64;
+    let dt = 1.0 / fps as f32;
+    app_state.wait_for_constant_q(track.sample_rate);
+
+    let mut start = 0;
+    for frame in 0..video_frames {
+        let end = (((frame + 1) as f64 * track.sample_rate as f64 / fps as f64).round() as usize).min(total_frames);
+        let chunk = SampleWrapper {
+            samples: track.samples[start * channels..end * channels].to_vec(),
+            layout: track.layout,
+            sample_rate: track.sample_rate,
+        };
+        start = end;
+        app_state.analyse(&chunk);
+
+        if let Some(colours) = app_state.colour_randomiser.advance(dt) {
+            app_state.visualisation_state.lock().unwrap().palette.set_stop_colours(&colours);
+        }
+        let state = app_state.visualisation_state.lock().unwrap();
+        if state.get_mode() == VisualisationMode::Particles {
+            let beat_detector = &app_state.beat_detector;
+            let brightness = app_state.analysis.brightness();
+            app_state.particle_system.update(dt, beat_detector.energy, beat_detector.beat_count, brightness);
+        }
+
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.clear();
+        draw_visualization(&mut canvas, area, app_state, &state, &mut spectrogram_view)?;
+        drop(state);
+        let pixels = canvas.read_pixels(None, PixelFormatEnum::RGB24)?;
+        writer.write_frame(&pixels, size)?;
+
+        if (frame + 1) % (fps as u64 * 10) == 0 {
+            let seconds = ((frame + 1) / fps as u64) as u32;
+            println!("Rendered {} / {}", format_time(seconds), format_time(duration as u32));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoder_exit_status_is_reported() {
+        // Stands in for an ffmpeg that gives up straight away. The frame is
+        // larger than a pipe buffer, so the write cannot finish before it exits
+        let mut child = Command::new("sh").args(["-c", "exit 3"]).stdin(Stdio::piped()).spawn().unwrap();
+        let stdin = child.stdin.take();
+        let mut writer = FrameWriter::Ffmpeg { child, stdin };
+        let error = writer.write_frame(&vec![0; 512 * 512 * 3], (512, 512)).unwrap_err().to_string();
+        assert!(error.contains("exit status: 3"), "{}", error);
+    }
+}
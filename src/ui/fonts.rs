@@ -0,0 +1,243 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::BlendMode;
+use sdl2::rwops::RWops;
+use sdl2::surface::Surface;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::structs::config::UiConfig;
+
+// DejaVu Sans, see assets/fonts/DejaVuSans-LICENSE.txt
+const EMBEDDED_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+const EMBEDDED_FONT_NAME: &str = "DejaVu Sans (built in)";
+const FONT_EXTENSIONS: [&str; 3] = ["ttf", "otf", "ttc"];
+// Font folders are rarely nested deeper than this
+const MAX_SEARCH_DEPTH: usize = 4;
+
+// The interface font plus fallbacks for characters it has no glyphs for
+pub struct TextFont<'ttf> {
+    // Identifies the font in the text cache
+    pub name: String,
+    pub size: u16,
+    pub font: Font<'ttf, 'static>,
+    // Tried in order when `font` lacks a character
+    pub fallbacks: Vec<Font<'ttf, 'static>>,
+}
+
+impl<'ttf> TextFont<'ttf> {
+    // Loads the configured font (a file, or a family found in the system font
+    // folders), falling back to the built-in font if that fails
+    pub fn load(ttf_context: &'ttf Sdl2TtfContext, config: &UiConfig, size: u16) -> Result<Self, String> {
+        let system_fonts = system_font_files();
+        let configured = match (&config.font_path, &config.font_family) {
+            (Some(path), _) => Some(path.clone()),
+            (None, Some(family)) => {
+                let path = best_match(&system_fonts, family);
+                if path.is_none() {
+                    eprintln!("Font family '{}' was not found, using the built-in font", family);
+                }
+                path
+            }
+            (None, None) => None,
+        };
+        let loaded = configured.and_then(|path| match ttf_context.load_font(&path, size) {
+            Ok(font) => Some((path.display().to_string(), font)),
+            Err(e) => {
+                eprintln!("Could not load font {}: {}. Using the built-in font", path.display(), e);
+                None
+            }
+        });
+
+        let mut fallbacks = Vec::new();
+        let (name, font) = match loaded {
+            Some((name, font)) => {
+                // Keeps Latin, Greek and Cyrillic covered whatever the chosen font
+                fallbacks.push(load_embedded_font(ttf_context, size)?);
+                (name, font)
+            }
+            None => (EMBEDDED_FONT_NAME.to_string(), load_embedded_font(ttf_context, size)?),
+        };
+        for entry in &config.fallback_fonts {
+            let path = Path::new(entry);
+            let path = if path.is_file() { Some(path.to_path_buf()) } else { best_match(&system_fonts, entry) };
+            // Fallbacks are best effort, most of them only exist on one platform
+            if let Some(font) = path.and_then(|path| ttf_context.load_font(path, size).ok()) {
+                fallbacks.push(font);
+            }
+        }
+
+        Ok(TextFont { name, size, font, fallbacks })
+    }
+
+    // Renders `text`, switching to a fallback font for each run of characters
+    // the main font cannot show
+    pub fn render(&self, text: &str, colour: Color) -> Result<Surface<'static>, String> {
+        let runs = self.runs(text);
+        if let [(font, run)] = runs.as_slice() {
+            return font.render(run).blended(colour).map_err(|e| e.to_string());
+        }
+
+        let mut pieces = Vec::with_capacity(runs.len());
+        for (font, run) in runs {
+            let surface = font.render(run).blended(colour).map_err(|e| e.to_string())?;
+            pieces.push((surface, font.ascent()));
+        }
+        // Line every run up on a shared baseline
+        let baseline = pieces.iter().map(|(_, ascent)| *ascent).max().unwrap_or(0);
+        let width: u32 = pieces.iter().map(|(surface, _)| surface.width()).sum();
+        let height = pieces
+            .iter()
+            .map(|(surface, ascent)| (baseline - ascent) as u32 + surface.height())
+            .max()
+            .unwrap_or(1);
+
+        let mut combined = Surface::new(width.max(1), height.max(1), PixelFormatEnum::ARGB8888)?;
+        let mut x = 0;
+        for (mut surface, ascent) in pieces {
+            surface.set_blend_mode(BlendMode::None)?;
+            let target = Rect::new(x, baseline - ascent, surface.width(), surface.height());
+            surface.blit(None, &mut combined, target)?;
+            x += surface.width() as i32;
+        }
+        Ok(combined)
+    }
+
+    // Splits `text` into runs that can each be drawn with a single font
+    fn runs<'t>(&self, text: &'t str) -> Vec<(&Font<'ttf, 'static>, &'t str)> {
+        let mut runs: Vec<(&Font<'ttf, 'static>, &'t str)> = Vec::new();
+        let mut start = 0;
+        let mut current: Option<&Font<'ttf, 'static>> = None;
+        for (index, ch) in text.char_indices() {
+            let font = self.font_for(ch);
+            match current {
+                Some(previous) if std::ptr::eq(previous, font) => {}
+                Some(previous) => {
+                    runs.push((previous, &text[start..index]));
+                    start = index;
+                    current = Some(font);
+                }
+                None => current = Some(font),
+            }
+        }
+        runs.push((current.unwrap_or(&self.font), &text[start..]));
+        runs
+    }
+
+    fn font_for(&self, ch: char) -> &Font<'ttf, 'static> {
+        if ch.is_whitespace() {
+            return &self.font;
+        }
+        // The SDL_ttf bindings can only look up glyphs below U+FFFF, so
+        // anything beyond (mostly emoji) goes to the first emoji or symbol font
+        if ch as u32 > 0xFFFF {
+            return self
+                .fallbacks
+                .iter()
+                .find(|font| {
+                    font.face_family_name()
+                        .map(|name| name.to_lowercase())
+                        .is_some_and(|name| name.contains("emoji") || name.contains("symbol"))
+                })
+                .unwrap_or(&self.font);
+        }
+        if self.font.find_glyph(ch).is_some() {
+            return &self.font;
+        }
+        self.fallbacks
+            .iter()
+            .find(|font| font.find_glyph(ch).is_some())
+            .unwrap_or(&self.font)
+    }
+}
+
+fn load_embedded_font(ttf_context: &Sdl2TtfContext, size: u16) -> Result<Font<'_, 'static>, String> {
+    ttf_context.load_font_from_rwops(RWops::from_bytes(EMBEDDED_FONT)?, size)
+}
+
+// Picks the font file whose name best matches `family`, ignoring case, spaces
+// and punctuation. "Noto Sans CJK" matches NotoSansCJK-Regular.ttc, and an
+// exact or regular-weight file wins over bold and italic ones.
+fn best_match(files: &[PathBuf], family: &str) -> Option<PathBuf> {
+    let wanted = normalise(family);
+    if wanted.is_empty() {
+        return None;
+    }
+    files
+        .iter()
+        .filter_map(|path| {
+            let stem = normalise(&path.file_stem()?.to_string_lossy());
+            let rest = stem.strip_prefix(&wanted)?;
+            let score = match rest {
+                "" => 0,
+                "regular" => 1,
+                _ => 2 + rest.len(),
+            };
+            Some((score, path))
+        })
+        .min_by_key(|(score, _)| *score)
+        .map(|(_, path)| path.clone())
+}
+
+fn normalise(name: &str) -> String {
+    name.chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(|ch| ch.to_lowercase())
+        .collect()
+}
+
+fn system_font_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for directory in font_directories() {
+        collect_font_files(&directory, 0, &mut files);
+    }
+    files
+}
+
+fn collect_font_files(directory: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth < MAX_SEARCH_DEPTH {
+                collect_font_files(&path, depth + 1, files);
+            }
+        } else if path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| FONT_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        {
+            files.push(path);
+        }
+    }
+}
+
+fn font_directories() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+    if cfg!(target_os = "windows") {
+        if let Some(windows) = std::env::var_os("WINDIR") {
+            directories.push(PathBuf::from(windows).join("Fonts"));
+        }
+        if let Some(local) = dirs::data_local_dir() {
+            directories.push(local.join("Microsoft").join("Windows").join("Fonts"));
+        }
+    } else if cfg!(target_os = "macos") {
+        directories.push(PathBuf::from("/System/Library/Fonts"));
+        directories.push(PathBuf::from("/Library/Fonts"));
+        if let Some(home) = dirs::home_dir() {
+            directories.push(home.join("Library").join("Fonts"));
+        }
+    } else {
+        directories.push(PathBuf::from("/usr/share/fonts"));
+        directories.push(PathBuf::from("/usr/local/share/fonts"));
+        if let Some(data) = dirs::data_dir() {
+            directories.push(data.join("fonts"));
+        }
+        if let Some(home) = dirs::home_dir() {
+            directories.push(home.join(".fonts"));
+        }
+    }
+    directories
+}
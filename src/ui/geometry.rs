@@ -0,0 +1,40 @@
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, RenderTarget};
+use sdl2::sys::{SDL_Color, SDL_FPoint, SDL_RenderGeometry, SDL_Vertex};
+
+// sdl2 0.37 does not wrap SDL_RenderGeometry yet, so this is a minimal safe
+// shim for untextured triangle lists.
+pub fn vertex(x: f32, y: f32, colour: Color) -> SDL_Vertex {
+    SDL_Vertex {
+        position: SDL_FPoint { x, y },
+        color: SDL_Color { r: colour.r, g: colour.g, b: colour.b, a: colour.a },
+        tex_coord: SDL_FPoint { x: 0.0, y: 0.0 },
+    }
+}
+
+// Draws the triangles described by `indices`, three per triangle
+pub fn render_geometry<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    vertices: &[SDL_Vertex],
+    indices: &[i32],
+) -> Result<(), String> {
+    if indices.iter().any(|&index| index < 0 || index as usize >= vertices.len()) {
+        return Err("geometry index out of range".to_string());
+    }
+    // SAFETY: the renderer pointer is owned by the live canvas, both slices
+    // outlive the call, and every index was checked against the vertex count.
+    let result = unsafe {
+        SDL_RenderGeometry(
+            canvas.raw(),
+            std::ptr::null_mut(),
+            vertices.as_ptr(),
+            vertices.len() as i32,
+            indices.as_ptr(),
+            indices.len() as i32,
+        )
+    };
+    if result != 0 {
+        return Err(sdl2::get_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{Canvas, RenderTarget};
+use crate::structs::goniometer::{Goniometer, TRAIL_LENGTH};
+use crate::structs::visualisation_state::VisualisationState;
+
+const METER_HEIGHT: u32 = 20;
+const METER_GAP: u32 = 10;
+
+pub fn draw_goniometer<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    area: Rect,
+    goniometer: &Goniometer,
+    state: &VisualisationState,
+) -> Result<(), String> {
+    let scope_size = area.height() - METER_HEIGHT - METER_GAP;
+    let scope_rect = Rect::new(
+        area.x() + (area.width() as i32 - scope_size as i32) / 2,
+        area.y(),
+        scope_size,
+        scope_size,
+    );
+    let centre = scope_rect.center();
+    let radius = scope_size as f32 / 2.0;
+
+    // Graticule: mid (M) vertical, side (S) horizontal, and the L/R diagonals
+    canvas.set_draw_color(Color::RGB(40, 40, 40));
+    canvas.draw_rect(scope_rect)?;
+    canvas.draw_line(Point::new(centre.x(), scope_rect.top()), Point::new(centre.x(), scope_rect.bottom()))?;
+    canvas.draw_line(Point::new(scope_rect.left(), centre.y()), Point::new(scope_rect.right(), centre.y()))?;
+    canvas.draw_line(scope_rect.top_left(), Point::new(scope_rect.right(), scope_rect.bottom()))?;
+    canvas.draw_line(Point::new(scope_rect.right(), scope_rect.top()), Point::new(scope_rect.left(), scope_rect.bottom()))?;
+
+    let colour = state.get_selected_color();
+    let trail_len = goniometer.trail.len();
+    for (age, points) in goniometer.trail.iter().enumerate() {
+        // Newest packet is at full brightness, older ones fade towards black
+        let brightness = (age + 1 + TRAIL_LENGTH - trail_len) as f32 / TRAIL_LENGTH as f32;
+        canvas.set_draw_color(Color::RGB(
+            (colour.r as f32 * brightness) as u8,
+            (colour.g as f32 * brightness) as u8,
+            (colour.b as f32 * brightness) as u8,
+        ));
+        let screen_points: Vec<Point> = points
+            .iter()
+            .map(|&(side, mid)| {
+                Point::new(
+                    centre.x() + (side * radius) as i32,
+                    centre.y() - (mid * radius) as i32,
+                )
+            })
+            .collect();
+        canvas.draw_points(screen_points.as_slice())?;
+    }
+
+    draw_correlation_meter(
+        canvas,
+        Rect::new(scope_rect.x(), scope_rect.bottom() + METER_GAP as i32, scope_size, METER_HEIGHT),
+        goniometer.correlation,
+    )
+}
+
+// Horizontal bar from -1 (out of phase) on the left to +1 (mono) on the right
+fn draw_correlation_meter<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    meter_rect: Rect,
+    correlation: f32,
+) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(20, 20, 20));
+    canvas.fill_rect(meter_rect)?;
+
+    let centre_x = meter_rect.x() + meter_rect.width() as i32 / 2;
+    let value_x = centre_x + (correlation.clamp(-1.0, 1.0) * meter_rect.width() as f32 / 2.0) as i32;
+    let (left, right) = if value_x < centre_x { (value_x, centre_x) } else { (centre_x, value_x) };
+
+    // Negative correlation warns of mono compatibility problems
+    if correlation < 0.0 {
+        canvas.set_draw_color(Color::RGB(220, 50, 30));
+    } else {
+        canvas.set_draw_color(Color::RGB(47, 198, 18));
+    }
+    canvas.fill_rect(Rect::new(left, meter_rect.y(), (right - left).max(1) as u32, meter_rect.height()))?;
+
+    canvas.set_draw_color(Color::RGB(200, 200, 200));
+    canvas.draw_line(Point::new(centre_x, meter_rect.top()), Point::new(centre_x, meter_rect.bottom()))?;
+    Ok(())
+}
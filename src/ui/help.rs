@@ -0,0 +1,130 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::BlendMode;
+use crate::ui::layout::{anchored, Anchor};
+use crate::ui::fonts::TextFont;
+use crate::ui::text_cache::TextCache;
+
+const PANEL_PADDING: u32 = 20;
+const LINE_SPACING: u32 = 4;
+const COLUMN_GAP: u32 = 40;
+
+// Dims the window and lists the keybindings in a centred panel
+pub fn draw_help_overlay(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    cache: &mut TextCache,
+    font: &TextFont,
+    lines: &[String],
+    theme: Color,
+) -> Result<(), String> {
+    let (window_width, window_height) = canvas.output_size()?;
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+    canvas.fill_rect(Rect::new(0, 0, window_width, window_height))?;
+    canvas.set_blend_mode(BlendMode::None);
+
+    let mut sizes = Vec::with_capacity(lines.len());
+    for line in lines.iter().filter(|line| !line.is_empty()) {
+        let query = cache.get(font, line, theme)?.query();
+        sizes.push((query.width, query.height));
+    }
+    let (placements, content_size) = arrange_lines(&sizes, (window_width, window_height));
+    let panel = anchored(
+        Rect::new(0, 0, window_width, window_height),
+        Anchor::Centre,
+        content_size.0 + PANEL_PADDING * 2,
+        content_size.1 + PANEL_PADDING * 2,
+    );
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.fill_rect(panel)?;
+    canvas.set_draw_color(theme);
+    canvas.draw_rect(panel)?;
+
+    let origin = (panel.x() + PANEL_PADDING as i32, panel.y() + PANEL_PADDING as i32);
+    for (line, place) in lines.iter().filter(|line| !line.is_empty()).zip(placements) {
+        let texture = cache.get(font, line, theme)?;
+        // The same texture may have been faded out with the controls
+        texture.set_alpha_mod(255);
+        canvas.copy(texture, None, Some(Rect::new(origin.0 + place.x(), origin.1 + place.y(), place.width(), place.height())))?;
+    }
+    Ok(())
+}
+
+// Places lines of the given sizes top to bottom, starting another column
+// whenever a single one would not fit in `window` with the padding, and
+// shrinks the lot if even that is too big. Returns each line's rect relative
+// to the top left of the text, and the size of the text as a whole.
+fn arrange_lines(sizes: &[(u32, u32)], window: (u32, u32)) -> (Vec<Rect>, (u32, u32)) {
+    let available = (
+        window.0.saturating_sub(PANEL_PADDING * 2).max(1),
+        window.1.saturating_sub(PANEL_PADDING * 2).max(1),
+    );
+    let line_height = |&(_, height): &(u32, u32)| height + LINE_SPACING;
+    let column_height = |per_column: usize| {
+        sizes.chunks(per_column.max(1)).map(|column| column.iter().map(line_height).sum::<u32>()).max().unwrap_or(0)
+    };
+    let mut columns = 1;
+    while columns < sizes.len() && column_height(sizes.len().div_ceil(columns)) > available.1 {
+        columns += 1;
+    }
+    let per_column = sizes.len().div_ceil(columns).max(1);
+
+    let mut places = Vec::with_capacity(sizes.len());
+    let mut x = 0;
+    for column in sizes.chunks(per_column) {
+        let mut y = 0;
+        for &(width, height) in column {
+            places.push((x, y, width, height));
+            y += height + LINE_SPACING;
+        }
+        x += column.iter().map(|&(width, _)| width).max().unwrap_or(0) + COLUMN_GAP;
+    }
+    let content = (x.saturating_sub(COLUMN_GAP), column_height(per_column));
+
+    let scale = (available.0 as f32 / content.0.max(1) as f32)
+        .min(available.1 as f32 / content.1.max(1) as f32)
+        .min(1.0);
+    let scaled = |value: u32| (value as f32 * scale) as u32;
+    let rects = places
+        .into_iter()
+        .map(|(x, y, width, height)| Rect::new(scaled(x) as i32, scaled(y) as i32, scaled(width).max(1), scaled(height).max(1)))
+        .collect();
+    (rects, (scaled(content.0), scaled(content.1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every default binding at the default font size
+    const LINES: [(u32, u32); 22] = [(300, 32); 22];
+
+    fn fits(window: (u32, u32)) -> (Vec<Rect>, (u32, u32)) {
+        let (rects, content) = arrange_lines(&LINES, window);
+        let text = Rect::new(0, 0, content.0, content.1);
+        assert!(rects.iter().all(|rect| text.contains_rect(*rect)), "{:?} outside {:?}", rects, text);
+        assert!(content.0 + PANEL_PADDING * 2 <= window.0 && content.1 + PANEL_PADDING * 2 <= window.1);
+        (rects, content)
+    }
+
+    #[test]
+    fn short_lists_stay_in_one_column() {
+        let (rects, content) = arrange_lines(&LINES[..5], (1280, 720));
+        assert!(rects.iter().all(|rect| rect.x() == 0));
+        assert_eq!(content, (300, 5 * 36));
+    }
+
+    #[test]
+    fn long_lists_wrap_into_columns() {
+        let (rects, content) = fits((1280, 720));
+        assert_eq!(rects[11], Rect::new(300 + COLUMN_GAP as i32, 0, 300, 32));
+        assert_eq!(content, (600 + COLUMN_GAP, 11 * 36));
+    }
+
+    #[test]
+    fn lists_shrink_to_fit_the_smallest_window() {
+        let (rects, _) = fits((640, 480));
+        assert!(rects[0].height() < 32);
+        fits((200, 100));
+    }
+}
@@ -0,0 +1,193 @@
+use sdl2::rect::Rect;
+
+// Sizes given in logical pixels are multiplied by the DPI scale; percentages
+// are of the space available in the container
+#[derive(Clone, Copy, Debug)]
+pub enum Length {
+    Pixels(u32),
+    Percent(f32),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    Centre,
+}
+
+// Everything needed to turn logical sizes into drawable pixels
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutContext {
+    pub width: u32,
+    pub height: u32,
+    // Drawable pixels per window coordinate; above 1.0 on high-DPI displays
+    pub scale: f32,
+}
+
+impl LayoutContext {
+    pub fn new(drawable_size: (u32, u32), window_size: (u32, u32)) -> Self {
+        let scale = drawable_size.0 as f32 / window_size.0.max(1) as f32;
+        Self { width: drawable_size.0, height: drawable_size.1, scale: scale.max(1.0) }
+    }
+
+    pub fn from_canvas(canvas: &sdl2::render::Canvas<sdl2::video::Window>) -> Result<Self, String> {
+        Ok(Self::new(canvas.output_size()?, canvas.window().size()))
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    // Logical pixels to drawable pixels
+    pub fn px(&self, value: u32) -> u32 {
+        (value as f32 * self.scale).round() as u32
+    }
+
+    pub fn resolve(&self, length: Length, available: u32) -> u32 {
+        match length {
+            Length::Pixels(value) => self.px(value).min(available),
+            Length::Percent(percent) => (available as f32 * percent.clamp(0.0, 1.0)) as u32,
+        }
+    }
+
+    // Mouse events arrive in window coordinates, drawing is in drawable pixels
+    pub fn drawable_point(&self, x: i32, y: i32) -> (i32, i32) {
+        ((x as f32 * self.scale) as i32, (y as f32 * self.scale) as i32)
+    }
+}
+
+// A margin too big for the rect leaves a sliver at its centre
+pub fn inset(rect: Rect, margin: u32) -> Rect {
+    let margin_x = margin.min(rect.width() / 2);
+    let margin_y = margin.min(rect.height() / 2);
+    Rect::new(
+        rect.x() + margin_x as i32,
+        rect.y() + margin_y as i32,
+        (rect.width() - margin_x * 2).max(1),
+        (rect.height() - margin_y * 2).max(1),
+    )
+}
+
+// A `width` x `height` rect placed at `anchor` inside `container`
+pub fn anchored(container: Rect, anchor: Anchor, width: u32, height: u32) -> Rect {
+    let width = width.clamp(1, container.width());
+    let height = height.clamp(1, container.height());
+    let (x, y) = match anchor {
+        Anchor::TopLeft => (container.x(), container.y()),
+        Anchor::TopRight => (container.right() - width as i32, container.y()),
+        Anchor::Centre => (
+            container.x() + (container.width() - width) as i32 / 2,
+            container.y() + (container.height() - height) as i32 / 2,
+        ),
+    };
+    Rect::new(x, y, width, height)
+}
+
+// `count` equal cells laid out left to right, each no wider than `max_width`.
+// Cells are at least one pixel wide, so too many of them run past the right edge.
+pub fn row(container: Rect, count: u32, gap: u32, max_width: u32) -> Vec<Rect> {
+    let count = count.max(1);
+    let free = container.width().saturating_sub(gap.saturating_mul(count - 1));
+    let width = (free / count).min(max_width).max(1);
+    (0..count)
+        .map(|index| {
+            let x = container.x().saturating_add(cell_offset(index, width, gap));
+            Rect::new(x, container.y(), width, container.height())
+        })
+        .collect()
+}
+
+// `count` equal cells laid out top to bottom, each no taller than `max_height`
+pub fn column(container: Rect, count: u32, gap: u32, max_height: u32) -> Vec<Rect> {
+    let count = count.max(1);
+    let free = container.height().saturating_sub(gap.saturating_mul(count - 1));
+    let height = (free / count).min(max_height).max(1);
+    (0..count)
+        .map(|index| {
+            let y = container.y().saturating_add(cell_offset(index, height, gap));
+            Rect::new(container.x(), y, container.width(), height)
+        })
+        .collect()
+}
+
+fn cell_offset(index: u32, size: u32, gap: u32) -> i32 {
+    (index as i64 * (size as i64 + gap as i64)).min(i32::MAX as i64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lengths_scale_and_clamp() {
+        let normal = LayoutContext::new((1280, 720), (1280, 720));
+        let retina = LayoutContext::new((2560, 1440), (1280, 720));
+        assert_eq!(normal.scale, 1.0);
+        assert_eq!(retina.scale, 2.0);
+        assert_eq!(retina.px(24), 48);
+        assert_eq!(retina.drawable_point(100, 50), (200, 100));
+
+        assert_eq!(normal.resolve(Length::Pixels(200), 1000), 200);
+        assert_eq!(retina.resolve(Length::Pixels(200), 1000), 400);
+        // Never more than the space available
+        assert_eq!(retina.resolve(Length::Pixels(200), 300), 300);
+        assert_eq!(normal.resolve(Length::Percent(0.25), 1000), 250);
+        assert_eq!(normal.resolve(Length::Percent(1.5), 1000), 1000);
+        assert_eq!(normal.resolve(Length::Percent(-0.5), 1000), 0);
+        assert_eq!(normal.resolve(Length::Percent(f32::NAN), 1000), 0);
+
+        // A minimised window can report a zero size
+        let minimised = LayoutContext::new((0, 0), (0, 0));
+        assert_eq!(minimised.scale, 1.0);
+        assert_eq!(minimised.resolve(Length::Pixels(10), 0), 0);
+    }
+
+    #[test]
+    fn anchored_rects_stay_inside_the_container() {
+        let container = Rect::new(10, 20, 100, 50);
+        assert_eq!(anchored(container, Anchor::TopLeft, 30, 10), Rect::new(10, 20, 30, 10));
+        assert_eq!(anchored(container, Anchor::TopRight, 30, 10), Rect::new(80, 20, 30, 10));
+        assert_eq!(anchored(container, Anchor::Centre, 30, 10), Rect::new(45, 40, 30, 10));
+        // Too big for the container, so it fills it
+        assert_eq!(anchored(container, Anchor::Centre, 500, 500), container);
+        assert_eq!(anchored(container, Anchor::TopRight, 0, 0), Rect::new(109, 20, 1, 1));
+    }
+
+    #[test]
+    fn inset_never_turns_inside_out() {
+        let rect = Rect::new(0, 0, 100, 40);
+        assert_eq!(inset(rect, 10), Rect::new(10, 10, 80, 20));
+        assert_eq!(inset(rect, 30), Rect::new(30, 20, 40, 1));
+        assert_eq!(inset(rect, u32::MAX), Rect::new(50, 20, 1, 1));
+    }
+
+    #[test]
+    fn cells_share_the_space() {
+        let container = Rect::new(0, 0, 100, 30);
+        let cells = row(container, 4, 4, 100);
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells[0], Rect::new(0, 0, 22, 30));
+        assert_eq!(cells[3], Rect::new(78, 0, 22, 30));
+        // The maximum width leaves the rest of the container empty
+        assert_eq!(row(container, 4, 4, 10)[3], Rect::new(42, 0, 10, 30));
+        assert_eq!(row(container, 0, 4, 100), vec![container]);
+
+        let cells = column(Rect::new(5, 5, 20, 100), 3, 5, 100);
+        assert_eq!(cells[1], Rect::new(5, 40, 20, 30));
+        assert_eq!(cells[2].bottom(), 105);
+    }
+
+    #[test]
+    fn more_cells_than_pixels() {
+        let container = Rect::new(0, 0, 10, 10);
+        let cells = row(container, 50, 2, 100);
+        assert_eq!(cells.len(), 50);
+        assert!(cells.iter().all(|cell| cell.width() == 1));
+        assert!(cells.windows(2).all(|pair| pair[1].x() > pair[0].x()));
+
+        // Gaps too large to add up in a u32
+        let cells = column(container, 1000, u32::MAX / 2, 100);
+        assert_eq!(cells.len(), 1000);
+        assert_eq!(cells[0].height(), 1);
+    }
+}
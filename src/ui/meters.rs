@@ -0,0 +1,94 @@
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{Canvas, RenderTarget};
+use crate::structs::level_meter::{to_db, LevelMeters, METER_FLOOR_DB};
+use crate::structs::visualisation_state::VisualisationState;
+
+const PANEL_GAP: i32 = 10;
+const METER_GAP: i32 = 4;
+const HOLD_THICKNESS: u32 = 2;
+const SCALE_MARKS_DB: [f32; 7] = [0.0, -6.0, -12.0, -18.0, -24.0, -36.0, -48.0];
+const CLIP_COLOUR: Color = Color::RGB(220, 50, 30);
+
+// Where the bars, the meters and the loudness readouts go when the panel is shown
+pub struct MeterPanel {
+    pub bars: Rect,
+    pub meters: Rect,
+    // Left for the caller to write the loudness figures in
+    pub readouts: Rect,
+}
+
+pub fn meter_panel_layout(area: Rect) -> MeterPanel {
+    let width = (area.width() / 6).clamp(80, 200).min(area.width() / 3);
+    let bars_width = (area.width() as i32 - width as i32 - PANEL_GAP).max(1) as u32;
+    let readouts_height = area.height() / 4;
+    let panel_x = area.right() - width as i32;
+    MeterPanel {
+        bars: Rect::new(area.x(), area.y(), bars_width, area.height()),
+        meters: Rect::new(panel_x, area.y(), width, (area.height() - readouts_height).max(1)),
+        readouts: Rect::new(panel_x, area.bottom() - readouts_height as i32, width, readouts_height.max(1)),
+    }
+}
+
+// One column per channel plus one for momentary loudness, on a dB scale down
+// to METER_FLOOR_DB. Each channel shows RMS solid over a dimmer peak, a peak
+// hold line and a red cap while the true peak is above full scale.
+pub fn draw_meters<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    area: Rect,
+    meters: &LevelMeters,
+    state: &VisualisationState,
+) -> Result<(), String> {
+    let columns = meters.channels.len() as i32 + 1;
+    let column_width = ((area.width() as i32 - METER_GAP * (columns - 1)) / columns).max(1);
+    let clip_height = (column_width / 2).max(2);
+    let scale_top = area.y() + clip_height + METER_GAP;
+    let scale_height = (area.bottom() - scale_top).max(1);
+    let height_of = |db: f32| ((db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0) * scale_height as f32;
+    let colour = state.get_selected_color();
+    let dim = Color::RGB(colour.r / 3, colour.g / 3, colour.b / 3);
+
+    let column_rect = |index: i32, height: f32| {
+        let height = height.round() as i32;
+        Rect::new(
+            area.x() + index * (column_width + METER_GAP),
+            area.bottom() - height,
+            column_width as u32,
+            height.max(1) as u32,
+        )
+    };
+
+    for (index, meter) in meters.channels.iter().enumerate() {
+        let index = index as i32;
+        canvas.set_draw_color(Color::RGB(20, 20, 20));
+        canvas.fill_rect(column_rect(index, scale_height as f32))?;
+        canvas.set_draw_color(dim);
+        canvas.fill_rect(column_rect(index, height_of(to_db(meter.peak))))?;
+        canvas.set_draw_color(colour);
+        canvas.fill_rect(column_rect(index, height_of(to_db(meter.rms))))?;
+
+        let hold = column_rect(index, height_of(to_db(meter.peak_hold)));
+        canvas.set_draw_color(Color::RGB(230, 230, 230));
+        canvas.fill_rect(Rect::new(hold.x(), hold.y(), hold.width(), HOLD_THICKNESS))?;
+
+        let clipping = meter.true_peak > 1.0;
+        canvas.set_draw_color(if clipping { CLIP_COLOUR } else { Color::RGB(20, 20, 20) });
+        canvas.fill_rect(Rect::new(hold.x(), area.y(), column_width as u32, clip_height as u32))?;
+    }
+
+    // Momentary loudness, read against the same scale as LUFS
+    let loudness_index = columns - 1;
+    canvas.set_draw_color(Color::RGB(20, 20, 20));
+    canvas.fill_rect(column_rect(loudness_index, scale_height as f32))?;
+    if let Some(momentary) = meters.loudness.momentary {
+        canvas.set_draw_color(state.get_palette().sample(height_of(momentary) / scale_height as f32));
+        canvas.fill_rect(column_rect(loudness_index, height_of(momentary)))?;
+    }
+
+    canvas.set_draw_color(Color::RGB(120, 120, 120));
+    for db in SCALE_MARKS_DB {
+        let y = area.bottom() - height_of(db).round() as i32;
+        canvas.draw_line(Point::new(area.x() - METER_GAP, y), Point::new(area.x() - 1, y))?;
+    }
+    Ok(())
+}
@@ -1,2 +1,17 @@
 pub mod visualisation;
 pub mod ui;
+pub mod oscilloscope;
+pub mod goniometer;
+pub mod spectrogram;
+pub mod geometry;
+pub mod radial;
+pub mod particles;
+pub mod help;
+pub mod layout;
+pub mod text_cache;
+pub mod fonts;
+pub mod export;
+pub mod screenshot;
+pub mod chromagram;
+pub mod tuner;
+pub mod meters;
@@ -0,0 +1,58 @@
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{Canvas, RenderTarget};
+use crate::structs::oscilloscope::{find_trigger_frame, ScopeChannels};
+use crate::structs::visualisation_state::VisualisationState;
+use crate::structs::waveform_buffer::WaveformBuffer;
+
+pub fn draw_oscilloscope<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    area: Rect,
+    waveform: &WaveformBuffer,
+    sample_rate: u32,
+    state: &VisualisationState,
+) -> Result<(), String> {
+    let window_frames = (sample_rate as u64 * state.get_scope_timebase_ms() as u64 / 1000) as usize;
+    if window_frames < 2 {
+        return Ok(());
+    }
+    let start = find_trigger_frame(waveform, window_frames, state.get_scope_trigger_level());
+    let visible_frames = window_frames.min(waveform.frame_count().saturating_sub(start));
+
+    let lanes = match state.get_scope_channels() {
+        ScopeChannels::Summed => 1,
+        ScopeChannels::PerChannel => waveform.channels(),
+    };
+    let lane_height = area.height() as i32 / lanes as i32;
+
+    for lane in 0..lanes {
+        let lane_top = area.y() + lane as i32 * lane_height;
+        let centre_y = lane_top + lane_height / 2;
+
+        canvas.set_draw_color(Color::RGB(40, 40, 40));
+        canvas.draw_line(Point::new(area.x(), centre_y), Point::new(area.right(), centre_y))?;
+
+        if visible_frames < 2 {
+            continue;
+        }
+
+        // One point per pixel column keeps the cost independent of the timebase
+        let points: Vec<Point> = (0..area.width() as i32)
+            .map(|x| {
+                let frame = start + (x as usize * (window_frames - 1)) / area.width() as usize;
+                let frame = frame.min(start + visible_frames - 1);
+                let sample = match state.get_scope_channels() {
+                    ScopeChannels::Summed => waveform.summed(frame),
+                    ScopeChannels::PerChannel => waveform.sample(frame, lane),
+                };
+                let offset = (sample.clamp(-1.0, 1.0) * (lane_height / 2) as f32) as i32;
+                Point::new(area.x() + x, centre_y - offset)
+            })
+            .collect();
+
+        canvas.set_draw_color(state.get_selected_color());
+        canvas.draw_lines(points.as_slice())?;
+    }
+
+    Ok(())
+}
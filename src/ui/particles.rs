@@ -0,0 +1,55 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, RenderTarget};
+use sdl2::sys::SDL_Vertex;
+use crate::structs::particle_system::ParticleSystem;
+use crate::structs::visualisation_state::VisualisationState;
+use crate::ui::geometry::{render_geometry, vertex};
+
+pub fn draw_particles<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    area: Rect,
+    particle_system: &ParticleSystem,
+    state: &VisualisationState,
+) -> Result<(), String> {
+    let palette = state.get_palette();
+    let live = particle_system.live_count();
+    let mut vertices: Vec<SDL_Vertex> = Vec::with_capacity(live * 4);
+    let mut indices: Vec<i32> = Vec::with_capacity(live * 6);
+
+    for particle in particle_system.particles.iter().filter(|particle| particle.alive) {
+        let x = area.x() as f32 + particle.x * area.width() as f32;
+        let y = area.y() as f32 + particle.y * area.height() as f32;
+        if !area.contains_point((x as i32, y as i32)) {
+            continue;
+        }
+
+        // Colour over life: white-hot at birth, the palette colour for the
+        // music's brightness mid-life, then fading out
+        let colour = palette.sample(particle.tint);
+        let life = particle.life_fraction();
+        let heat = (1.0 - life * 3.0).max(0.0);
+        let particle_colour = Color::RGBA(
+            (colour.r as f32 + (255.0 - colour.r as f32) * heat) as u8,
+            (colour.g as f32 + (255.0 - colour.g as f32) * heat) as u8,
+            (colour.b as f32 + (255.0 - colour.b as f32) * heat) as u8,
+            ((1.0 - life) * 255.0) as u8,
+        );
+        let half = particle.size * (1.0 - life * 0.5) / 2.0;
+
+        let base = vertices.len() as i32;
+        vertices.push(vertex(x - half, y - half, particle_colour));
+        vertices.push(vertex(x + half, y - half, particle_colour));
+        vertices.push(vertex(x - half, y + half, particle_colour));
+        vertices.push(vertex(x + half, y + half, particle_colour));
+        indices.extend_from_slice(&[base, base + 1, base + 2, base + 1, base + 3, base + 2]);
+    }
+
+    if indices.is_empty() {
+        return Ok(());
+    }
+    canvas.set_blend_mode(BlendMode::Add);
+    let result = render_geometry(canvas, &vertices, &indices);
+    canvas.set_blend_mode(BlendMode::None);
+    result
+}
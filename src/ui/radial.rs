@@ -0,0 +1,130 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, RenderTarget};
+use sdl2::sys::SDL_Vertex;
+use crate::structs::radial::RadialState;
+use crate::structs::visualisation_state::VisualisationState;
+use crate::structs::waveform_buffer::WaveformBuffer;
+use crate::ui::geometry::{render_geometry, vertex};
+
+const INNER_RADIUS_FRACTION: f32 = 0.35;
+// Fraction of each segment left empty between neighbouring bars
+const SEGMENT_GAP: f32 = 0.15;
+// Width of the transparent fringe that softens the outer edge of each bar
+const EDGE_FEATHER: f32 = 1.5;
+const RING_POINTS: usize = 256;
+const RING_THICKNESS: f32 = 1.5;
+
+pub fn draw_radial<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    area: Rect,
+    frequencies: &[f32],
+    waveform: &WaveformBuffer,
+    radial: &RadialState,
+    state: &VisualisationState,
+) -> Result<(), String> {
+    let centre = area.center();
+    let (centre_x, centre_y) = (centre.x() as f32, centre.y() as f32);
+    let max_radius = area.height() as f32 / 2.0 - EDGE_FEATHER;
+    let inner_radius = max_radius * INNER_RADIUS_FRACTION;
+    let colour = state.get_selected_color();
+    let inner_colour = scale_colour(colour, 0.4, 255);
+    let feather_colour = scale_colour(colour, 1.0, 0);
+
+    // Mirroring runs the bands up one side and back down the other so the
+    // shape is symmetric about the starting angle
+    let band_count = frequencies.len();
+    let segment_count = if state.get_radial_mirror() { band_count * 2 } else { band_count };
+    if segment_count == 0 {
+        return Ok(());
+    }
+    let segment_angle = std::f32::consts::TAU / segment_count as f32;
+    let half_width = segment_angle * (1.0 - SEGMENT_GAP) / 2.0;
+
+    let mut vertices: Vec<SDL_Vertex> = Vec::with_capacity(segment_count * 6);
+    let mut indices: Vec<i32> = Vec::with_capacity(segment_count * 12);
+    let point = |angle: f32, radius: f32| (centre_x + angle.cos() * radius, centre_y + angle.sin() * radius);
+
+    for segment in 0..segment_count {
+        let band = if segment < band_count { segment } else { segment_count - 1 - segment };
+        let amplitude = frequencies[band].clamp(0.0, 1.0);
+        let outer_radius = inner_radius + amplitude * (max_radius - inner_radius);
+        let angle = radial.rotation + segment as f32 * segment_angle;
+        let (start, end) = (angle - half_width, angle + half_width);
+
+        let base = vertices.len() as i32;
+        for &(radius, colour) in &[
+            (inner_radius, inner_colour),
+            (outer_radius, colour),
+            (outer_radius + EDGE_FEATHER, feather_colour),
+        ] {
+            let (x0, y0) = point(start, radius);
+            let (x1, y1) = point(end, radius);
+            vertices.push(vertex(x0, y0, colour));
+            vertices.push(vertex(x1, y1, colour));
+        }
+        // Body quad then feather quad, two triangles each
+        for ring in 0..2 {
+            let (a, b, c, d) = (base + ring * 2, base + ring * 2 + 1, base + ring * 2 + 2, base + ring * 2 + 3);
+            indices.extend_from_slice(&[a, b, c, b, d, c]);
+        }
+    }
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    let result = render_geometry(canvas, &vertices, &indices).and_then(|_| {
+        if state.get_radial_inner_ring() {
+            draw_waveform_ring(canvas, centre_x, centre_y, inner_radius * 0.7, waveform, radial, colour)
+        } else {
+            Ok(())
+        }
+    });
+    canvas.set_blend_mode(BlendMode::None);
+    result
+}
+
+// Closed loop around the centre whose radius follows the latest samples
+fn draw_waveform_ring<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    centre_x: f32,
+    centre_y: f32,
+    radius: f32,
+    waveform: &WaveformBuffer,
+    radial: &RadialState,
+    colour: Color,
+) -> Result<(), String> {
+    let frame_count = waveform.frame_count();
+    let first_frame = frame_count.saturating_sub(RING_POINTS);
+    let swing = radius * 0.35;
+
+    let mut vertices: Vec<SDL_Vertex> = Vec::with_capacity(RING_POINTS * 2);
+    for index in 0..RING_POINTS {
+        let sample = if first_frame + index < frame_count {
+            waveform.summed(first_frame + index).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        let angle = radial.rotation + index as f32 / RING_POINTS as f32 * std::f32::consts::TAU;
+        let ring_radius = radius + sample * swing;
+        for offset in [-RING_THICKNESS, RING_THICKNESS] {
+            let r = ring_radius + offset;
+            vertices.push(vertex(centre_x + angle.cos() * r, centre_y + angle.sin() * r, colour));
+        }
+    }
+
+    let mut indices: Vec<i32> = Vec::with_capacity(RING_POINTS * 6);
+    for index in 0..RING_POINTS as i32 {
+        let next = (index + 1) % RING_POINTS as i32;
+        let (a, b, c, d) = (index * 2, index * 2 + 1, next * 2, next * 2 + 1);
+        indices.extend_from_slice(&[a, b, c, b, d, c]);
+    }
+    render_geometry(canvas, &vertices, &indices)
+}
+
+fn scale_colour(colour: Color, brightness: f32, alpha: u8) -> Color {
+    Color::RGBA(
+        (colour.r as f32 * brightness) as u8,
+        (colour.g as f32 * brightness) as u8,
+        (colour.b as f32 * brightness) as u8,
+        alpha,
+    )
+}
@@ -0,0 +1,146 @@
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Writes tightly packed RGB24 pixels as a PNG, with `text` stored as
+// (keyword, value) chunks that image viewers show as metadata. The image is
+// encoded in memory first so a failure never leaves a partial file behind.
+pub fn write_png(
+    path: &Path,
+    pixels: &[u8],
+    size: (u32, u32),
+    text: &[(&str, String)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(path, encode_png(pixels, size, text)?)?;
+    Ok(())
+}
+
+fn encode_png(pixels: &[u8], size: (u32, u32), text: &[(&str, String)]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, size.0, size.1);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (keyword, value) in text {
+        // tEXt only holds Latin-1, so titles in other scripts go in a UTF-8 iTXt chunk
+        if value.chars().all(|c| c <= '\u{ff}') {
+            encoder.add_text_chunk(keyword.to_string(), value.clone())?;
+        } else {
+            encoder.add_itxt_chunk(keyword.to_string(), value.clone())?;
+        }
+    }
+    encoder.write_header()?.write_image_data(pixels)?;
+    Ok(bytes)
+}
+
+// Reads back `area` (or the whole target) and saves it to a new timestamped
+// file in `directory`, returning the file's path
+pub fn save_screenshot<T: RenderTarget>(
+    canvas: &Canvas<T>,
+    area: Option<Rect>,
+    directory: &Path,
+    text: &[(&str, String)],
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let size = match area {
+        Some(area) => (area.width(), area.height()),
+        None => canvas.output_size()?,
+    };
+    let pixels = canvas.read_pixels(area, PixelFormatEnum::RGB24)?;
+
+    fs::create_dir_all(directory)?;
+    let stamp = timestamp(SystemTime::now());
+    let mut path = directory.join(format!("screenshot_{}.png", stamp));
+    // Several screenshots within a second get a counter
+    let mut counter = 1;
+    while path.exists() {
+        counter += 1;
+        path = directory.join(format!("screenshot_{}_{}.png", stamp, counter));
+    }
+    write_png(&path, &pixels, size, text)?;
+    Ok(path)
+}
+
+// UTC date and time as YYYY-MM-DD_HH-MM-SS, safe for file names
+pub fn timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time_of_day = seconds % 86400;
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60
+    )
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian (year, month, day), after
+// Howard Hinnant's days_from_civil algorithms
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn text_outside_latin_1_is_kept() {
+        let text = [
+            ("Title", "東京 — Don’t Stop 🎵".to_string()),
+            ("Position", "1:23 / 4:56".to_string()),
+        ];
+        let bytes = encode_png(&[0; 2 * 2 * 3], (2, 2), &text).unwrap();
+
+        let reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let info = reader.info();
+        let title = info.utf8_text.iter().find(|chunk| chunk.keyword == "Title").unwrap();
+        assert_eq!(title.get_text().unwrap(), text[0].1);
+        let position = info.uncompressed_latin1_text.iter().find(|chunk| chunk.keyword == "Position").unwrap();
+        assert_eq!(position.text, text[1].1);
+    }
+
+    #[test]
+    fn timestamps_are_utc_and_file_name_safe() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01_00-00-00");
+        assert_eq!(timestamp(at(1_709_251_199)), "2024-02-29_23-59-59");
+        assert_eq!(timestamp(at(1_709_251_200)), "2024-03-01_00-00-00");
+        // Times before the epoch fall back to it rather than failing
+        assert_eq!(timestamp(UNIX_EPOCH - Duration::from_secs(60)), "1970-01-01_00-00-00");
+    }
+
+    #[test]
+    fn days_map_to_calendar_dates() {
+        let cases = [
+            (0, (1970, 1, 1)),
+            (-1, (1969, 12, 31)),
+            (11_016, (2000, 2, 29)),
+            (11_017, (2000, 3, 1)),
+            (19_782, (2024, 2, 29)),
+            // 2100 is not a leap year
+            (47_540, (2100, 2, 28)),
+            (47_541, (2100, 3, 1)),
+        ];
+        for (days, date) in cases {
+            assert_eq!(civil_from_days(days), date, "day {}", days);
+        }
+    }
+}
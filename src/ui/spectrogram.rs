@@ -0,0 +1,179 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget, Texture, TextureCreator};
+use sdl2::video::WindowContext;
+use crate::structs::colour_map::ColourMap;
+use crate::structs::spectrogram::{Spectrogram, SPECTROGRAM_ROWS};
+use crate::structs::visualisation_state::VisualisationState;
+
+// Owns the streaming texture the spectrogram is drawn into. The texture is a
+// ring buffer: each new analysis frame overwrites a single column and the
+// texture is drawn in two pieces so the newest column is always on the right.
+// `C` is the texture creator's context, so the view can draw to a window or
+// to an off-screen surface
+pub struct SpectrogramView<'a, C = WindowContext> {
+    texture_creator: &'a TextureCreator<C>,
+    texture: Option<Texture<'a>>,
+    history_length: usize,
+    colour_map: ColourMap,
+    theme: Color,
+    uploaded_columns: u64,
+    // `Spectrogram::generation` the texture was drawn from
+    generation: u64,
+}
+
+impl<'a, C> SpectrogramView<'a, C> {
+    pub fn new(texture_creator: &'a TextureCreator<C>) -> Self {
+        Self {
+            texture_creator,
+            texture: None,
+            history_length: 0,
+            colour_map: ColourMap::Viridis,
+            theme: Color::RGB(0, 0, 0),
+            uploaded_columns: 0,
+            generation: 0,
+        }
+    }
+
+    pub fn draw<T: RenderTarget>(
+        &mut self,
+        canvas: &mut Canvas<T>,
+        area: Rect,
+        spectrogram: &Spectrogram,
+        state: &VisualisationState,
+    ) -> Result<(), String> {
+        let history_length = state.get_spectrogram_history_length();
+        let colour_map = state.get_spectrogram_colour_map();
+        let theme = state.get_selected_color();
+
+        // Rebuild from the stored history whenever the look changes or the
+        // spectrogram was reset for a new song
+        let needs_rebuild = self.texture.is_none()
+            || history_length != self.history_length
+            || colour_map != self.colour_map
+            || (colour_map == ColourMap::Theme && theme != self.theme)
+            || spectrogram.generation != self.generation;
+        if needs_rebuild {
+            let mut texture = self
+                .texture_creator
+                .create_texture_streaming(PixelFormatEnum::RGB24, history_length as u32, SPECTROGRAM_ROWS as u32)
+                .map_err(|e| e.to_string())?;
+            let background = vec![0u8; history_length * SPECTROGRAM_ROWS * 3];
+            texture.update(None, &background, history_length * 3).map_err(|e| e.to_string())?;
+
+            self.texture = Some(texture);
+            self.history_length = history_length;
+            self.colour_map = colour_map;
+            self.theme = theme;
+            self.generation = spectrogram.generation;
+            let kept = spectrogram.columns.len().min(history_length) as u64;
+            self.uploaded_columns = spectrogram.total_columns - kept;
+        }
+
+        let texture = match self.texture.as_mut() {
+            Some(texture) => texture,
+            None => return Ok(()),
+        };
+
+        // Columns pushed while another mode was showing may already have
+        // dropped out of the history; skip straight to the oldest one kept
+        let oldest_kept = spectrogram.total_columns - spectrogram.columns.len() as u64;
+        self.uploaded_columns = self.uploaded_columns.max(oldest_kept);
+
+        let mut pixels = vec![0u8; SPECTROGRAM_ROWS * 3];
+        while self.uploaded_columns < spectrogram.total_columns {
+            let age = (spectrogram.total_columns - self.uploaded_columns) as usize;
+            let column = &spectrogram.columns[spectrogram.columns.len() - age];
+            for (row, &value) in column.iter().enumerate() {
+                let colour = colour_map.colour_at(value, theme);
+                let offset = (SPECTROGRAM_ROWS - 1 - row) * 3;
+                pixels[offset..offset + 3].copy_from_slice(&[colour.r, colour.g, colour.b]);
+            }
+            let x = (self.uploaded_columns % history_length as u64) as i32;
+            texture
+                .update(Rect::new(x, 0, 1, SPECTROGRAM_ROWS as u32), &pixels, 3)
+                .map_err(|e| e.to_string())?;
+            self.uploaded_columns += 1;
+        }
+
+        // The column about to be overwritten is the oldest one on screen
+        let write_position = (spectrogram.total_columns % history_length as u64) as u32;
+        let older_width = history_length as u32 - write_position;
+        let split_x = area.x() + (area.width() as u64 * older_width as u64 / history_length as u64) as i32;
+
+        canvas.copy(
+            texture,
+            Rect::new(write_position as i32, 0, older_width, SPECTROGRAM_ROWS as u32),
+            Rect::new(area.x(), area.y(), (split_x - area.x()) as u32, area.height()),
+        )?;
+        if write_position > 0 {
+            canvas.copy(
+                texture,
+                Rect::new(0, 0, write_position, SPECTROGRAM_ROWS as u32),
+                Rect::new(split_x, area.y(), (area.right() - split_x) as u32, area.height()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::surface::Surface;
+
+    #[test]
+    fn catches_up_after_history_overflows_in_another_mode() {
+        let canvas_surface = Surface::new(64, 32, PixelFormatEnum::RGB24).unwrap();
+        let mut canvas = canvas_surface.into_canvas().unwrap();
+        let texture_creator = canvas.texture_creator();
+        let mut view = SpectrogramView::new(&texture_creator);
+        let state = VisualisationState::new();
+        let area = Rect::new(0, 0, 64, 32);
+
+        let mut spectrogram = Spectrogram::new();
+        let spectrum = vec![0.5; 512];
+        spectrogram.push(&spectrum, 44100);
+        view.draw(&mut canvas, area, &spectrogram, &state).unwrap();
+
+        // More columns than the history holds arrive while the view is not drawn
+        for _ in 0..1100 {
+            spectrogram.push(&spectrum, 44100);
+        }
+        view.draw(&mut canvas, area, &spectrogram, &state).unwrap();
+        assert_eq!(view.uploaded_columns, spectrogram.total_columns);
+    }
+
+    #[test]
+    fn rebuilds_after_a_reset_it_did_not_see() {
+        let state = VisualisationState::new();
+        // One canvas pixel per history slot, oldest on the left
+        let history = state.get_spectrogram_history_length();
+        let canvas_surface = Surface::new(history as u32, 8, PixelFormatEnum::RGB24).unwrap();
+        let mut canvas = canvas_surface.into_canvas().unwrap();
+        let texture_creator = canvas.texture_creator();
+        let mut view = SpectrogramView::new(&texture_creator);
+        let area = Rect::new(0, 0, history as u32, 8);
+
+        let mut spectrogram = Spectrogram::new();
+        for _ in 0..100 {
+            spectrogram.push(&vec![0.5; 512], 44100);
+        }
+        view.draw(&mut canvas, area, &spectrogram, &state).unwrap();
+
+        // A new song starts and overtakes the old one while another mode is shown
+        spectrogram.clear();
+        for _ in 0..150 {
+            spectrogram.push(&vec![0.02; 512], 44100);
+        }
+        view.draw(&mut canvas, area, &spectrogram, &state).unwrap();
+
+        // Only the new song's columns are left, and the slots it has not reached are blank
+        let pixels = canvas.read_pixels(Rect::new(0, 4, history as u32, 1), PixelFormatEnum::RGB24).unwrap();
+        let slots: Vec<&[u8]> = pixels.chunks(3).collect();
+        let newest = slots[history - 1];
+        assert_ne!(newest, [0, 0, 0]);
+        assert!(slots[..history - 150].iter().all(|&slot| slot == [0, 0, 0]));
+        assert!(slots[history - 150..].iter().all(|&slot| slot == newest));
+    }
+}
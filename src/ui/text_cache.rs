@@ -0,0 +1,85 @@
+use sdl2::pixels::Color;
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::video::WindowContext;
+use std::collections::HashMap;
+use crate::ui::fonts::TextFont;
+
+// Enough for every label on screen plus a few minutes of position readouts
+pub const DEFAULT_TEXT_CACHE_CAPACITY: usize = 256;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TextKey {
+    text: String,
+    font: String,
+    size: u16,
+    colour: (u8, u8, u8, u8),
+}
+
+struct CachedText<'a> {
+    texture: Texture<'a>,
+    last_used: u64,
+}
+
+// Rendered text textures keyed by (string, font, size, colour). Once full the
+// least recently used texture is dropped to make room.
+pub struct TextCache<'a> {
+    texture_creator: &'a TextureCreator<WindowContext>,
+    entries: HashMap<TextKey, CachedText<'a>>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl<'a> TextCache<'a> {
+    pub fn new(texture_creator: &'a TextureCreator<WindowContext>, capacity: usize) -> Self {
+        TextCache {
+            texture_creator,
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+        }
+    }
+
+    // Empty strings cannot be rendered by SDL_ttf, so callers should skip them
+    pub fn get(&mut self, font: &TextFont, text: &str, colour: Color) -> Result<&mut Texture<'a>, String> {
+        self.clock += 1;
+        let key = TextKey {
+            text: text.to_string(),
+            font: font.name.clone(),
+            size: font.size,
+            colour: colour.rgba(),
+        };
+        if !self.entries.contains_key(&key) {
+            let texture = self.render(font, text, colour)?;
+            if self.entries.len() >= self.capacity {
+                self.evict_oldest();
+            }
+            self.entries.insert(key.clone(), CachedText { texture, last_used: 0 });
+        }
+        let entry = self.entries.get_mut(&key).unwrap();
+        entry.last_used = self.clock;
+        Ok(&mut entry.texture)
+    }
+
+    // Drops every texture, e.g. after the font is reloaded at a new size
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn render(&self, font: &TextFont, text: &str, colour: Color) -> Result<Texture<'a>, String> {
+        let surface = font.render(text, colour)?;
+        self.texture_creator
+            .create_texture_from_surface(&surface)
+            .map_err(|e| e.to_string())
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+}
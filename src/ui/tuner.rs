@@ -0,0 +1,111 @@
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Canvas, RenderTarget};
+use crate::structs::pitch_detector::PitchDetector;
+use crate::structs::visualisation_state::VisualisationState;
+use crate::ui::geometry::{render_geometry, vertex};
+
+const METER_HEIGHT: u32 = 20;
+const METER_GAP: u32 = 10;
+// The scale runs from -50 to +50 cents over this many radians either side of
+// straight up
+const SCALE_ANGLE: f32 = std::f32::consts::PI / 3.0;
+// Readings this close to the note count as in tune
+const IN_TUNE_CENTS: f32 = 5.0;
+const IN_TUNE_COLOUR: Color = Color::RGB(47, 198, 18);
+const NEEDLE_WIDTH: f32 = 6.0;
+const ZONE_SEGMENTS: usize = 8;
+
+// Needle gauge of the cents offset from the nearest note, with a bar under it
+// showing how sure the detector is. The note name is drawn over it by the caller.
+pub fn draw_tuner<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    area: Rect,
+    detector: &PitchDetector,
+    state: &VisualisationState,
+) -> Result<(), String> {
+    let gauge_height = area.height().saturating_sub(METER_HEIGHT + METER_GAP) as f32;
+    let pivot_x = area.center().x() as f32;
+    let pivot_y = area.y() as f32 + gauge_height;
+    let radius = (area.width() as f32 / 2.0 / SCALE_ANGLE.sin()).min(gauge_height) * 0.9;
+    let at = |cents: f32, distance: f32| {
+        let angle = cents.clamp(-50.0, 50.0) / 50.0 * SCALE_ANGLE;
+        (pivot_x + angle.sin() * distance, pivot_y - angle.cos() * distance)
+    };
+
+    // Green band for the in-tune zone
+    let mut vertices = Vec::with_capacity((ZONE_SEGMENTS + 1) * 2);
+    let mut indices = Vec::with_capacity(ZONE_SEGMENTS * 6);
+    for segment in 0..=ZONE_SEGMENTS {
+        let cents = -IN_TUNE_CENTS + 2.0 * IN_TUNE_CENTS * segment as f32 / ZONE_SEGMENTS as f32;
+        let (inner_x, inner_y) = at(cents, radius * 0.85);
+        let (outer_x, outer_y) = at(cents, radius);
+        vertices.push(vertex(inner_x, inner_y, IN_TUNE_COLOUR));
+        vertices.push(vertex(outer_x, outer_y, IN_TUNE_COLOUR));
+        if segment > 0 {
+            let base = (segment as i32 - 1) * 2;
+            indices.extend_from_slice(&[base, base + 1, base + 2, base + 1, base + 3, base + 2]);
+        }
+    }
+    render_geometry(canvas, &vertices, &indices)?;
+
+    // Ticks every 10 cents, longer at the ends and the centre
+    canvas.set_draw_color(Color::RGB(200, 200, 200));
+    for step in -5..=5 {
+        let cents = step as f32 * 10.0;
+        let length = if step % 5 == 0 { 0.2 } else { 0.1 };
+        let (inner_x, inner_y) = at(cents, radius * (1.0 - length));
+        let (outer_x, outer_y) = at(cents, radius);
+        canvas.draw_line(
+            Point::new(inner_x as i32, inner_y as i32),
+            Point::new(outer_x as i32, outer_y as i32),
+        )?;
+    }
+
+    if let Some(pitch) = detector.pitch {
+        let base = if pitch.cents.abs() <= IN_TUNE_CENTS { IN_TUNE_COLOUR } else { state.get_selected_color() };
+        // The needle fades as the detector grows less sure
+        let colour = Color::RGBA(base.r, base.g, base.b, (detector.confidence * 255.0) as u8);
+        let angle = pitch.cents.clamp(-50.0, 50.0) / 50.0 * SCALE_ANGLE;
+        let (across_x, across_y) = (angle.cos() * NEEDLE_WIDTH / 2.0, angle.sin() * NEEDLE_WIDTH / 2.0);
+        let (tip_x, tip_y) = at(pitch.cents, radius * 0.95);
+        let needle = [
+            vertex(pivot_x - across_x, pivot_y - across_y, colour),
+            vertex(pivot_x + across_x, pivot_y + across_y, colour),
+            vertex(tip_x, tip_y, colour),
+        ];
+        canvas.set_blend_mode(BlendMode::Blend);
+        let result = render_geometry(canvas, &needle, &[0, 1, 2]);
+        canvas.set_blend_mode(BlendMode::None);
+        result?;
+    }
+
+    draw_confidence_meter(
+        canvas,
+        Rect::new(
+            area.x() + area.width() as i32 / 4,
+            area.bottom() - METER_HEIGHT as i32,
+            area.width() / 2,
+            METER_HEIGHT,
+        ),
+        detector.confidence,
+        state.get_selected_color(),
+    )
+}
+
+// Bar filling from the left as the detector's confidence rises
+fn draw_confidence_meter<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    meter_rect: Rect,
+    confidence: f32,
+    colour: Color,
+) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(20, 20, 20));
+    canvas.fill_rect(meter_rect)?;
+    let width = (confidence.clamp(0.0, 1.0) * meter_rect.width() as f32) as u32;
+    if width > 0 {
+        canvas.set_draw_color(colour);
+        canvas.fill_rect(Rect::new(meter_rect.x(), meter_rect.y(), width, meter_rect.height()))?;
+    }
+    Ok(())
+}
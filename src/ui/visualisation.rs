@@ -1,51 +1,105 @@
-use rand::prelude::*;
 use sdl2::rect::Rect;
-use crate::structs::colour_slider::ColourSlider;
+use sdl2::render::{Canvas, RenderTarget};
+use crate::structs::app_state::AppState;
+use crate::structs::colour_randomiser::ColourRandomiser;
+use crate::structs::palette::GradientMode;
+use crate::structs::visualisation_mode::VisualisationMode;
 use crate::structs::visualisation_state::VisualisationState;
+use crate::ui::chromagram::draw_chromagram;
+use crate::ui::tuner::draw_tuner;
+use crate::ui::goniometer::draw_goniometer;
+use crate::ui::meters::{draw_meters, meter_panel_layout};
+use crate::ui::oscilloscope::draw_oscilloscope;
+use crate::ui::particles::draw_particles;
+use crate::ui::radial::draw_radial;
+use crate::ui::spectrogram::SpectrogramView;
 
-pub fn draw_visualization(
-    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+// Height in pixels of each band when colouring bars by height
+const HEIGHT_STRIP: i32 = 4;
+
+pub fn draw_visualization<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    area: Rect,
+    app_state: &AppState,
+    state: &VisualisationState,
+    spectrogram_view: &mut SpectrogramView<T::Context>,
+) -> Result<(), String> {
+    match state.get_mode() {
+        VisualisationMode::Bars if app_state.config.visualisation.show_meters => {
+            let panel = meter_panel_layout(area);
+            draw_bars(canvas, panel.bars, &app_state.frequencies, state)?;
+            draw_meters(canvas, panel.meters, &app_state.meters, state)
+        }
+        VisualisationMode::Bars => draw_bars(canvas, area, &app_state.frequencies, state),
+        VisualisationMode::Oscilloscope => {
+            draw_oscilloscope(canvas, area, &app_state.waveform, app_state.sample_rate, state)
+        }
+        VisualisationMode::Vectorscope => draw_goniometer(canvas, area, &app_state.goniometer, state),
+        VisualisationMode::Spectrogram => spectrogram_view.draw(canvas, area, &app_state.spectrogram, state),
+        VisualisationMode::Radial => draw_radial(
+            canvas,
+            area,
+            &app_state.frequencies,
+            &app_state.waveform,
+            &app_state.radial,
+            state,
+        ),
+        VisualisationMode::Particles => draw_particles(canvas, area, &app_state.particle_system, state),
+        VisualisationMode::Chromagram => draw_chromagram(canvas, area, &app_state.chroma, state),
+        VisualisationMode::Tuner => draw_tuner(canvas, area, &app_state.pitch, state),
+    }
+}
+
+fn draw_bars<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    area: Rect,
     frequencies: &[f32],
     state: &VisualisationState,
 ) -> Result<(), String> {
-    let viz_width = 1000;
-    let viz_height = 300;
-    let viz_x = 140;
-    let viz_y = 720 - viz_height - 50;
+    let viz_width = area.width() as i32;
+    let viz_height = area.height() as i32;
+    let viz_x = area.x();
+    let viz_y = area.y();
 
-    let bar_count = 16;
-    let bar_width = (viz_width / bar_count) - 2;
+    let bar_count = frequencies.len().max(1) as i32;
+    let bar_width = ((viz_width / bar_count) - 2).max(1);
     let max_bar_height = viz_height;
 
+    let palette = state.get_palette();
     for (i, &amplitude) in frequencies.iter().enumerate() {
         let bar_height = (amplitude * max_bar_height as f32) as i32;
         let x = viz_x + (i as i32 * (bar_width + 2));
         let y = viz_y + (viz_height - bar_height);
 
-        let bar_rect = Rect::new(x, y, bar_width as u32, bar_height as u32);
-        canvas.set_draw_color(state.get_selected_color());
-        canvas.fill_rect(bar_rect)?;
+        match state.get_gradient_mode() {
+            GradientMode::BarIndex => {
+                let position = i as f32 / (frequencies.len().max(2) - 1) as f32;
+                canvas.set_draw_color(palette.sample(position));
+                canvas.fill_rect(Rect::new(x, y, bar_width as u32, bar_height as u32))?;
+            }
+            GradientMode::Amplitude => {
+                canvas.set_draw_color(palette.sample(amplitude));
+                canvas.fill_rect(Rect::new(x, y, bar_width as u32, bar_height as u32))?;
+            }
+            GradientMode::Height => {
+                // Fill in strips so every bar shares the same vertical gradient
+                let bottom = viz_y + viz_height;
+                let mut strip_top = bottom;
+                while strip_top > y {
+                    let strip_height = (strip_top - y).min(HEIGHT_STRIP);
+                    strip_top -= strip_height;
+                    let position = (bottom - strip_top) as f32 / max_bar_height as f32;
+                    canvas.set_draw_color(palette.sample(position));
+                    canvas.fill_rect(Rect::new(x, strip_top, bar_width as u32, strip_height as u32))?;
+                }
+            }
+        }
     }
 
     Ok(())
 }
 
-pub fn update_visualization_bar_colors(state: &mut VisualisationState, 
-    red_slider: &mut ColourSlider,
-    green_slider: &mut ColourSlider,
-    blue_slider: &mut ColourSlider,
-) -> Result<(), String> {
-    let mut rng = rand::rng();
-    let mut nums: Vec<i32> = (0..255).collect();
-    nums.shuffle(&mut rng);
-
-    state.set_red_slider(nums[0] as u8);
-    state.set_green_slider(nums[1] as u8);
-    state.set_blue_slider(nums[2] as u8);
-
-    red_slider.value = nums[0] as u8;
-    green_slider.value = nums[1] as u8;
-    blue_slider.value = nums[2] as u8;
-
-    Ok(())
+// Starts a smooth change of every palette stop to a new harmonious scheme
+pub fn update_visualization_bar_colors(state: &VisualisationState, randomiser: &mut ColourRandomiser) {
+    randomiser.start(&state.get_palette().stop_colours());
 }